@@ -0,0 +1,131 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::{password_error, EncryptFields};
+use crate::pdf::annotations::{edit_annotations, list_annotations, AnnotationAction, AnnotationFilter};
+use crate::pdf::error::PdfError;
+use crate::pdf::utils::parse_page_ranges;
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
+
+/// Sans `action`, retourne les annotations en JSON ; avec `action=remove`
+/// ou `action=flatten`, retourne le PDF modifié. `subtypes` (liste séparée
+/// par des virgules), `author` (répétable) et `pages` restreignent la sélection.
+pub async fn annotations_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+    let mut action: Option<AnnotationAction> = None;
+    let mut filter = AnnotationFilter::default();
+    let mut pages_input = String::new();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if name == "file" { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        if name == "file" {
+            let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+            if ct != "application/pdf" {
+                return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+            }
+            if data.len() < 5 || &data[..5] != b"%PDF-" {
+                return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+            }
+            pdf_data = Some(data);
+            continue;
+        }
+
+        let Ok(value) = String::from_utf8(data) else {
+            return HttpResponse::BadRequest().body("Encodage invalide.");
+        };
+        match name.as_str() {
+            "password" => password = value,
+            n if EncryptFields::accepts(n) => encrypt.set(n, value),
+            "action" => {
+                action = match value.trim() {
+                    "" | "list" => None,
+                    "remove" => Some(AnnotationAction::Remove),
+                    "flatten" => Some(AnnotationAction::Flatten),
+                    other => {
+                        return HttpResponse::BadRequest()
+                            .body(format!("Action inconnue : '{}' (list, remove ou flatten)", other));
+                    }
+                }
+            }
+            "subtypes" => filter.subtypes.extend(
+                value.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string),
+            ),
+            // Un nom d'auteur peut contenir une virgule : un champ par auteur.
+            "author" if !value.trim().is_empty() => filter.authors.push(value),
+            "pages" => pages_input = value,
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    if !pages_input.trim().is_empty() {
+        match parse_page_ranges(&pages_input) {
+            Ok(p) => filter.pages = Some(p),
+            Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        }
+    }
+
+    let Some(action) = action else {
+        return match list_annotations(&data, &filter, Some(password.as_str())) {
+            Ok(json) => HttpResponse::Ok()
+                .content_type("application/json")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .body(json.to_string()),
+            Err(e) if e.is_password_error() => password_error(&e),
+            Err(e @ PdfError::PageOutOfRange { .. }) => HttpResponse::BadRequest().body(e.to_string()),
+            Err(e) => {
+                log::error!("Erreur de lecture des annotations : {}", e);
+                HttpResponse::UnprocessableEntity()
+                    .body(format!("Erreur lors de la lecture des annotations : {}", e))
+            }
+        };
+    };
+
+    log::info!("Annotations : {:?} ({:?})", action, filter);
+
+    match edit_annotations(&data, action, &filter, Some(password.as_str()), encryption.as_ref()) {
+        Ok((bytes, count)) => {
+            log::info!("{} annotation(s) traitée(s) ({} octets)", count, bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .insert_header(("X-Annotations-Count", count.to_string()))
+                .append_header(("Content-Disposition", "attachment; filename=\"annotations.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e @ (PdfError::PageOutOfRange { .. } | PdfError::WidgetAnnotations)) => {
+            HttpResponse::BadRequest().body(e.to_string())
+        }
+        Err(e) => {
+            log::error!("Erreur de traitement des annotations : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors du traitement des annotations : {}", e))
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::{password_error, EncryptFields};
+use crate::pdf::bookmarks::{bookmarks_from_json, bookmarks_to_json, read_bookmarks, write_bookmarks};
+use crate::pdf::error::PdfError;
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE, MAX_JSON_SIZE};
+
+/// Sans champ `bookmarks`, retourne les signets en JSON ; sinon remplace
+/// les signets du document par l'arbre fourni et retourne le PDF.
+pub async fn bookmarks_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+    let mut bookmarks_input: Option<String> = None;
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = match name.as_str() {
+                "file" => MAX_FILE_SIZE,
+                "bookmarks" => MAX_JSON_SIZE,
+                _ => MAX_FIELD_SIZE,
+            };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => {
+                let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+                if ct != "application/pdf" {
+                    return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+                }
+                if data.len() < 5 || &data[..5] != b"%PDF-" {
+                    return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+                }
+                pdf_data = Some(data);
+            }
+            "bookmarks" => match String::from_utf8(data) {
+                Ok(s) => bookmarks_input = Some(s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    let Some(input) = bookmarks_input else {
+        return match read_bookmarks(&data, Some(password.as_str())) {
+            Ok(bookmarks) => HttpResponse::Ok()
+                .content_type("application/json")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .body(bookmarks_to_json(&bookmarks).to_string()),
+            Err(e) if e.is_password_error() => password_error(&e),
+            Err(e) => {
+                log::error!("Erreur de lecture des signets : {}", e);
+                HttpResponse::UnprocessableEntity()
+                    .body(format!("Erreur lors de la lecture des signets : {}", e))
+            }
+        };
+    };
+
+    let bookmarks = match bookmarks_from_json(&input) {
+        Ok(b) => b,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    log::info!("Remplacement des signets ({} entrée(s) de premier niveau)", bookmarks.len());
+
+    match write_bookmarks(&data, &bookmarks, Some(password.as_str()), encryption.as_ref()) {
+        Ok(bytes) => {
+            log::info!("Signets mis à jour ({} octets)", bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", "attachment; filename=\"bookmarks.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e @ PdfError::PageOutOfRange { .. }) => HttpResponse::BadRequest().body(e.to_string()),
+        Err(e) => {
+            log::error!("Erreur de mise à jour des signets : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors de la mise à jour des signets : {}", e))
+        }
+    }
+}
@@ -2,13 +2,23 @@ use actix_multipart::Multipart;
 use actix_web::HttpResponse;
 use futures_util::TryStreamExt;
 
+use crate::handlers::{parse_flag, password_error, select_by_text, EncryptFields};
 use crate::pdf::delete::delete_pages;
+use crate::pdf::error::PdfError;
+use crate::pdf::search::SelectionInput;
 use crate::pdf::utils::parse_page_ranges;
 use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
 
+/// Supprime les pages de `pages` et/ou celles retenues par leur texte
+/// (`contains`, `no_text`…, voir `SelectionInput`) ; avec les deux, la
+/// sélection textuelle ne porte que sur `pages`. Les pages retenues par le
+/// texte sont listées dans l'en-tête `X-Matched-Pages`.
 pub async fn delete_handler(mut payload: Multipart) -> HttpResponse {
     let mut pdf_data: Option<Vec<u8>> = None;
     let mut pages_input = String::new();
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+    let mut selection = SelectionInput::default();
 
     while let Ok(Some(mut field)) = payload.try_next().await {
         let name = field.name().to_string();
@@ -37,6 +47,49 @@ pub async fn delete_handler(mut payload: Multipart) -> HttpResponse {
                 }
                 pages_input = String::from_utf8_lossy(&data).trim().to_string();
             }
+            "password" => {
+                if data.len() > MAX_FIELD_SIZE {
+                    return HttpResponse::BadRequest()
+                        .body("Paramètre 'password' trop long.");
+                }
+                match String::from_utf8(data) {
+                    Ok(s) => password = s,
+                    Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+                }
+            }
+            n if EncryptFields::accepts(n) => {
+                if data.len() > MAX_FIELD_SIZE {
+                    return HttpResponse::BadRequest()
+                        .body(format!("Paramètre '{}' trop long.", n));
+                }
+                match String::from_utf8(data) {
+                    Ok(s) => encrypt.set(n, s),
+                    Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+                }
+            }
+            "contains" => {
+                if data.len() > MAX_FIELD_SIZE {
+                    return HttpResponse::BadRequest()
+                        .body("Paramètre 'contains' trop long.");
+                }
+                match String::from_utf8(data) {
+                    Ok(s) => selection.query.query = s,
+                    Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+                }
+            }
+            "regex" | "case_sensitive" | "accent_sensitive" | "invert" | "no_text" => {
+                let enabled = match parse_flag(&name, &data) {
+                    Ok(enabled) => enabled,
+                    Err(response) => return response,
+                };
+                match name.as_str() {
+                    "regex" => selection.query.regex = enabled,
+                    "case_sensitive" => selection.query.case_sensitive = enabled,
+                    "accent_sensitive" => selection.query.accent_sensitive = enabled,
+                    "invert" => selection.invert = enabled,
+                    _ => selection.no_text = enabled,
+                }
+            }
             _ => {}
         }
     }
@@ -46,20 +99,48 @@ pub async fn delete_handler(mut payload: Multipart) -> HttpResponse {
         None => return HttpResponse::BadRequest().body("Aucun fichier PDF reçu."),
     };
 
-    if pages_input.is_empty() {
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    let selector = match selection.selector() {
+        Ok(s) => s,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    if pages_input.is_empty() && selector.is_none() {
         return HttpResponse::BadRequest().body("Aucune page spécifiée.");
     }
 
-    let page_numbers = match parse_page_ranges(&pages_input) {
-        Ok(p) => p,
-        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    let page_numbers = if pages_input.is_empty() {
+        None
+    } else {
+        match parse_page_ranges(&pages_input) {
+            Ok(p) => Some(p),
+            Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        }
     };
 
-    match delete_pages(&pdf_data, &page_numbers) {
-        Ok(bytes) => HttpResponse::Ok()
-            .content_type("application/pdf")
-            .append_header(("Content-Disposition", "attachment; filename=\"deleted.pdf\""))
-            .body(bytes),
+    let (page_numbers, matched) =
+        match select_by_text(&pdf_data, page_numbers, selector.as_ref(), &password) {
+            Ok(selected) => selected,
+            Err(response) => return response,
+        };
+
+    match delete_pages(&pdf_data, &page_numbers, Some(password.as_str()), encryption.as_ref()) {
+        Ok(bytes) => {
+            let mut response = HttpResponse::Ok();
+            response
+                .content_type("application/pdf")
+                .append_header(("Content-Disposition", "attachment; filename=\"deleted.pdf\""));
+            if let Some(matched) = matched {
+                response.insert_header(("X-Matched-Pages", matched));
+            }
+            response.body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e @ PdfError::WouldDeleteAll) => HttpResponse::BadRequest().body(e.to_string()),
         Err(e) => HttpResponse::InternalServerError()
             .body(format!("Erreur de suppression : {}", e)),
     }
@@ -0,0 +1,93 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::password_error;
+use crate::pdf::encrypt::{encrypt_pdf, Encryption};
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
+
+pub async fn encrypt_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut user_password = String::new();
+    let mut owner_password = String::new();
+    let mut algorithm_input = String::new();
+    let mut permissions_input = String::new();
+    let mut password = String::new();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if name == "file" { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => {
+                let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+                if ct != "application/pdf" {
+                    return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+                }
+                if data.len() < 5 || &data[..5] != b"%PDF-" {
+                    return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+                }
+                pdf_data = Some(data);
+            }
+            "user_password" | "owner_password" | "algorithm" | "permissions" => {
+                let Ok(s) = String::from_utf8(data) else {
+                    return HttpResponse::BadRequest().body("Encodage invalide.");
+                };
+                // Les mots de passe sont pris tels quels : les espaces comptent.
+                match name.as_str() {
+                    "user_password" => user_password = s,
+                    "owner_password" => owner_password = s,
+                    "algorithm" => algorithm_input = s.trim().to_string(),
+                    _ => permissions_input = s.trim().to_string(),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+
+    let encryption =
+        match Encryption::from_fields(&algorithm_input, user_password, owner_password, &permissions_input) {
+            Ok(e) => e,
+            Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        };
+
+    log::info!("Chiffrement {:?} (permissions {:?})", encryption.algorithm, encryption.permissions);
+
+    match encrypt_pdf(&data, &encryption, Some(password.as_str())) {
+        Ok(bytes) => {
+            log::info!("Chiffrement réussi ({} octets)", bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", "attachment; filename=\"encrypted.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e) => {
+            log::error!("Erreur de chiffrement : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors du chiffrement : {}", e))
+        }
+    }
+}
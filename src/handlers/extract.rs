@@ -2,13 +2,22 @@ use actix_multipart::Multipart;
 use actix_web::HttpResponse;
 use futures_util::TryStreamExt;
 
+use crate::handlers::{parse_flag, password_error, select_by_text, EncryptFields};
 use crate::pdf::extract::extract_pages;
+use crate::pdf::search::SelectionInput;
 use crate::pdf::utils::parse_page_ranges;
 use crate::{MAX_FIELD_SIZE, MAX_FILE_SIZE};
 
+/// Extrait les pages de `pages` et/ou celles retenues par leur texte
+/// (`contains`, `no_text`…, voir `SelectionInput`) ; avec les deux, la
+/// sélection textuelle ne porte que sur `pages`. Les pages retenues par le
+/// texte sont listées dans l'en-tête `X-Matched-Pages`.
 pub async fn extract_handler(mut payload: Multipart) -> HttpResponse {
     let mut pdf_data: Option<Vec<u8>> = None;
     let mut pages_input = String::new();
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+    let mut selection = SelectionInput::default();
 
     while let Ok(Some(mut field)) = payload.try_next().await {
         let name = field.name().to_string();
@@ -30,6 +39,14 @@ pub async fn extract_handler(mut payload: Multipart) -> HttpResponse {
         }
 
         match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
             "file" => {
                 // Vérifie le Content-Type
                 let content_type = field
@@ -66,6 +83,23 @@ pub async fn extract_handler(mut payload: Multipart) -> HttpResponse {
                     }
                 }
             }
+            "contains" => match String::from_utf8(data) {
+                Ok(s) => selection.query.query = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "regex" | "case_sensitive" | "accent_sensitive" | "invert" | "no_text" => {
+                let enabled = match parse_flag(&name, &data) {
+                    Ok(enabled) => enabled,
+                    Err(response) => return response,
+                };
+                match name.as_str() {
+                    "regex" => selection.query.regex = enabled,
+                    "case_sensitive" => selection.query.case_sensitive = enabled,
+                    "accent_sensitive" => selection.query.accent_sensitive = enabled,
+                    "invert" => selection.invert = enabled,
+                    _ => selection.no_text = enabled,
+                }
+            }
             // Ignore tout champ inconnu
             _ => {
                 log::debug!("Champ multipart inconnu ignoré : '{}'", name);
@@ -76,12 +110,33 @@ pub async fn extract_handler(mut payload: Multipart) -> HttpResponse {
     let Some(data) = pdf_data else {
         return HttpResponse::BadRequest().body("Fichier PDF requis.".to_string());
     };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
 
-    let page_numbers: Vec<u32> = match parse_page_ranges(&pages_input) {
-        Ok(p) => p,
+    let selector = match selection.selector() {
+        Ok(s) => s,
         Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
     };
 
+    // Sans sélection textuelle, `pages` est requis (l'erreur de
+    // `parse_page_ranges` le signale).
+    let page_numbers = if pages_input.is_empty() && selector.is_some() {
+        None
+    } else {
+        match parse_page_ranges(&pages_input) {
+            Ok(p) => Some(p),
+            Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        }
+    };
+
+    let (page_numbers, matched) =
+        match select_by_text(&data, page_numbers, selector.as_ref(), &password) {
+            Ok(selected) => selected,
+            Err(response) => return response,
+        };
+
     // Limite le nombre de pages à extraire
     if page_numbers.len() > 500 {
         return HttpResponse::BadRequest()
@@ -96,16 +151,21 @@ pub async fn extract_handler(mut payload: Multipart) -> HttpResponse {
 
     log::info!("Extraction de {} page(s)", page_numbers.len());
 
-    match extract_pages(&data, &page_numbers) {
+    match extract_pages(&data, &page_numbers, Some(password.as_str()), encryption.as_ref()) {
         Ok(bytes) => {
             log::info!("Extraction réussie ({} octets)", bytes.len());
-            HttpResponse::Ok()
+            let mut response = HttpResponse::Ok();
+            response
                 .content_type("application/pdf")
                 .insert_header(("X-Content-Type-Options", "nosniff"))
                 .append_header(("Content-Disposition",
-                    format!("attachment; filename=\"{}\"", filename)))
-                .body(bytes)
+                    format!("attachment; filename=\"{}\"", filename)));
+            if let Some(matched) = matched {
+                response.insert_header(("X-Matched-Pages", matched));
+            }
+            response.body(bytes)
         }
+        Err(e) if e.is_password_error() => password_error(&e),
         Err(e) => {
             log::error!("Erreur d'extraction : {}", e);
             HttpResponse::InternalServerError()
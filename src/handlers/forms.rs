@@ -0,0 +1,433 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+use serde_json::{json, Map, Value};
+
+use crate::handlers::{parse_flag, password_error, EncryptFields};
+use crate::pdf::error::PdfError;
+use crate::pdf::flatten::flatten_form;
+use crate::pdf::form_data::{export_form_data, ExportFormat};
+use crate::pdf::forms::{field_errors_to_json, fill_form, FieldError};
+use crate::pdf::mail_merge::{mail_merge, MailMergeOutput};
+use crate::{MAX_FILE_SIZE, MAX_FILES, MAX_FIELD_SIZE, MAX_JSON_SIZE};
+
+/// Remplit le formulaire avec l'objet JSON du champ `values` (nom complet
+/// du champ → valeur) et retourne le PDF rempli, aplati si `flatten` est vrai.
+pub async fn fill_form_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+    let mut values_input: Option<String> = None;
+    let mut flatten = false;
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = match name.as_str() {
+                "file" => MAX_FILE_SIZE,
+                "values" => MAX_JSON_SIZE,
+                _ => MAX_FIELD_SIZE,
+            };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => {
+                let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+                if ct != "application/pdf" {
+                    return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+                }
+                if data.len() < 5 || &data[..5] != b"%PDF-" {
+                    return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+                }
+                pdf_data = Some(data);
+            }
+            "values" => match String::from_utf8(data) {
+                Ok(s) => values_input = Some(s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "flatten" => match parse_flag(&name, &data) {
+                Ok(enabled) => flatten = enabled,
+                Err(response) => return response,
+            },
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    let Some(input) = values_input else {
+        return HttpResponse::BadRequest().body("Champ 'values' requis (objet JSON).");
+    };
+    let values: Map<String, Value> = match serde_json::from_str(&input) {
+        Ok(Value::Object(values)) => values,
+        Ok(_) => return HttpResponse::BadRequest().body("'values' doit être un objet JSON."),
+        Err(e) => return HttpResponse::BadRequest().body(format!("JSON invalide : {}", e)),
+    };
+
+    log::info!("Remplissage de {} champ(s)", values.len());
+
+    match fill_form(&data, &values, flatten, Some(password.as_str()), encryption.as_ref()) {
+        Ok(bytes) => {
+            log::info!("Formulaire rempli ({} octets)", bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", "attachment; filename=\"filled.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(ref e @ PdfError::InvalidFormData(ref errors)) => field_errors(e, errors),
+        Err(e) => {
+            log::error!("Erreur de remplissage : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors du remplissage du formulaire : {}", e))
+        }
+    }
+}
+
+/// Aplatit le formulaire : les valeurs deviennent du contenu de page non
+/// modifiable. Le champ `fields` (noms séparés par des virgules) limite
+/// l'opération à certains champs.
+pub async fn flatten_form_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+    let mut fields_input = String::new();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = match name.as_str() {
+                "file" => MAX_FILE_SIZE,
+                "fields" => MAX_JSON_SIZE,
+                _ => MAX_FIELD_SIZE,
+            };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => {
+                let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+                if ct != "application/pdf" {
+                    return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+                }
+                if data.len() < 5 || &data[..5] != b"%PDF-" {
+                    return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+                }
+                pdf_data = Some(data);
+            }
+            "fields" => match String::from_utf8(data) {
+                Ok(s) => fields_input = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    // Sans liste, tout le formulaire est aplati.
+    let names: Vec<String> = fields_input
+        .split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+        .collect();
+    let only = (!names.is_empty()).then_some(names.as_slice());
+
+    log::info!("Aplatissement du formulaire ({} champ(s) désigné(s))", names.len());
+
+    match flatten_form(&data, only, Some(password.as_str()), encryption.as_ref()) {
+        Ok(bytes) => {
+            log::info!("Formulaire aplati ({} octets)", bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", "attachment; filename=\"flattened.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(ref e @ PdfError::InvalidFormData(ref errors)) => field_errors(e, errors),
+        Err(e) => {
+            log::error!("Erreur d'aplatissement : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors de l'aplatissement du formulaire : {}", e))
+        }
+    }
+}
+
+/// Exporte les champs d'un ou plusieurs formulaires (champ `file` répété)
+/// au format choisi par `format` : json (défaut), csv, fdf ou xfdf.
+pub async fn export_form_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Vec<Vec<u8>> = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    let mut password = String::new();
+    let mut format = ExportFormat::Json;
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        // Champs texte : mot de passe des fichiers chiffrés, format d'export
+        let name = field.name().to_string();
+        if name == "password" || name == "format" {
+            let mut data: Vec<u8> = Vec::new();
+            while let Ok(Some(chunk)) = field.try_next().await {
+                data.extend_from_slice(&chunk);
+                if data.len() > MAX_FIELD_SIZE {
+                    return HttpResponse::PayloadTooLarge().body(format!("Champ '{}' trop long.", name));
+                }
+            }
+            let Ok(value) = String::from_utf8(data) else {
+                return HttpResponse::BadRequest().body("Encodage invalide.");
+            };
+            if name == "password" {
+                password = value;
+                continue;
+            }
+            format = match ExportFormat::from_name(&value) {
+                Some(format) => format,
+                None => {
+                    return HttpResponse::BadRequest()
+                        .body(format!("Format inconnu : '{}' (json, csv, fdf ou xfdf)", value.trim()));
+                }
+            };
+            continue;
+        }
+
+        let file_name = field
+            .content_disposition()
+            .get_filename()
+            .map(str::to_string)
+            .unwrap_or_default();
+
+        let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+        if ct != "application/pdf" {
+            return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+        }
+
+        if pdf_data.len() >= MAX_FILES {
+            return HttpResponse::BadRequest()
+                .body(format!("Maximum {} fichiers par requête.", MAX_FILES));
+        }
+
+        let mut data: Vec<u8> = Vec::new();
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            if data.len() > MAX_FILE_SIZE {
+                return HttpResponse::PayloadTooLarge().body("Fichier trop volumineux (max 1 Go).");
+            }
+        }
+
+        if data.len() < 5 || &data[..5] != b"%PDF-" {
+            return HttpResponse::BadRequest().body("Un fichier ne semble pas être un PDF valide.");
+        }
+
+        pdf_data.push(data);
+        names.push(file_name);
+    }
+
+    if pdf_data.is_empty() {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    }
+    if format.single_document() && pdf_data.len() > 1 {
+        return HttpResponse::BadRequest()
+            .body("Les formats fdf et xfdf n'acceptent qu'un fichier : utilisez json ou csv.");
+    }
+
+    log::info!("Export des champs de {} formulaire(s) en {}", pdf_data.len(), format.extension());
+
+    match export_form_data(&pdf_data, &names, format, Some(password.as_str())) {
+        Ok(bytes) => {
+            let mut response = HttpResponse::Ok();
+            response
+                .content_type(format.content_type())
+                .insert_header(("X-Content-Type-Options", "nosniff"));
+            // Le JSON est lu par les scripts, les autres formats enregistrés.
+            if format != ExportFormat::Json {
+                response.append_header((
+                    "Content-Disposition",
+                    format!("attachment; filename=\"form-data.{}\"", format.extension()),
+                ));
+            }
+            response.body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e) => {
+            log::error!("Erreur d'export du formulaire : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Impossible de traiter un des fichiers : {}", e))
+        }
+    }
+}
+
+/// Publipostage : remplit le formulaire `file` pour chaque ligne du champ
+/// `csv` et retourne un PDF unique (`output=pdf`, défaut) ou une archive
+/// ZIP (`output=zip`) dont les fichiers sont nommés d'après `names`.
+pub async fn mail_merge_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+    let mut csv: Option<String> = None;
+    let mut flatten = false;
+    let mut zip = false;
+    let mut names: Option<String> = None;
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = match name.as_str() {
+                "file" => MAX_FILE_SIZE,
+                "csv" => MAX_JSON_SIZE,
+                _ => MAX_FIELD_SIZE,
+            };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => {
+                let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+                if ct != "application/pdf" {
+                    return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+                }
+                if data.len() < 5 || &data[..5] != b"%PDF-" {
+                    return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+                }
+                pdf_data = Some(data);
+            }
+            // Texte ou fichier joint : seul le contenu compte.
+            "csv" => match String::from_utf8(data) {
+                Ok(s) => csv = Some(s),
+                Err(_) => return HttpResponse::BadRequest().body("Le CSV doit être encodé en UTF-8."),
+            },
+            "names" => match String::from_utf8(data) {
+                Ok(s) => names = Some(s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "flatten" => match parse_flag(&name, &data) {
+                Ok(enabled) => flatten = enabled,
+                Err(response) => return response,
+            },
+            "output" => {
+                zip = match std::str::from_utf8(&data).map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+                    Ok("" | "pdf") => false,
+                    Ok("zip") => true,
+                    _ => return HttpResponse::BadRequest().body("Sortie inconnue (pdf ou zip)."),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    let Some(csv) = csv else {
+        return HttpResponse::BadRequest().body("Champ 'csv' requis.");
+    };
+
+    let output = if zip { MailMergeOutput::Zip { names } } else { MailMergeOutput::Pdf };
+    log::info!("Publipostage ({:?}, aplatissement : {})", output, flatten);
+
+    match mail_merge(&data, &csv, flatten, &output, Some(password.as_str()), encryption.as_ref()) {
+        Ok(bytes) => {
+            log::info!("Publipostage réussi ({} octets)", bytes.len());
+            let (content_type, file_name) =
+                if zip { ("application/zip", "mail-merge.zip") } else { ("application/pdf", "mail-merge.pdf") };
+            HttpResponse::Ok()
+                .content_type(content_type)
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", format!("attachment; filename=\"{}\"", file_name)))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(ref e @ PdfError::InvalidRecord { line, ref errors }) => HttpResponse::BadRequest()
+            .content_type("application/json")
+            .insert_header(("X-Content-Type-Options", "nosniff"))
+            .body(json!({ "error": e.to_string(), "line": line, "fields": field_errors_to_json(errors) }).to_string()),
+        Err(e @ (PdfError::InvalidCsv(_) | PdfError::TooManyRecords(_) | PdfError::InvalidNameTemplate(_))) => {
+            HttpResponse::BadRequest().body(e.to_string())
+        }
+        Err(e) => {
+            log::error!("Erreur de publipostage : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors du publipostage : {}", e))
+        }
+    }
+}
+
+/// Erreurs par champ, en JSON pour être exploitables par les scripts appelants.
+fn field_errors(e: &PdfError, errors: &[FieldError]) -> HttpResponse {
+    HttpResponse::BadRequest()
+        .content_type("application/json")
+        .insert_header(("X-Content-Type-Options", "nosniff"))
+        .body(json!({ "error": e.to_string(), "fields": field_errors_to_json(errors) }).to_string())
+}
@@ -0,0 +1,122 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::{password_error, EncryptFields};
+use crate::pdf::insert::insert_pages;
+use crate::pdf::utils::parse_page_ranges;
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
+
+pub async fn insert_handler(mut payload: Multipart) -> HttpResponse {
+    let mut target_data: Option<Vec<u8>> = None;
+    let mut source_data: Option<Vec<u8>> = None;
+    let mut after_input = String::new();
+    let mut pages_input = String::new();
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let is_file = name == "file" || name == "source";
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if is_file { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if is_file {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        if is_file {
+            let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+            if ct != "application/pdf" {
+                return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+            }
+            if data.len() < 5 || &data[..5] != b"%PDF-" {
+                return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => target_data = Some(data),
+            "source" => source_data = Some(data),
+            "after" | "pages" => {
+                let Ok(s) = std::str::from_utf8(&data) else {
+                    return HttpResponse::BadRequest().body("Encodage invalide.");
+                };
+                if name == "after" {
+                    after_input = s.trim().to_string();
+                } else {
+                    pages_input = s.trim().to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(target) = target_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let Some(source) = source_data else {
+        return HttpResponse::BadRequest().body("PDF à insérer requis (champ 'source').");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    let after: u32 = match after_input.parse() {
+        Ok(n) => n,
+        Err(_) => return HttpResponse::BadRequest()
+            .body(format!("Position invalide : '{}' (0 = avant la page 1)", after_input)),
+    };
+
+    let pages = if pages_input.is_empty() {
+        None
+    } else {
+        match parse_page_ranges(&pages_input) {
+            Ok(p) => Some(p),
+            Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        }
+    };
+
+    log::info!("Insertion après la page {}", after);
+
+    match insert_pages(
+        &target,
+        &source,
+        after,
+        pages.as_deref(),
+        Some(password.as_str()),
+        encryption.as_ref(),
+    ) {
+        Ok(bytes) => {
+            log::info!("Insertion réussie ({} octets)", bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", "attachment; filename=\"inserted.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e) => {
+            log::error!("Erreur d'insertion : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors de l'insertion : {}", e))
+        }
+    }
+}
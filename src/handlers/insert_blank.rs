@@ -0,0 +1,123 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::{password_error, EncryptFields};
+use crate::pdf::insert_blank::{insert_blank_pages, BlankSize};
+use crate::pdf::utils::parse_page_ranges;
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
+
+pub async fn insert_blank_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut after_input = String::new();
+    let mut pad_input = String::new();
+    let mut size_input = String::new();
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if name == "file" { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => {
+                let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+                if ct != "application/pdf" {
+                    return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+                }
+                if data.len() < 5 || &data[..5] != b"%PDF-" {
+                    return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+                }
+                pdf_data = Some(data);
+            }
+            "after" | "pad" | "size" => {
+                let Ok(s) = std::str::from_utf8(&data) else {
+                    return HttpResponse::BadRequest().body("Encodage invalide.");
+                };
+                let value = s.trim().to_string();
+                match name.as_str() {
+                    "after" => after_input = value,
+                    "pad" => pad_input = value,
+                    _ => size_input = value,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    if after_input.is_empty() && pad_input.is_empty() {
+        return HttpResponse::BadRequest()
+            .body("Indiquez des positions ('after') et/ou un multiple ('pad').");
+    }
+
+    let after = if after_input.is_empty() {
+        Vec::new()
+    } else {
+        match parse_page_ranges(&after_input) {
+            Ok(p) => p,
+            Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        }
+    };
+
+    let pad_to = match pad_input.as_str() {
+        "" => None,
+        p => match p.parse::<u32>() {
+            Ok(n) if (1..=64).contains(&n) => Some(n),
+            _ => return HttpResponse::BadRequest()
+                .body(format!("Multiple invalide : '{}' (entre 1 et 64)", p)),
+        },
+    };
+
+    let Some(size) = BlankSize::from_name(&size_input) else {
+        return HttpResponse::BadRequest()
+            .body(format!("Format invalide : '{}' (auto, a3, a4, a5, letter, legal)", size_input));
+    };
+
+    log::info!("Insertion de pages vierges ({} position(s), multiple {:?})", after.len(), pad_to);
+
+    match insert_blank_pages(&data, &after, pad_to, size, Some(password.as_str()), encryption.as_ref()) {
+        Ok(bytes) => {
+            log::info!("Insertion réussie ({} octets)", bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", "attachment; filename=\"blank_inserted.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e) => {
+            log::error!("Erreur d'insertion : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors de l'insertion : {}", e))
+        }
+    }
+}
@@ -1,13 +1,59 @@
 use actix_multipart::Multipart;
 use actix_web::HttpResponse;
 use futures_util::TryStreamExt;
-use crate::pdf::merge::merge_documents;
-use crate::{MAX_FILES, MAX_FILE_SIZE};
+use crate::handlers::{password_error, EncryptFields};
+use crate::pdf::merge::{merge_documents, TocMode};
+use crate::{MAX_FIELD_SIZE, MAX_FILES, MAX_FILE_SIZE};
 
 pub async fn merge_handler(mut payload: Multipart) -> HttpResponse {
     let mut pdf_data: Vec<Vec<u8>> = Vec::new();
+    let mut titles: Vec<String> = Vec::new();
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+    let mut toc: Option<TocMode> = None;
 
     while let Ok(Some(mut field)) = payload.try_next().await {
+        // Champs texte : mot de passe des fichiers chiffrés, table des matières,
+        // chiffrement de la sortie
+        let name = field.name().to_string();
+        if name == "password" || name == "toc" || EncryptFields::accepts(&name) {
+            let mut data: Vec<u8> = Vec::new();
+            while let Ok(Some(chunk)) = field.try_next().await {
+                data.extend_from_slice(&chunk);
+                if data.len() > MAX_FIELD_SIZE {
+                    return HttpResponse::PayloadTooLarge().body(format!("Champ '{}' trop long.", name));
+                }
+            }
+            let Ok(value) = String::from_utf8(data) else {
+                return HttpResponse::BadRequest().body("Encodage invalide.");
+            };
+            if name == "password" {
+                password = value;
+                continue;
+            }
+            if EncryptFields::accepts(&name) {
+                encrypt.set(&name, value);
+                continue;
+            }
+            toc = match value.trim() {
+                "" | "none" => None,
+                "files" => Some(TocMode::Files),
+                "bookmarks" => Some(TocMode::Bookmarks),
+                other => {
+                    return HttpResponse::BadRequest()
+                        .body(format!("Table des matières inconnue : '{}' (files, bookmarks ou none)", other));
+                }
+            };
+            continue;
+        }
+
+        // Titre de l'entrée dans la table des matières : nom du fichier, sans extension
+        let title = field
+            .content_disposition()
+            .get_filename()
+            .map(|f| f.strip_suffix(".pdf").or_else(|| f.strip_suffix(".PDF")).unwrap_or(f).to_string())
+            .unwrap_or_default();
+
         // Vérifie le Content-Type du champ
         let content_type = field
             .content_type()
@@ -44,6 +90,7 @@ pub async fn merge_handler(mut payload: Multipart) -> HttpResponse {
 
         if !data.is_empty() {
             pdf_data.push(data);
+            titles.push(title);
         }
     }
 
@@ -52,9 +99,14 @@ pub async fn merge_handler(mut payload: Multipart) -> HttpResponse {
             .body("Au moins 2 fichiers PDF sont requis.");
     }
 
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
     log::info!("Fusion de {} fichiers PDF", pdf_data.len());
 
-    match merge_documents(pdf_data) {
+    match merge_documents(pdf_data, &titles, toc, Some(password.as_str()), encryption.as_ref()) {
         Ok(bytes) => {
             log::info!("Fusion réussie ({} octets)", bytes.len());
             HttpResponse::Ok()
@@ -63,6 +115,7 @@ pub async fn merge_handler(mut payload: Multipart) -> HttpResponse {
                 .append_header(("Content-Disposition", "attachment; filename=\"merged.pdf\""))
                 .body(bytes)
         }
+        Err(e) if e.is_password_error() => password_error(&e),
         Err(e) => {
             log::error!("Erreur de fusion : {}", e);
             HttpResponse::UnprocessableEntity()
@@ -0,0 +1,117 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+use std::collections::BTreeMap;
+
+use crate::handlers::{password_error, EncryptFields};
+use crate::pdf::error::PdfError;
+use crate::pdf::metadata::{read_metadata, write_metadata, STANDARD_KEYS};
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
+
+/// Sans champ de métadonnée, retourne les métadonnées en JSON ; sinon
+/// retourne le PDF mis à jour.
+pub async fn metadata_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+    let mut changes: BTreeMap<String, String> = BTreeMap::new();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if name == "file" { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        if name == "file" {
+            let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+            if ct != "application/pdf" {
+                return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+            }
+            if data.len() < 5 || &data[..5] != b"%PDF-" {
+                return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+            }
+            pdf_data = Some(data);
+            continue;
+        }
+
+        let Ok(value) = String::from_utf8(data) else {
+            return HttpResponse::BadRequest().body("Encodage invalide.");
+        };
+
+        // `title` → Title ; `custom.Service` → Service
+        let key = match name.strip_prefix("custom.") {
+            Some(custom) => Some(custom.to_string()),
+            None => STANDARD_KEYS
+                .iter()
+                .find(|k| k.eq_ignore_ascii_case(&name))
+                .map(|k| k.to_string()),
+        };
+        match key {
+            Some(key) => {
+                changes.insert(key, value.trim().to_string());
+            }
+            None if name == "password" => password = value,
+            None if EncryptFields::accepts(&name) => encrypt.set(&name, value),
+            None => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    if changes.is_empty() {
+        return match read_metadata(&data, Some(password.as_str())) {
+            Ok(entries) => match serde_json::to_string(&entries) {
+                Ok(json) => HttpResponse::Ok()
+                    .content_type("application/json")
+                    .insert_header(("X-Content-Type-Options", "nosniff"))
+                    .body(json),
+                Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+            },
+            Err(e) if e.is_password_error() => password_error(&e),
+            Err(e) => {
+                log::error!("Erreur de lecture des métadonnées : {}", e);
+                HttpResponse::UnprocessableEntity()
+                    .body(format!("Erreur lors de la lecture des métadonnées : {}", e))
+            }
+        };
+    }
+
+    log::info!("Mise à jour de {} métadonnée(s)", changes.len());
+
+    match write_metadata(&data, &changes, Some(password.as_str()), encryption.as_ref()) {
+        Ok(bytes) => {
+            log::info!("Métadonnées mises à jour ({} octets)", bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", "attachment; filename=\"metadata.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e @ PdfError::InvalidMetadataKey(_)) => {
+            HttpResponse::BadRequest().body(e.to_string())
+        }
+        Err(e) => {
+            log::error!("Erreur de mise à jour des métadonnées : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors de la mise à jour des métadonnées : {}", e))
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::{password_error, EncryptFields};
+use crate::pdf::mirror::{mirror_pages, MirrorAxis};
+use crate::pdf::utils::parse_page_ranges;
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
+
+pub async fn mirror_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut pages_input = String::new();
+    let mut axis_input = String::new();
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if name == "file" { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => {
+                let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+                if ct != "application/pdf" {
+                    return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+                }
+                if data.len() < 5 || &data[..5] != b"%PDF-" {
+                    return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+                }
+                pdf_data = Some(data);
+            }
+            "pages" | "axis" => {
+                let Ok(s) = std::str::from_utf8(&data) else {
+                    return HttpResponse::BadRequest().body("Encodage invalide.");
+                };
+                if name == "pages" {
+                    pages_input = s.trim().to_string();
+                } else {
+                    axis_input = s.trim().to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    // Sans liste de pages, tout le document est traité.
+    let pages = if pages_input.is_empty() {
+        None
+    } else {
+        match parse_page_ranges(&pages_input) {
+            Ok(p) => Some(p),
+            Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        }
+    };
+
+    let axis = match axis_input.as_str() {
+        "" | "horizontal" => MirrorAxis::Horizontal,
+        "vertical" => MirrorAxis::Vertical,
+        "both" => MirrorAxis::Both,
+        other => return HttpResponse::BadRequest()
+            .body(format!("Axe invalide : '{}' (valeurs autorisées : horizontal, vertical, both)", other)),
+    };
+
+    log::info!("Miroir {:?}", axis);
+
+    match mirror_pages(&data, pages.as_deref(), axis, Some(password.as_str()), encryption.as_ref()) {
+        Ok(bytes) => {
+            log::info!("Miroir réussi ({} octets)", bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", "attachment; filename=\"mirrored.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e) => {
+            log::error!("Erreur de miroir : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors de l'application du miroir : {}", e))
+        }
+    }
+}
@@ -3,3 +3,126 @@ pub mod reorder;
 pub mod extract;
 pub mod rotate;
 pub mod delete;
+pub mod overlay;
+pub mod insert_blank;
+pub mod insert;
+pub mod replace;
+pub mod mirror;
+pub mod encrypt;
+pub mod metadata;
+pub mod sanitize;
+pub mod active_content;
+pub mod bookmarks;
+pub mod forms;
+pub mod annotations;
+pub mod redact;
+pub mod text;
+pub mod search;
+pub mod split;
+
+use actix_web::HttpResponse;
+
+use crate::pdf::encrypt::Encryption;
+use crate::pdf::error::PdfError;
+use crate::pdf::search::{select_pages, PageSelector};
+use crate::pdf::utils::format_page_ranges;
+
+/// Réponse commune à toutes les routes pour un document chiffré : mot de
+/// passe absent (401) ou incorrect (403), chiffrement illisible (422),
+/// plutôt qu'une erreur générique.
+pub fn password_error(e: &PdfError) -> HttpResponse {
+    log::warn!("Document chiffré : {}", e);
+    match e {
+        PdfError::WrongPassword => HttpResponse::Forbidden().body(e.to_string()),
+        PdfError::UnsupportedEncryption => HttpResponse::UnprocessableEntity().body(e.to_string()),
+        _ => HttpResponse::Unauthorized().body(e.to_string()),
+    }
+}
+
+/// Valeur d'un champ booléen : `true`, `1` ou `on`, `false`, `0`, `off` ou
+/// vide, à la casse et aux espaces près. Toute autre valeur est refusée
+/// plutôt que lue comme `false`.
+pub fn parse_flag(name: &str, data: &[u8]) -> Result<bool, HttpResponse> {
+    match std::str::from_utf8(data).map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+        Ok("true" | "1" | "on") => Ok(true),
+        Ok("" | "false" | "0" | "off") => Ok(false),
+        _ => Err(HttpResponse::BadRequest().body(format!("Valeur de '{}' invalide (true ou false).", name))),
+    }
+}
+
+/// Champs `encrypt_user_password`, `encrypt_owner_password`,
+/// `encrypt_algorithm` et `encrypt_permissions`, acceptés par toutes les
+/// routes qui produisent un PDF : la sortie est chiffrée dès que l'un d'eux
+/// est renseigné, avec les mêmes règles que `/encrypt`.
+#[derive(Debug, Default)]
+pub struct EncryptFields {
+    user_password: String,
+    owner_password: String,
+    algorithm: String,
+    permissions: String,
+}
+
+impl EncryptFields {
+    /// Vrai si `name` est l'un des champs `encrypt_*`.
+    pub fn accepts(name: &str) -> bool {
+        matches!(
+            name,
+            "encrypt_user_password" | "encrypt_owner_password" | "encrypt_algorithm" | "encrypt_permissions"
+        )
+    }
+
+    /// Enregistre la valeur d'un champ accepté par [`EncryptFields::accepts`].
+    pub fn set(&mut self, name: &str, value: String) {
+        match name {
+            // Les mots de passe sont pris tels quels : les espaces comptent.
+            "encrypt_user_password" => self.user_password = value,
+            "encrypt_owner_password" => self.owner_password = value,
+            "encrypt_algorithm" => self.algorithm = value,
+            "encrypt_permissions" => self.permissions = value,
+            _ => {}
+        }
+    }
+
+    /// Chiffrement demandé, `None` si aucun champ n'est renseigné.
+    pub fn encryption(self) -> Result<Option<Encryption>, PdfError> {
+        let requested = [&self.user_password, &self.owner_password, &self.algorithm, &self.permissions]
+            .iter()
+            .any(|v| !v.is_empty());
+        if !requested {
+            return Ok(None);
+        }
+        Encryption::from_fields(&self.algorithm, self.user_password, self.owner_password, &self.permissions)
+            .map(Some)
+    }
+}
+
+/// Restreint `pages` (toutes si `None`) aux pages retenues par `selector`,
+/// pour `/delete` et `/extract`. Retourne les pages et, si la sélection est
+/// textuelle, leur liste pour l'en-tête `X-Matched-Pages` ; une sélection
+/// vide est refusée (422).
+pub fn select_by_text(
+    data: &[u8],
+    pages: Option<Vec<u32>>,
+    selector: Option<&PageSelector>,
+    password: &str,
+) -> Result<(Vec<u32>, Option<String>), HttpResponse> {
+    let Some(selector) = selector else {
+        return Ok((pages.unwrap_or_default(), None));
+    };
+    match select_pages(data, selector, pages.as_deref(), Some(password)) {
+        Ok(p) if p.is_empty() => {
+            Err(HttpResponse::UnprocessableEntity().body("Aucune page ne correspond à la sélection."))
+        }
+        Ok(p) => {
+            let matched = format_page_ranges(&p);
+            log::info!("Pages retenues par leur texte : {}", matched);
+            Ok((p, Some(matched)))
+        }
+        Err(e) if e.is_password_error() => Err(password_error(&e)),
+        Err(e @ PdfError::PageOutOfRange { .. }) => Err(HttpResponse::BadRequest().body(e.to_string())),
+        Err(e) => {
+            log::error!("Erreur de lecture du texte : {}", e);
+            Err(HttpResponse::UnprocessableEntity().body(format!("Erreur de lecture du texte : {}", e)))
+        }
+    }
+}
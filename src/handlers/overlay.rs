@@ -0,0 +1,123 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::{password_error, EncryptFields};
+use crate::pdf::overlay::{overlay_pages, Layer, OverlayMode};
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
+
+pub async fn overlay_handler(mut payload: Multipart) -> HttpResponse {
+    let mut base_data: Option<Vec<u8>> = None;
+    let mut overlay_data: Option<Vec<u8>> = None;
+    let mut mode_input = String::new();
+    let mut page_input = String::new();
+    let mut layer_input = String::new();
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let is_file = name == "file" || name == "overlay";
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if is_file { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if is_file {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        if is_file {
+            let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+            if ct != "application/pdf" {
+                return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+            }
+            if data.len() < 5 || &data[..5] != b"%PDF-" {
+                return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => base_data = Some(data),
+            "overlay" => overlay_data = Some(data),
+            "mode" | "page" | "layer" => {
+                let Ok(s) = std::str::from_utf8(&data) else {
+                    return HttpResponse::BadRequest().body("Encodage invalide.");
+                };
+                let value = s.trim().to_string();
+                match name.as_str() {
+                    "mode" => mode_input = value,
+                    "page" => page_input = value,
+                    _ => layer_input = value,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(base) = base_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let Some(overlay) = overlay_data else {
+        return HttpResponse::BadRequest().body("PDF d'overlay requis (champ 'overlay').");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    let mode = match mode_input.as_str() {
+        "" | "single" => match page_input.as_str() {
+            "" => OverlayMode::Single(1),
+            p => match p.parse() {
+                Ok(n) => OverlayMode::Single(n),
+                Err(_) => return HttpResponse::BadRequest()
+                    .body(format!("Numéro de page invalide : '{}'", p)),
+            },
+        },
+        "page" => OverlayMode::PageForPage,
+        "cycle" => OverlayMode::Cycle,
+        other => return HttpResponse::BadRequest()
+            .body(format!("Mode invalide : '{}' (valeurs autorisées : single, page, cycle)", other)),
+    };
+
+    let layer = match layer_input.as_str() {
+        "" | "over" => Layer::Over,
+        "under" => Layer::Under,
+        other => return HttpResponse::BadRequest()
+            .body(format!("Position invalide : '{}' (valeurs autorisées : over, under)", other)),
+    };
+
+    log::info!("Overlay ({:?}, {:?})", mode, layer);
+
+    match overlay_pages(&base, &overlay, mode, layer, Some(password.as_str()), encryption.as_ref()) {
+        Ok(bytes) => {
+            log::info!("Overlay réussi ({} octets)", bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", "attachment; filename=\"overlay.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e) => {
+            log::error!("Erreur d'overlay : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors de la superposition : {}", e))
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::{parse_flag, password_error, EncryptFields};
+use crate::pdf::error::PdfError;
+use crate::pdf::redact::{compile_patterns, preview_matches, redact, regions_from_json};
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE, MAX_JSON_SIZE};
+
+/// Caviarde les zones du champ `regions`
+/// (`[{ "page": 1, "rect": [x0, y0, x1, y1] }]`) et les occurrences des
+/// champs `pattern` (expression régulière) et `preset` (`email`, `iban`,
+/// `nir`), répétables, puis retourne le PDF, le rapport des éléments
+/// retirés dans l'en-tête `X-Redaction-Report`. Avec `dry_run`, retourne
+/// seulement les occurrences trouvées, en JSON.
+pub async fn redact_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+    let mut regions_input: Option<String> = None;
+    let mut patterns: Vec<String> = Vec::new();
+    let mut presets: Vec<String> = Vec::new();
+    let mut dry_run = false;
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = match name.as_str() {
+                "file" => MAX_FILE_SIZE,
+                "regions" => MAX_JSON_SIZE,
+                _ => MAX_FIELD_SIZE,
+            };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "regions" => match String::from_utf8(data) {
+                Ok(s) => regions_input = Some(s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "pattern" | "preset" => match String::from_utf8(data) {
+                Ok(s) if s.is_empty() => {}
+                Ok(s) if name == "pattern" => patterns.push(s),
+                Ok(s) => presets.push(s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "dry_run" => match parse_flag(&name, &data) {
+                Ok(enabled) => dry_run = enabled,
+                Err(response) => return response,
+            },
+            "file" => {
+                let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+                if ct != "application/pdf" {
+                    return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+                }
+                if data.len() < 5 || &data[..5] != b"%PDF-" {
+                    return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+                }
+                pdf_data = Some(data);
+            }
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    let patterns = match compile_patterns(&patterns, &presets) {
+        Ok(p) => p,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    if dry_run {
+        if patterns.is_empty() {
+            return HttpResponse::BadRequest().body("Champ 'pattern' ou 'preset' requis pour une simulation.");
+        }
+        return match preview_matches(&data, &patterns, Some(password.as_str())) {
+            Ok(found) => {
+                log::info!("Simulation de caviardage : {} occurrence(s)", found["count"]);
+                HttpResponse::Ok()
+                    .content_type("application/json")
+                    .insert_header(("X-Content-Type-Options", "nosniff"))
+                    .body(found.to_string())
+            }
+            Err(e) if e.is_password_error() => password_error(&e),
+            Err(e) => {
+                log::error!("Erreur de recherche : {}", e);
+                HttpResponse::UnprocessableEntity()
+                    .body(format!("Erreur lors de la recherche : {}", e))
+            }
+        };
+    }
+
+    let regions = match regions_input.as_deref().map(regions_from_json).transpose() {
+        Ok(r) => r.unwrap_or_default(),
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    if regions.is_empty() && patterns.is_empty() {
+        return HttpResponse::BadRequest().body("Champ 'regions', 'pattern' ou 'preset' requis.");
+    }
+
+    log::info!("Caviardage de {} zone(s) et {} motif(s)", regions.len(), patterns.len());
+
+    match redact(&data, &regions, &patterns, Some(password.as_str()), encryption.as_ref()) {
+        Ok((bytes, report)) => {
+            log::info!("Document caviardé ({} octets) : {}", bytes.len(), report);
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .insert_header(("X-Redaction-Report", report.to_string()))
+                .append_header(("Content-Disposition", "attachment; filename=\"redacted.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e @ PdfError::PageOutOfRange { .. }) => HttpResponse::BadRequest().body(e.to_string()),
+        Err(e) => {
+            log::error!("Erreur de caviardage : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors du caviardage : {}", e))
+        }
+    }
+}
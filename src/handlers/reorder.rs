@@ -3,11 +3,14 @@ use actix_web::HttpResponse;
 use futures_util::StreamExt;
 
 use crate::{MAX_FIELD_SIZE, MAX_FILE_SIZE};
+use crate::handlers::{password_error, EncryptFields};
 use crate::pdf::reorder::reorder_pages;
 
 pub async fn reorder_handler(mut payload: Multipart) -> HttpResponse {
     let mut file_data: Option<Vec<u8>> = None;
     let mut order_str = String::new();
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
 
     while let Some(item) = payload.next().await {
         let mut field = match item {
@@ -76,6 +79,52 @@ pub async fn reorder_handler(mut payload: Multipart) -> HttpResponse {
                 order_str = String::from_utf8_lossy(&buf).to_string();
             }
 
+            "password" => {
+                let mut buf = Vec::new();
+                while let Some(chunk) = field.next().await {
+                    match chunk {
+                        Ok(data) => {
+                            buf.extend_from_slice(&data);
+                            if buf.len() > MAX_FIELD_SIZE {
+                                return HttpResponse::BadRequest()
+                                    .body("Champ 'password' trop long.");
+                            }
+                        }
+                        Err(e) => {
+                            return HttpResponse::BadRequest()
+                                .body(format!("Erreur lecture champ : {}", e))
+                        }
+                    }
+                }
+                password = match String::from_utf8(buf) {
+                    Ok(s) => s,
+                    Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+                };
+            }
+
+            n if EncryptFields::accepts(n) => {
+                let mut buf = Vec::new();
+                while let Some(chunk) = field.next().await {
+                    match chunk {
+                        Ok(data) => {
+                            buf.extend_from_slice(&data);
+                            if buf.len() > MAX_FIELD_SIZE {
+                                return HttpResponse::BadRequest()
+                                    .body(format!("Champ '{}' trop long.", n));
+                            }
+                        }
+                        Err(e) => {
+                            return HttpResponse::BadRequest()
+                                .body(format!("Erreur lecture champ : {}", e))
+                        }
+                    }
+                }
+                match String::from_utf8(buf) {
+                    Ok(s) => encrypt.set(n, s),
+                    Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+                }
+            }
+
             _ => {
                 while let Some(chunk) = field.next().await {
                     if chunk.is_err() { break; }
@@ -103,11 +152,17 @@ pub async fn reorder_handler(mut payload: Multipart) -> HttpResponse {
             .body("Paramètre 'order' invalide (attendu : \"1,3,2,...\")."),
     };
 
-    match reorder_pages(&data, &order) {
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    match reorder_pages(&data, &order, Some(password.as_str()), encryption.as_ref()) {
         Ok(result) => HttpResponse::Ok()
             .content_type("application/pdf")
             .insert_header(("Content-Disposition", "attachment; filename=\"reordered.pdf\""))
             .body(result),
+        Err(e) if e.is_password_error() => password_error(&e),
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
 }
@@ -0,0 +1,123 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::{password_error, EncryptFields};
+use crate::pdf::replace::replace_pages;
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
+
+pub async fn replace_handler(mut payload: Multipart) -> HttpResponse {
+    let mut target_data: Option<Vec<u8>> = None;
+    let mut source_data: Option<Vec<u8>> = None;
+    let mut mapping_input = String::new();
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let is_file = name == "file" || name == "source";
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if is_file { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if is_file {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        if is_file {
+            let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+            if ct != "application/pdf" {
+                return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+            }
+            if data.len() < 5 || &data[..5] != b"%PDF-" {
+                return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => target_data = Some(data),
+            "source" => source_data = Some(data),
+            "mapping" => {
+                match std::str::from_utf8(&data) {
+                    Ok(s) => mapping_input = s.trim().to_string(),
+                    Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(target) = target_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let Some(source) = source_data else {
+        return HttpResponse::BadRequest().body("PDF source requis (champ 'source').");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    // Format attendu : "7:1,9:2" (page cible : page source)
+    let mapping = match parse_mapping(&mapping_input) {
+        Ok(m) => m,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+
+    if mapping.is_empty() {
+        return HttpResponse::BadRequest().body("Aucun remplacement spécifié.");
+    }
+
+    log::info!("Remplacement de {} page(s)", mapping.len());
+
+    match replace_pages(&target, &source, &mapping, Some(password.as_str()), encryption.as_ref()) {
+        Ok(bytes) => {
+            log::info!("Remplacement réussi ({} octets)", bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", "attachment; filename=\"replaced.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e) => {
+            log::error!("Erreur de remplacement : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors du remplacement : {}", e))
+        }
+    }
+}
+
+/// Parse "7:1,9:2" → [(7, 1), (9, 2)]
+fn parse_mapping(input: &str) -> Result<Vec<(u32, u32)>, String> {
+    let mut result = Vec::new();
+
+    for part in input.split(',') {
+        let part = part.trim();
+        if part.is_empty() { continue; }
+        let (target, source) = part.split_once(':')
+            .ok_or_else(|| format!("Remplacement invalide : '{}' (attendu : cible:source)", part))?;
+        let target: u32 = target.trim().parse()
+            .map_err(|_| format!("Numéro de page invalide : '{}'", part))?;
+        let source: u32 = source.trim().parse()
+            .map_err(|_| format!("Numéro de page invalide : '{}'", part))?;
+        result.push((target, source));
+    }
+
+    Ok(result)
+}
@@ -1,13 +1,20 @@
 use actix_multipart::Multipart;
 use actix_web::HttpResponse;
 use futures_util::TryStreamExt;
+use std::collections::HashSet;
 
-use crate::pdf::rotate::rotate_pages;
+use crate::handlers::{parse_flag, password_error, EncryptFields};
+use crate::pdf::error::PdfError;
+use crate::pdf::rotate::{normalize_rotation, rotate_content, rotate_pages, RotationFit};
+use crate::pdf::utils::parse_page_ranges;
 use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
 
 pub async fn rotate_handler(mut payload: Multipart) -> HttpResponse {
     let mut pdf_data: Option<Vec<u8>> = None;
     let mut rotations_input = String::new();
+    let mut bake = false;
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
 
     while let Ok(Some(mut field)) = payload.try_next().await {
         let name = field.name().to_string();
@@ -27,6 +34,14 @@ pub async fn rotate_handler(mut payload: Multipart) -> HttpResponse {
         }
 
         match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
             "file" => {
                 let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
                 if ct != "application/pdf" {
@@ -43,6 +58,11 @@ pub async fn rotate_handler(mut payload: Multipart) -> HttpResponse {
                     Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
                 }
             }
+            // Intègre la rotation au contenu plutôt que de laisser /Rotate
+            "bake" => match parse_flag(&name, &data) {
+                Ok(enabled) => bake = enabled,
+                Err(response) => return response,
+            },
             _ => {}
         }
     }
@@ -50,6 +70,10 @@ pub async fn rotate_handler(mut payload: Multipart) -> HttpResponse {
     let Some(data) = pdf_data else {
         return HttpResponse::BadRequest().body("Fichier PDF requis.");
     };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
 
     // Format attendu : "1:90,2:180,3:270"
     let rotations = match parse_rotations(&rotations_input) {
@@ -63,7 +87,7 @@ pub async fn rotate_handler(mut payload: Multipart) -> HttpResponse {
 
     log::info!("Rotation de {} page(s)", rotations.len());
 
-    match rotate_pages(&data, &rotations) {
+    match rotate_pages(&data, &rotations, bake, Some(password.as_str()), encryption.as_ref()) {
         Ok(bytes) => {
             log::info!("Rotation réussie ({} octets)", bytes.len());
             HttpResponse::Ok()
@@ -72,6 +96,7 @@ pub async fn rotate_handler(mut payload: Multipart) -> HttpResponse {
                 .append_header(("Content-Disposition", "attachment; filename=\"rotated.pdf\""))
                 .body(bytes)
         }
+        Err(e) if e.is_password_error() => password_error(&e),
         Err(e) => {
             log::error!("Erreur de rotation : {}", e);
             HttpResponse::InternalServerError().body("Erreur lors de la rotation.")
@@ -79,6 +104,194 @@ pub async fn rotate_handler(mut payload: Multipart) -> HttpResponse {
     }
 }
 
+pub async fn normalize_rotation_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut pages_input = String::new();
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if name == "file" { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => {
+                let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+                if ct != "application/pdf" {
+                    return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+                }
+                if data.len() < 5 || &data[..5] != b"%PDF-" {
+                    return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+                }
+                pdf_data = Some(data);
+            }
+            "pages" => {
+                match std::str::from_utf8(&data) {
+                    Ok(s) => pages_input = s.trim().to_string(),
+                    Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    // Sans liste de pages, tout le document est normalisé.
+    let pages = if pages_input.is_empty() {
+        None
+    } else {
+        match parse_page_ranges(&pages_input) {
+            Ok(p) => Some(p),
+            Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        }
+    };
+
+    match normalize_rotation(&data, pages.as_deref(), Some(password.as_str()), encryption.as_ref()) {
+        Ok(bytes) => {
+            log::info!("Normalisation de la rotation réussie ({} octets)", bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", "attachment; filename=\"normalized.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e) => {
+            log::error!("Erreur de normalisation : {}", e);
+            HttpResponse::InternalServerError().body("Erreur lors de la normalisation.")
+        }
+    }
+}
+
+pub async fn rotate_content_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut angles_input = String::new();
+    let mut fit_input = String::new();
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if name == "file" { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => {
+                let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+                if ct != "application/pdf" {
+                    return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+                }
+                if data.len() < 5 || &data[..5] != b"%PDF-" {
+                    return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+                }
+                pdf_data = Some(data);
+            }
+            "angles" | "fit" => {
+                let Ok(s) = std::str::from_utf8(&data) else {
+                    return HttpResponse::BadRequest().body("Encodage invalide.");
+                };
+                if name == "angles" {
+                    angles_input = s.trim().to_string();
+                } else {
+                    fit_input = s.trim().to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    // Format attendu : "1-3:1.5, 5:-2"
+    let rotations = match parse_angles(&angles_input) {
+        Ok(r) => r,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+
+    if rotations.is_empty() {
+        return HttpResponse::BadRequest().body("Aucune rotation spécifiée.");
+    }
+
+    let fit = match fit_input.as_str() {
+        "" | "grow" => RotationFit::Grow,
+        "clip" => RotationFit::Clip,
+        other => return HttpResponse::BadRequest()
+            .body(format!("Mode invalide : '{}' (valeurs autorisées : grow, clip)", other)),
+    };
+
+    log::info!("Rotation libre de {} page(s) ({:?})", rotations.len(), fit);
+
+    match rotate_content(&data, &rotations, fit, Some(password.as_str()), encryption.as_ref()) {
+        Ok(bytes) => {
+            log::info!("Rotation libre réussie ({} octets)", bytes.len());
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .append_header(("Content-Disposition", "attachment; filename=\"deskewed.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e) => {
+            log::error!("Erreur de rotation libre : {}", e);
+            HttpResponse::InternalServerError().body("Erreur lors de la rotation.")
+        }
+    }
+}
+
 /// Parse "1:90,3:180,5:270" → [(1, 90), (3, 180), (5, 270)]
 fn parse_rotations(input: &str) -> Result<Vec<(u32, i64)>, String> {
     let valid_angles = [90i64, 180, 270];
@@ -100,3 +313,30 @@ fn parse_rotations(input: &str) -> Result<Vec<(u32, i64)>, String> {
 
     Ok(result)
 }
+
+/// Parse "1-3:1.5,5:-2" → [(1, 1.5), (2, 1.5), (3, 1.5), (5, -2.0)]
+fn parse_angles(input: &str) -> Result<Vec<(u32, f32)>, String> {
+    let mut result = Vec::new();
+    let mut seen = HashSet::new();
+
+    for part in input.split(',') {
+        let part = part.trim();
+        if part.is_empty() { continue; }
+        let (range, angle) = part.split_once(':')
+            .ok_or_else(|| format!("Rotation invalide : '{}' (attendu : pages:angle)", part))?;
+        let angle: f32 = angle.trim().parse()
+            .map_err(|_| format!("Angle invalide : '{}'", part))?;
+        if !angle.is_finite() || angle.abs() > 360.0 {
+            return Err(format!("Angle invalide : {} (entre -360 et 360)", angle));
+        }
+        for page in parse_page_ranges(range).map_err(|e| e.to_string())? {
+            // Deux angles pour une même page : lequel appliquer serait arbitraire.
+            if !seen.insert(page) {
+                return Err(PdfError::DuplicatePage(page).to_string());
+            }
+            result.push((page, angle));
+        }
+    }
+
+    Ok(result)
+}
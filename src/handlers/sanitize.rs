@@ -0,0 +1,106 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::{password_error, EncryptFields};
+use crate::pdf::sanitize::{sanitize, SanitizeCategory};
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
+
+pub async fn sanitize_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut categories_input = String::new();
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if name == "file" { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        match name.as_str() {
+            "password" => match String::from_utf8(data) {
+                Ok(s) => password = s,
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            n if EncryptFields::accepts(n) => match String::from_utf8(data) {
+                Ok(s) => encrypt.set(n, s),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            "file" => {
+                let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+                if ct != "application/pdf" {
+                    return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+                }
+                if data.len() < 5 || &data[..5] != b"%PDF-" {
+                    return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+                }
+                pdf_data = Some(data);
+            }
+            "categories" => match std::str::from_utf8(&data) {
+                Ok(s) => categories_input = s.trim().to_string(),
+                Err(_) => return HttpResponse::BadRequest().body("Encodage invalide."),
+            },
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    // Sans liste, toutes les catégories sont nettoyées.
+    let categories: Vec<SanitizeCategory> = if categories_input.is_empty() {
+        SanitizeCategory::ALL.to_vec()
+    } else {
+        let mut categories = Vec::new();
+        for part in categories_input.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match SanitizeCategory::from_name(part) {
+                Some(c) => categories.push(c),
+                None => {
+                    let known: Vec<&str> = SanitizeCategory::ALL.iter().map(|c| c.name()).collect();
+                    return HttpResponse::BadRequest()
+                        .body(format!("Catégorie inconnue : '{}' ({})", part, known.join(", ")));
+                }
+            }
+        }
+        categories
+    };
+
+    log::info!("Nettoyage de {} catégorie(s)", categories.len());
+
+    match sanitize(&data, &categories, Some(password.as_str()), encryption.as_ref()) {
+        Ok((bytes, report)) => {
+            log::info!("Nettoyage réussi ({} octets) : {:?}", bytes.len(), report);
+            // Le rapport accompagne le PDF dans un en-tête, en JSON compact.
+            let report = serde_json::to_string(&report).unwrap_or_default();
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .insert_header(("X-Sanitize-Report", report))
+                .append_header(("Content-Disposition", "attachment; filename=\"sanitized.pdf\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e) => {
+            log::error!("Erreur de nettoyage : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors du nettoyage : {}", e))
+        }
+    }
+}
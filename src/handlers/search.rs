@@ -0,0 +1,109 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::{parse_flag, password_error};
+use crate::pdf::error::PdfError;
+use crate::pdf::search::{search, SearchQuery};
+use crate::pdf::utils::parse_page_ranges;
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
+
+/// Cherche `query` dans le texte des pages (`pages`, toutes par défaut) et
+/// retourne en JSON les pages trouvées, des extraits et la liste des pages
+/// au format de `/extract`. `regex=true` lit la requête comme une
+/// expression régulière ; la casse et les accents sont ignorés sauf avec
+/// `case_sensitive=true` et `accent_sensitive=true`.
+pub async fn search_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut password = String::new();
+    let mut pages_input = String::new();
+    let mut query = SearchQuery::default();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if name == "file" { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        if name == "file" {
+            let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+            if ct != "application/pdf" {
+                return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+            }
+            if data.len() < 5 || &data[..5] != b"%PDF-" {
+                return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+            }
+            pdf_data = Some(data);
+            continue;
+        }
+
+        let flag = match name.as_str() {
+            "regex" => &mut query.regex,
+            "case_sensitive" => &mut query.case_sensitive,
+            "accent_sensitive" => &mut query.accent_sensitive,
+            _ => {
+                let Ok(value) = String::from_utf8(data) else {
+                    return HttpResponse::BadRequest().body("Encodage invalide.");
+                };
+                match name.as_str() {
+                    "password" => password = value,
+                    "pages" => pages_input = value,
+                    "query" => query.query = value,
+                    _ => {}
+                }
+                continue;
+            }
+        };
+        match parse_flag(&name, &data) {
+            Ok(enabled) => *flag = enabled,
+            Err(response) => return response,
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    if query.query.trim().is_empty() {
+        return HttpResponse::BadRequest().body("Champ 'query' requis.");
+    }
+    let matcher = match query.compile() {
+        Ok(m) => m,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    let pages = if pages_input.trim().is_empty() {
+        None
+    } else {
+        match parse_page_ranges(&pages_input) {
+            Ok(p) => Some(p),
+            Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        }
+    };
+
+    match search(&data, &matcher, pages.as_deref(), Some(password.as_str())) {
+        Ok(result) => {
+            log::info!("Recherche : {} occurrence(s), pages {}", result["count"], result["ranges"]);
+            HttpResponse::Ok()
+                .content_type("application/json")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .body(result.to_string())
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e @ PdfError::PageOutOfRange { .. }) => HttpResponse::BadRequest().body(e.to_string()),
+        Err(e) => {
+            log::error!("Erreur de recherche : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors de la recherche : {}", e))
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::{parse_flag, password_error, EncryptFields};
+use crate::pdf::error::PdfError;
+use crate::pdf::search::SearchQuery;
+use crate::pdf::split::split_at_separators;
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
+
+/// Découpe le document aux pages contenant le texte `marker` et retourne
+/// une archive ZIP, un PDF par document plus `report.json` (séparateurs
+/// trouvés, pages de chaque document) ; l'en-tête `X-Split-Report` n'en
+/// donne que les totaux.
+/// `regex`, `case_sensitive` et `accent_sensitive` s'appliquent au
+/// marqueur comme à `/search` ; `keep_separators=true` garde les
+/// séparateurs en tête du document qu'ils ouvrent.
+pub async fn split_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut password = String::new();
+    let mut encrypt = EncryptFields::default();
+    let mut marker = SearchQuery::default();
+    let mut keep_separators = false;
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if name == "file" { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        if name == "file" {
+            let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+            if ct != "application/pdf" {
+                return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+            }
+            if data.len() < 5 || &data[..5] != b"%PDF-" {
+                return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+            }
+            pdf_data = Some(data);
+            continue;
+        }
+
+        let flag = match name.as_str() {
+            "regex" => &mut marker.regex,
+            "case_sensitive" => &mut marker.case_sensitive,
+            "accent_sensitive" => &mut marker.accent_sensitive,
+            "keep_separators" => &mut keep_separators,
+            _ => {
+                let Ok(value) = String::from_utf8(data) else {
+                    return HttpResponse::BadRequest().body("Encodage invalide.");
+                };
+                match name.as_str() {
+                    "password" => password = value,
+                    n if EncryptFields::accepts(n) => encrypt.set(n, value),
+                    "marker" => marker.query = value,
+                    _ => {}
+                }
+                continue;
+            }
+        };
+        match parse_flag(&name, &data) {
+            Ok(enabled) => *flag = enabled,
+            Err(response) => return response,
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let encryption = match encrypt.encryption() {
+        Ok(e) => e,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    if marker.query.trim().is_empty() {
+        return HttpResponse::BadRequest().body("Champ 'marker' requis.");
+    }
+    let matcher = match marker.compile() {
+        Ok(m) => m,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    match split_at_separators(
+        &data,
+        &matcher,
+        keep_separators,
+        Some(password.as_str()),
+        encryption.as_ref(),
+    ) {
+        Ok((bytes, report)) => {
+            log::info!("Découpage ({} octets) : {}", bytes.len(), report);
+            HttpResponse::Ok()
+                .content_type("application/zip")
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .insert_header(("X-Split-Report", report.to_string()))
+                .append_header(("Content-Disposition", "attachment; filename=\"split.zip\""))
+                .body(bytes)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e @ PdfError::NoPages) => {
+            HttpResponse::BadRequest().body(format!("{} : toutes les pages sont des séparateurs", e))
+        }
+        Err(e) => {
+            log::error!("Erreur de découpage : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors du découpage : {}", e))
+        }
+    }
+}
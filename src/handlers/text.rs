@@ -0,0 +1,103 @@
+use actix_multipart::Multipart;
+use actix_web::HttpResponse;
+use futures_util::TryStreamExt;
+
+use crate::handlers::password_error;
+use crate::pdf::error::PdfError;
+use crate::pdf::text::{extract_text, text_to_json};
+use crate::pdf::utils::parse_page_ranges;
+use crate::{MAX_FILE_SIZE, MAX_FIELD_SIZE};
+
+/// Retourne le texte des pages (`pages`, toutes par défaut) : objet JSON
+/// indexé par numéro de page, ou texte brut (`format=text`) dont les pages
+/// sont séparées par un saut de page (`\f`).
+pub async fn text_handler(mut payload: Multipart) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut password = String::new();
+    let mut pages_input = String::new();
+    let mut plain = false;
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.name().to_string();
+        let mut data: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            data.extend_from_slice(&chunk);
+            let limit = if name == "file" { MAX_FILE_SIZE } else { MAX_FIELD_SIZE };
+            if data.len() > limit {
+                return HttpResponse::PayloadTooLarge()
+                    .body(if name == "file" {
+                        "Fichier trop volumineux (max 1 Go).".to_string()
+                    } else {
+                        format!("Champ '{}' trop long.", name)
+                    });
+            }
+        }
+
+        if name == "file" {
+            let ct = field.content_type().map(|m| m.to_string()).unwrap_or_default();
+            if ct != "application/pdf" {
+                return HttpResponse::BadRequest().body("Seuls les fichiers PDF sont acceptés.");
+            }
+            if data.len() < 5 || &data[..5] != b"%PDF-" {
+                return HttpResponse::BadRequest().body("Le fichier ne semble pas être un PDF valide.");
+            }
+            pdf_data = Some(data);
+            continue;
+        }
+
+        let Ok(value) = String::from_utf8(data) else {
+            return HttpResponse::BadRequest().body("Encodage invalide.");
+        };
+        match name.as_str() {
+            "password" => password = value,
+            "pages" => pages_input = value,
+            "format" => {
+                plain = match value.trim() {
+                    "" | "json" => false,
+                    "text" => true,
+                    other => {
+                        return HttpResponse::BadRequest()
+                            .body(format!("Format inconnu : '{}' (json ou text)", other));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(data) = pdf_data else {
+        return HttpResponse::BadRequest().body("Fichier PDF requis.");
+    };
+    let pages = if pages_input.trim().is_empty() {
+        None
+    } else {
+        match parse_page_ranges(&pages_input) {
+            Ok(p) => Some(p),
+            Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        }
+    };
+
+    match extract_text(&data, pages.as_deref(), Some(password.as_str())) {
+        Ok(texts) => {
+            log::info!("Texte extrait de {} page(s)", texts.len());
+            let (content_type, body) = if plain {
+                let pages: Vec<&str> = texts.iter().map(|(_, t)| t.as_str()).collect();
+                ("text/plain; charset=utf-8", pages.join("\u{c}"))
+            } else {
+                ("application/json", text_to_json(&texts))
+            };
+            HttpResponse::Ok()
+                .content_type(content_type)
+                .insert_header(("X-Content-Type-Options", "nosniff"))
+                .body(body)
+        }
+        Err(e) if e.is_password_error() => password_error(&e),
+        Err(e @ PdfError::PageOutOfRange { .. }) => HttpResponse::BadRequest().body(e.to_string()),
+        Err(e) => {
+            log::error!("Erreur d'extraction du texte : {}", e);
+            HttpResponse::UnprocessableEntity()
+                .body(format!("Erreur lors de l'extraction du texte : {}", e))
+        }
+    }
+}
@@ -6,6 +6,8 @@ use std::sync::OnceLock;
 use actix_web::{middleware, web, App, HttpResponse, HttpServer};
 use actix_files::Files;
 
+use pdf::active_content::ActiveContentPolicy;
+
 // Noms des fichiers assets déterminés à la compilation par build.rs.
 // En release : "style.a3f2c891.css" / "app.b7d41e02.js"
 // En debug   : "style.css" / "app.js"
@@ -35,6 +37,8 @@ pub const MAX_FILE_SIZE: usize = 1024 * 1024 * 1024;
 pub const MAX_FILES: usize = 20;
 // Taille max d'un champ texte
 pub const MAX_FIELD_SIZE: usize = 1024;
+// Taille max d'un champ JSON (signets, données de formulaire)
+pub const MAX_JSON_SIZE: usize = 1024 * 1024;
 
 async fn index() -> HttpResponse {
     HttpResponse::Ok()
@@ -57,6 +61,18 @@ async fn main() -> std::io::Result<()> {
     log::info!("🚀 PDF Eater démarré → http://localhost:8080");
     log::info!("   Assets : {} | {}", STYLE_CSS_FILE, APP_JS_FILE);
 
+    // ACTIVE_CONTENT_POLICY=strip|strip-uris : retire le contenu actif de
+    // chaque PDF produit, quelle que soit la route.
+    let policy_name = std::env::var("ACTIVE_CONTENT_POLICY").unwrap_or_default();
+    let Some(policy) = ActiveContentPolicy::from_name(&policy_name) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("ACTIVE_CONTENT_POLICY invalide : '{}' (keep, strip ou strip-uris)", policy_name),
+        ));
+    };
+    pdf::active_content::set_policy(policy);
+    log::info!("   Contenu actif : {:?}", policy);
+
     HttpServer::new(|| {
         let json_cfg = web::JsonConfig::default().limit(MAX_FILE_SIZE);
 
@@ -71,6 +87,27 @@ async fn main() -> std::io::Result<()> {
             .route("/rotate",  web::post().to(handlers::rotate::rotate_handler))
             .route("/delete",  web::post().to(handlers::delete::delete_handler))
             .route("/reorder", web::post().to(handlers::reorder::reorder_handler))
+            .route("/overlay", web::post().to(handlers::overlay::overlay_handler))
+            .route("/insert-blank", web::post().to(handlers::insert_blank::insert_blank_handler))
+            .route("/insert",  web::post().to(handlers::insert::insert_handler))
+            .route("/replace", web::post().to(handlers::replace::replace_handler))
+            .route("/mirror",  web::post().to(handlers::mirror::mirror_handler))
+            .route("/normalize-rotation", web::post().to(handlers::rotate::normalize_rotation_handler))
+            .route("/rotate-content", web::post().to(handlers::rotate::rotate_content_handler))
+            .route("/encrypt", web::post().to(handlers::encrypt::encrypt_handler))
+            .route("/metadata", web::post().to(handlers::metadata::metadata_handler))
+            .route("/sanitize", web::post().to(handlers::sanitize::sanitize_handler))
+            .route("/strip-active", web::post().to(handlers::active_content::strip_active_handler))
+            .route("/bookmarks", web::post().to(handlers::bookmarks::bookmarks_handler))
+            .route("/fill-form", web::post().to(handlers::forms::fill_form_handler))
+            .route("/flatten-form", web::post().to(handlers::forms::flatten_form_handler))
+            .route("/export-form", web::post().to(handlers::forms::export_form_handler))
+            .route("/mail-merge", web::post().to(handlers::forms::mail_merge_handler))
+            .route("/annotations", web::post().to(handlers::annotations::annotations_handler))
+            .route("/redact", web::post().to(handlers::redact::redact_handler))
+            .route("/text", web::post().to(handlers::text::text_handler))
+            .route("/search", web::post().to(handlers::search::search_handler))
+            .route("/split", web::post().to(handlers::split::split_handler))
             .default_service(web::to(|| async {
                 HttpResponse::NotFound().body("404 - Page introuvable")
             }))
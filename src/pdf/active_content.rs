@@ -0,0 +1,137 @@
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::utils::{
+    find_catalog, finalize, load_document, prune_unreachable, remove_name_tree, visit_dicts_mut,
+};
+
+/// Politique appliquée par `finalize()` à tous les PDFs produits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ActiveContentPolicy {
+    /// Les actions du document sont conservées telles quelles.
+    #[default]
+    Keep,
+    /// JavaScript, actions additionnelles, `Launch` et `SubmitForm` sont retirés.
+    Strip,
+    /// Comme `Strip`, en retirant aussi les liens externes (`URI`).
+    StripWithUris,
+}
+
+impl ActiveContentPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "" | "keep" => Some(Self::Keep),
+            "strip" => Some(Self::Strip),
+            "strip-uris" => Some(Self::StripWithUris),
+            _ => None,
+        }
+    }
+}
+
+static POLICY: OnceLock<ActiveContentPolicy> = OnceLock::new();
+
+/// Fixe la politique du serveur. Sans effet après le premier appel.
+pub fn set_policy(policy: ActiveContentPolicy) {
+    let _ = POLICY.set(policy);
+}
+
+pub fn policy() -> ActiveContentPolicy {
+    POLICY.get().copied().unwrap_or_default()
+}
+
+/// Nombre d'éléments retirés, par type d'action.
+pub type ActiveContentReport = BTreeMap<&'static str, usize>;
+
+/// Retire le contenu actif du document et retourne le PDF avec le nombre
+/// d'éléments retirés.
+pub fn strip_active(
+    data: &[u8],
+    strip_uris: bool,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<(Vec<u8>, ActiveContentReport)> {
+    let mut doc = load_document(data, password)?;
+    let catalog_id = find_catalog(&doc)
+        .ok_or_else(|| PdfError::InvalidFile("(catalogue introuvable)".to_string()))?;
+
+    let report = strip_active_content(&mut doc, catalog_id, strip_uris)?;
+    let bytes = finalize(&mut doc, catalog_id, encryption)?;
+    Ok((bytes, report))
+}
+
+/// Type d'une action à retirer, `None` si elle est conservée. Toute action
+/// portant une clé `/JS` (y compris `Rendition`) compte comme du JavaScript.
+fn dangerous_action(dict: &Dictionary, strip_uris: bool) -> Option<&'static str> {
+    if dict.has(b"JS") {
+        return Some("javascript");
+    }
+    match dict.get(b"S").and_then(Object::as_name).ok()? {
+        b"JavaScript" => Some("javascript"),
+        b"Launch" => Some("launch"),
+        b"SubmitForm" => Some("submit_form"),
+        b"URI" if strip_uris => Some("uri"),
+        _ => None,
+    }
+}
+
+/// Retire en place l'arbre `/JavaScript`, les actions additionnelles (`/AA`)
+/// et les actions dangereuses référencées par `/A`, `/OpenAction` ou `/Next`.
+pub fn strip_active_content(
+    doc: &mut Document,
+    catalog_id: ObjectId,
+    strip_uris: bool,
+) -> Result<ActiveContentReport> {
+    let mut report = ActiveContentReport::new();
+
+    let scripts = remove_name_tree(doc, catalog_id, b"JavaScript")?;
+    if scripts > 0 {
+        report.insert("javascript", scripts);
+    }
+
+    // Actions indirectes : elles ne sont pas résolues depuis `visit_dicts_mut`.
+    let indirect: HashMap<ObjectId, &'static str> = doc
+        .objects
+        .iter()
+        .filter_map(|(&id, obj)| Some((id, dangerous_action(obj.as_dict().ok()?, strip_uris)?)))
+        .collect();
+    let kind_of = |obj: &Object| match obj {
+        Object::Reference(id) => indirect.get(id).copied(),
+        Object::Dictionary(dict) => dangerous_action(dict, strip_uris),
+        _ => None,
+    };
+
+    for obj in doc.objects.values_mut() {
+        visit_dicts_mut(obj, &mut |dict| {
+            if dict.remove(b"AA").is_some() {
+                *report.entry("additional_actions").or_default() += 1;
+            }
+            for key in [b"A".as_slice(), b"OpenAction", b"Next"] {
+                match dict.get_mut(key) {
+                    // `/Next` peut enchaîner plusieurs actions.
+                    Ok(Object::Array(actions)) => actions.retain(|a| match kind_of(a) {
+                        Some(kind) => {
+                            *report.entry(kind).or_default() += 1;
+                            false
+                        }
+                        None => true,
+                    }),
+                    Ok(action) => {
+                        if let Some(kind) = kind_of(action) {
+                            dict.remove(key);
+                            *report.entry(kind).or_default() += 1;
+                        }
+                    }
+                    Err(_) => {}
+                }
+            }
+        });
+    }
+
+    if !report.is_empty() {
+        prune_unreachable(doc);
+    }
+    Ok(report)
+}
@@ -0,0 +1,206 @@
+use lopdf::{decode_text_string, Dictionary, Document, Object, ObjectId};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::flatten::{draw_annotations, remove_annots};
+use super::utils::{annotation_rect, load_document, prune_unreachable, rebuild};
+
+/// Sous-types jamais modifiés sans être demandés explicitement : retirer
+/// les liens change la navigation, pas la relecture.
+const KEPT_BY_DEFAULT: &[&str] = &["Link"];
+
+/// Sélection d'annotations. Un critère vide ne filtre rien.
+#[derive(Debug, Clone, Default)]
+pub struct AnnotationFilter {
+    /// Sous-types (`Highlight`, `Text`, `Stamp`…), sans tenir compte de la casse.
+    pub subtypes: Vec<String>,
+    /// Auteurs (`/T`), sans tenir compte de la casse.
+    pub authors: Vec<String>,
+    /// Pages, 1-indexées.
+    pub pages: Option<Vec<u32>>,
+}
+
+/// Traitement appliqué aux annotations sélectionnées.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationAction {
+    Remove,
+    /// Dessine leur apparence dans le contenu de la page, puis les retire.
+    Flatten,
+}
+
+/// Annotation d'une page, hors fenêtres `Popup` (rattachées à leur parent).
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    pub id: ObjectId,
+    pub page: u32,
+    pub subtype: String,
+    pub author: Option<String>,
+    pub contents: Option<String>,
+    /// Fenêtre `Popup` associée.
+    pub popup: Option<ObjectId>,
+    /// Annotation à laquelle celle-ci répond (`/IRT`).
+    pub reply_to: Option<ObjectId>,
+}
+
+impl AnnotationFilter {
+    fn matches(&self, annot: &Annotation) -> bool {
+        let subtype_ok = if self.subtypes.is_empty() {
+            true
+        } else {
+            self.subtypes.iter().any(|s| s.eq_ignore_ascii_case(&annot.subtype))
+        };
+        let author_ok = self.authors.is_empty()
+            || annot
+                .author
+                .as_deref()
+                .is_some_and(|a| self.authors.iter().any(|f| f.trim().to_lowercase() == a.trim().to_lowercase()));
+        let page_ok = self.pages.as_ref().is_none_or(|p| p.contains(&annot.page));
+        subtype_ok && author_ok && page_ok
+    }
+
+    /// Les widgets relèvent du formulaire, les liens ne sont traités que
+    /// s'ils sont demandés.
+    fn editable(&self, annot: &Annotation) -> bool {
+        annot.subtype != "Widget"
+            && (!self.subtypes.is_empty() || !KEPT_BY_DEFAULT.contains(&annot.subtype.as_str()))
+            && self.matches(annot)
+    }
+
+    fn check_pages(&self, total: u32) -> Result<()> {
+        match self.pages.iter().flatten().find(|&&p| p > total) {
+            Some(&page) => Err(PdfError::PageOutOfRange { page, total }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Annotations sélectionnées par `filter`, en JSON, regroupées par page :
+/// `[{ "page": 1, "annotations": [{ "subtype", "author", "contents", "rect" }] }]`.
+pub fn list_annotations(data: &[u8], filter: &AnnotationFilter, password: Option<&str>) -> Result<Value> {
+    let doc = load_document(data, password)?;
+    filter.check_pages(doc.get_pages().len() as u32)?;
+
+    let mut pages: BTreeMap<u32, Vec<Value>> = BTreeMap::new();
+    for annot in annotations(&doc).iter().filter(|a| filter.matches(a)) {
+        pages.entry(annot.page).or_default().push(json!({
+            "subtype": annot.subtype,
+            "author": annot.author,
+            "contents": annot.contents,
+            "rect": annotation_rect(&doc, annot.id),
+        }));
+    }
+    Ok(Value::Array(
+        pages.into_iter().map(|(page, items)| json!({ "page": page, "annotations": items })).collect(),
+    ))
+}
+
+/// Retire ou aplatit les annotations sélectionnées et retourne le PDF et
+/// leur nombre. Les réponses (`/IRT`) aux annotations traitées et leurs
+/// fenêtres `Popup` sont retirées avec elles.
+pub fn edit_annotations(
+    data: &[u8],
+    action: AnnotationAction,
+    filter: &AnnotationFilter,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<(Vec<u8>, usize)> {
+    if filter.subtypes.iter().any(|s| s.eq_ignore_ascii_case("Widget")) {
+        return Err(PdfError::WidgetAnnotations);
+    }
+    let mut doc = load_document(data, password)?;
+    doc.decompress();
+    let pages = doc.get_pages();
+    filter.check_pages(pages.len() as u32)?;
+
+    let all = annotations(&doc);
+    let selected: Vec<&Annotation> = all.iter().filter(|a| filter.editable(a)).collect();
+    let mut targets: HashSet<ObjectId> = selected.iter().map(|a| a.id).collect();
+    let replies = add_replies(&all, &mut targets);
+
+    if action == AnnotationAction::Flatten {
+        for (&number, &page_id) in &pages {
+            // Les réponses ne s'affichent que dans le fil de commentaires.
+            let drawn: Vec<ObjectId> =
+                selected.iter().filter(|a| a.page == number && !replies.contains(&a.id)).map(|a| a.id).collect();
+            if !drawn.is_empty() {
+                draw_annotations(&mut doc, page_id, &drawn, "Annot")?;
+            }
+        }
+    }
+
+    let removed: HashSet<ObjectId> = all
+        .iter()
+        .filter(|a| targets.contains(&a.id))
+        .flat_map(|a| std::iter::once(a.id).chain(a.popup))
+        .collect();
+    for &page_id in pages.values() {
+        remove_annots(&mut doc, page_id, &removed)?;
+    }
+
+    prune_unreachable(&mut doc);
+    Ok((rebuild(doc, encryption)?, targets.len()))
+}
+
+/// Ajoute à `targets` les réponses (`/IRT`), directes ou non, à ses
+/// annotations et retourne celles ajoutées : une réponse orpheline
+/// réapparaîtrait comme une note isolée.
+pub fn add_replies(all: &[Annotation], targets: &mut HashSet<ObjectId>) -> HashSet<ObjectId> {
+    let mut replies: HashSet<ObjectId> = HashSet::new();
+    loop {
+        let found: Vec<ObjectId> = all
+            .iter()
+            .filter(|a| !targets.contains(&a.id) && a.reply_to.is_some_and(|r| targets.contains(&r)))
+            .map(|a| a.id)
+            .collect();
+        if found.is_empty() {
+            return replies;
+        }
+        targets.extend(&found);
+        replies.extend(found);
+    }
+}
+
+/// Annotations du document, dans l'ordre des pages puis de `/Annots`.
+pub fn annotations(doc: &Document) -> Vec<Annotation> {
+    let text = |dict: &Dictionary, key: &[u8]| -> Option<String> {
+        let value = dict.get(key).ok()?;
+        decode_text_string(doc.dereference(value).ok()?.1).ok()
+    };
+
+    let mut out = Vec::new();
+    for (page, page_id) in doc.get_pages() {
+        let annots = doc
+            .get_dictionary(page_id)
+            .ok()
+            .and_then(|p| p.get(b"Annots").ok())
+            .and_then(|a| doc.dereference(a).ok())
+            .and_then(|(_, a)| a.as_array().ok())
+            .cloned()
+            .unwrap_or_default();
+        for id in annots.iter().filter_map(|a| a.as_reference().ok()) {
+            let Ok(dict) = doc.get_dictionary(id) else {
+                continue;
+            };
+            let subtype = dict
+                .get(b"Subtype")
+                .and_then(Object::as_name)
+                .map(|s| String::from_utf8_lossy(s).into_owned())
+                .unwrap_or_default();
+            if subtype == "Popup" {
+                continue;
+            }
+            out.push(Annotation {
+                id,
+                page,
+                subtype,
+                author: text(dict, b"T"),
+                contents: text(dict, b"Contents"),
+                popup: dict.get(b"Popup").and_then(Object::as_reference).ok(),
+                reply_to: dict.get(b"IRT").and_then(Object::as_reference).ok(),
+            });
+        }
+    }
+    out
+}
@@ -0,0 +1,212 @@
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::error::{PdfError, Result};
+
+/// Archive ZIP construite en mémoire (format classique, sans ZIP64 :
+/// 65 535 fichiers et 4 Go au plus).
+pub struct ZipArchive {
+    data: Vec<u8>,
+    central: Vec<u8>,
+    entries: u16,
+    /// Heure et date MS-DOS communes à toutes les entrées.
+    timestamp: (u16, u16),
+}
+
+impl ZipArchive {
+    pub fn new() -> Self {
+        Self { data: Vec::new(), central: Vec::new(), entries: 0, timestamp: dos_timestamp(SystemTime::now()) }
+    }
+
+    /// Ajoute un fichier, compressé s'il y gagne (les PDFs le sont souvent déjà).
+    pub fn add(&mut self, name: &str, content: &[u8]) -> Result<()> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content)?;
+        let deflated = encoder.finish()?;
+        let (method, stored): (u16, &[u8]) =
+            if deflated.len() < content.len() { (8, &deflated) } else { (0, content) };
+
+        let offset = u32::try_from(self.data.len()).map_err(|_| PdfError::ArchiveTooLarge)?;
+        let size = u32::try_from(content.len()).map_err(|_| PdfError::ArchiveTooLarge)?;
+        let compressed = u32::try_from(stored.len()).map_err(|_| PdfError::ArchiveTooLarge)?;
+        self.entries = self.entries.checked_add(1).ok_or(PdfError::ArchiveTooLarge)?;
+        let crc = crc32fast::hash(content);
+        let (time, date) = self.timestamp;
+        let name = name.as_bytes();
+
+        // Champs communs à l'en-tête local et au répertoire central ; le bit
+        // 11 indique un nom en UTF-8.
+        let mut common = Vec::with_capacity(26);
+        for half in [20u16, 1 << 11, method, time, date] {
+            common.extend_from_slice(&half.to_le_bytes());
+        }
+        for word in [crc, compressed, size] {
+            common.extend_from_slice(&word.to_le_bytes());
+        }
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        self.data.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        self.data.extend_from_slice(&common);
+        self.data.extend_from_slice(name);
+        self.data.extend_from_slice(stored);
+
+        self.central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        self.central.extend_from_slice(&20u16.to_le_bytes());
+        self.central.extend_from_slice(&common);
+        // Commentaire, disque, attributs internes et externes
+        self.central.extend_from_slice(&[0; 10]);
+        self.central.extend_from_slice(&offset.to_le_bytes());
+        self.central.extend_from_slice(name);
+        Ok(())
+    }
+
+    /// Ajoute le répertoire central et retourne l'archive.
+    pub fn finish(mut self) -> Result<Vec<u8>> {
+        let offset = u32::try_from(self.data.len()).map_err(|_| PdfError::ArchiveTooLarge)?;
+        let size = u32::try_from(self.central.len()).map_err(|_| PdfError::ArchiveTooLarge)?;
+        self.data.append(&mut self.central);
+        self.data.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        self.data.extend_from_slice(&[0; 4]);
+        self.data.extend_from_slice(&self.entries.to_le_bytes());
+        self.data.extend_from_slice(&self.entries.to_le_bytes());
+        self.data.extend_from_slice(&size.to_le_bytes());
+        self.data.extend_from_slice(&offset.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        Ok(self.data)
+    }
+}
+
+impl Default for ZipArchive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Heure et date MS-DOS (UTC, précision de deux secondes).
+fn dos_timestamp(now: SystemTime) -> (u16, u16) {
+    let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Jours depuis 1970 → date civile (algorithme de H. Hinnant).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    // Le format ne couvre que 1980–2107.
+    let year = year.clamp(1980, 2107);
+    let time = ((rem / 3600) << 11 | (rem % 3600 / 60) << 5 | ((rem % 60) / 2)) as u16;
+    let date = ((year - 1980) << 9 | month << 5 | day) as u16;
+    (time, date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::DeflateDecoder;
+    use std::io::Read;
+
+    fn u16_at(data: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([data[at], data[at + 1]])
+    }
+
+    fn u32_at(data: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+    }
+
+    /// Relit l'archive par son répertoire central : (nom, méthode, contenu).
+    fn read_zip(data: &[u8]) -> Vec<(String, u16, Vec<u8>)> {
+        let end = data.len() - 22;
+        assert_eq!(u32_at(data, end), 0x0605_4b50);
+        let count = u16_at(data, end + 10) as usize;
+        assert_eq!(u16_at(data, end + 8) as usize, count);
+        let mut at = u32_at(data, end + 16) as usize;
+        assert_eq!(at + u32_at(data, end + 12) as usize, end);
+
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            assert_eq!(u32_at(data, at), 0x0201_4b50);
+            assert_eq!(u16_at(data, at + 8) & (1 << 11), 1 << 11, "nom UTF-8");
+            let method = u16_at(data, at + 10);
+            let crc = u32_at(data, at + 16);
+            let (compressed, size) = (u32_at(data, at + 20) as usize, u32_at(data, at + 24) as usize);
+            let name_len = u16_at(data, at + 28) as usize;
+            let name = String::from_utf8(data[at + 46..at + 46 + name_len].to_vec()).unwrap();
+            let local = u32_at(data, at + 42) as usize;
+
+            // L'en-tête local reprend les champs du répertoire central.
+            assert_eq!(u32_at(data, local), 0x0403_4b50);
+            assert_eq!(data[local + 4..local + 30], data[at + 6..at + 32]);
+            let start = local + 30 + name_len;
+            let stored = &data[start..start + compressed];
+            let content = match method {
+                0 => stored.to_vec(),
+                8 => {
+                    let mut out = Vec::new();
+                    DeflateDecoder::new(stored).read_to_end(&mut out).unwrap();
+                    out
+                }
+                other => panic!("méthode {}", other),
+            };
+            assert_eq!(content.len(), size);
+            assert_eq!(crc32fast::hash(&content), crc);
+            entries.push((name, method, content));
+            at += 46 + name_len;
+        }
+        entries
+    }
+
+    #[test]
+    fn round_trips_entries() {
+        let text = b"%PDF-1.5\n".repeat(200);
+        // Pseudo-aléatoire : incompressible, donc stocké tel quel.
+        let mut state = 0x2545_f491_u32;
+        let noise: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+
+        let mut archive = ZipArchive::new();
+        archive.add("texte.pdf", &text).unwrap();
+        archive.add("données/bruit.bin", &noise).unwrap();
+        archive.add("vide.txt", b"").unwrap();
+        let entries = read_zip(&archive.finish().unwrap());
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!((entries[0].0.as_str(), entries[0].1), ("texte.pdf", 8));
+        assert_eq!(entries[0].2, text);
+        assert_eq!((entries[1].0.as_str(), entries[1].1), ("données/bruit.bin", 0));
+        assert_eq!(entries[1].2, noise);
+        assert_eq!((entries[2].0.as_str(), entries[2].2.len()), ("vide.txt", 0));
+    }
+
+    #[test]
+    fn empty_archive_is_a_lone_end_record() {
+        let data = ZipArchive::new().finish().unwrap();
+        assert_eq!(data.len(), 22);
+        assert!(read_zip(&data).is_empty());
+    }
+
+    #[test]
+    fn converts_timestamps_to_ms_dos() {
+        let at = |secs: u64| dos_timestamp(UNIX_EPOCH + std::time::Duration::from_secs(secs));
+        // 2024-03-12 14:30:58 UTC
+        assert_eq!(at(1_710_253_858), (14 << 11 | 30 << 5 | 29, 44 << 9 | 3 << 5 | 12));
+        // 2000-02-29 23:59:59 UTC : secondes arrondies à deux près
+        assert_eq!(at(951_868_799), (23 << 11 | 59 << 5 | 29, 20 << 9 | 2 << 5 | 29));
+        // Avant 1980, l'année est ramenée au début du format.
+        assert_eq!(at(0).1 >> 9, 0);
+    }
+}
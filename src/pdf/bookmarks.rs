@@ -0,0 +1,367 @@
+use lopdf::{decode_text_string, text_string, Dictionary, Document, Object, ObjectId};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::utils::{find_catalog, finalize, load_document, prune_unreachable, resolve_dict};
+
+/// Entrée de la table des signets (`/Outlines`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bookmark {
+    pub title: String,
+    /// Page cible, 1-indexée ; `None` pour un signet sans destination.
+    pub page: Option<u32>,
+    /// Enfants dépliés à l'ouverture du document.
+    pub open: bool,
+    /// Couleur RVB, composantes entre 0 et 1.
+    pub color: Option<[f32; 3]>,
+    pub bold: bool,
+    pub italic: bool,
+    pub children: Vec<Bookmark>,
+}
+
+// Bits du champ /F d'une entrée (ISO 32000, table 153).
+const FLAG_ITALIC: i64 = 1;
+const FLAG_BOLD: i64 = 2;
+
+/// Lit l'arbre des signets du document.
+pub fn read_bookmarks(data: &[u8], password: Option<&str>) -> Result<Vec<Bookmark>> {
+    let doc = load_document(data, password)?;
+    find_catalog(&doc)
+        .ok_or_else(|| PdfError::InvalidFile("(catalogue introuvable)".to_string()))?;
+    Ok(outline(&doc))
+}
+
+/// Arbre des signets d'un document déjà chargé (vide s'il n'en a pas).
+pub fn outline(doc: &Document) -> Vec<Bookmark> {
+    let pages: HashMap<ObjectId, u32> =
+        doc.get_pages().into_iter().map(|(num, id)| (id, num)).collect();
+    let Some(outlines) = find_catalog(doc)
+        .and_then(|id| doc.get_dictionary(id).ok())
+        .and_then(|catalog| catalog.get(b"Outlines").ok())
+        .and_then(|o| resolve_dict(doc, o))
+    else {
+        return Vec::new();
+    };
+
+    let mut visited = HashSet::new();
+    read_items(doc, outlines, &pages, &mut visited)
+}
+
+/// Lit les enfants de `parent` en suivant la chaîne `/First` → `/Next`.
+/// `visited` protège des chaînes cycliques des fichiers corrompus.
+fn read_items(
+    doc: &Document,
+    parent: &Dictionary,
+    pages: &HashMap<ObjectId, u32>,
+    visited: &mut HashSet<ObjectId>,
+) -> Vec<Bookmark> {
+    let mut items = Vec::new();
+    let mut next = parent.get(b"First").and_then(Object::as_reference).ok();
+
+    while let Some(id) = next.filter(|id| visited.insert(*id)) {
+        let Ok(item) = doc.get_dictionary(id) else {
+            break;
+        };
+        let title = item
+            .get(b"Title")
+            .ok()
+            .and_then(|t| doc.dereference(t).ok())
+            .and_then(|(_, t)| decode_text_string(t).ok())
+            .unwrap_or_default();
+        let color = item
+            .get(b"C")
+            .and_then(Object::as_array)
+            .ok()
+            .and_then(|c| match c.as_slice() {
+                [r, g, b] => Some([r.as_float().ok()?, g.as_float().ok()?, b.as_float().ok()?]),
+                _ => None,
+            });
+        let flags = item.get(b"F").and_then(Object::as_i64).unwrap_or(0);
+
+        items.push(Bookmark {
+            title,
+            page: destination_page(doc, item, pages),
+            open: item.get(b"Count").and_then(Object::as_i64).unwrap_or(0) > 0,
+            color,
+            bold: flags & FLAG_BOLD != 0,
+            italic: flags & FLAG_ITALIC != 0,
+            children: read_items(doc, item, pages, visited),
+        });
+        next = item.get(b"Next").and_then(Object::as_reference).ok();
+    }
+    items
+}
+
+/// Page cible d'une entrée : `/Dest` explicite ou nommée, ou action `GoTo`.
+fn destination_page(doc: &Document, item: &Dictionary, pages: &HashMap<ObjectId, u32>) -> Option<u32> {
+    let mut dest = match item.get(b"Dest") {
+        Ok(dest) => dest,
+        Err(_) => {
+            let action = resolve_dict(doc, item.get(b"A").ok()?)?;
+            if action.get(b"S").and_then(Object::as_name).ok()? != b"GoTo" {
+                return None;
+            }
+            action.get(b"D").ok()?
+        }
+    };
+
+    // Une destination nommée renvoie vers un tableau, éventuellement
+    // enveloppé dans un dictionnaire `/D` : quelques indirections suffisent.
+    for _ in 0..4 {
+        dest = match dest {
+            Object::Reference(id) => doc.get_object(*id).ok()?,
+            Object::Array(arr) => return pages.get(&arr.first()?.as_reference().ok()?).copied(),
+            Object::Dictionary(dict) => dict.get(b"D").ok()?,
+            Object::Name(name) => named_destination(doc, name)?,
+            Object::String(name, _) => named_destination(doc, name)?,
+            _ => return None,
+        };
+    }
+    None
+}
+
+/// Résout une destination nommée : dictionnaire `/Dests` du catalogue
+/// (PDF 1.1) ou arbre `/Names /Dests`.
+pub fn named_destination<'a>(doc: &'a Document, name: &[u8]) -> Option<&'a Object> {
+    let catalog = doc.get_dictionary(find_catalog(doc)?).ok()?;
+    if let Some(dest) = catalog
+        .get(b"Dests")
+        .ok()
+        .and_then(|d| resolve_dict(doc, d))
+        .and_then(|d| d.get(name).ok())
+    {
+        return Some(dest);
+    }
+    let names = resolve_dict(doc, catalog.get(b"Names").ok()?)?;
+    find_in_name_tree(doc, names.get(b"Dests").ok()?, name, 0)
+}
+
+fn find_in_name_tree<'a>(doc: &'a Document, node: &'a Object, name: &[u8], depth: usize) -> Option<&'a Object> {
+    let dict = resolve_dict(doc, node).filter(|_| depth < 32)?;
+    if let Ok(entries) = dict.get(b"Names").and_then(Object::as_array) {
+        let found = entries
+            .chunks(2)
+            .find(|pair| pair[0].as_str().ok() == Some(name))
+            .and_then(|pair| pair.get(1));
+        if found.is_some() {
+            return found;
+        }
+    }
+    dict.get(b"Kids")
+        .and_then(Object::as_array)
+        .ok()?
+        .iter()
+        .find_map(|kid| find_in_name_tree(doc, kid, name, depth + 1))
+}
+
+/// Remplace l'arbre des signets du document par `bookmarks`. Une liste
+/// vide retire les signets.
+pub fn write_bookmarks(
+    data: &[u8],
+    bookmarks: &[Bookmark],
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut doc = load_document(data, password)?;
+    let catalog_id = find_catalog(&doc)
+        .ok_or_else(|| PdfError::InvalidFile("(catalogue introuvable)".to_string()))?;
+
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    check_pages(bookmarks, pages.len() as u32)?;
+
+    let outlines = if bookmarks.is_empty() {
+        None
+    } else {
+        let root_id = doc.new_object_id();
+        let (first, last, count) = build_items(&mut doc, bookmarks, root_id, &pages);
+        doc.objects.insert(
+            root_id,
+            Object::Dictionary(Dictionary::from_iter(vec![
+                ("Type", Object::Name(b"Outlines".to_vec())),
+                ("First", Object::Reference(first)),
+                ("Last", Object::Reference(last)),
+                ("Count", Object::Integer(count)),
+            ])),
+        );
+        Some(root_id)
+    };
+
+    let catalog = doc.get_object_mut(catalog_id)?.as_dict_mut()?;
+    match outlines {
+        Some(root_id) => {
+            catalog.set("Outlines", Object::Reference(root_id));
+            // Affiche le panneau des signets à l'ouverture, sauf choix contraire du document.
+            if !catalog.has(b"PageMode") {
+                catalog.set("PageMode", Object::Name(b"UseOutlines".to_vec()));
+            }
+        }
+        None => {
+            catalog.remove(b"Outlines");
+            if catalog.get(b"PageMode").and_then(Object::as_name).ok() == Some(b"UseOutlines".as_slice()) {
+                catalog.remove(b"PageMode");
+            }
+        }
+    }
+
+    // L'ancien arbre n'est plus référencé.
+    prune_unreachable(&mut doc);
+    finalize(&mut doc, catalog_id, encryption)
+}
+
+fn check_pages(bookmarks: &[Bookmark], total: u32) -> Result<()> {
+    for bookmark in bookmarks {
+        if let Some(page) = bookmark.page {
+            if page == 0 || page > total {
+                return Err(PdfError::PageOutOfRange { page, total });
+            }
+        }
+        check_pages(&bookmark.children, total)?;
+    }
+    Ok(())
+}
+
+/// Crée les entrées de `items` sous `parent` et retourne la première, la
+/// dernière et le nombre d'entrées visibles (le `/Count` du parent ouvert).
+fn build_items(
+    doc: &mut Document,
+    items: &[Bookmark],
+    parent: ObjectId,
+    pages: &[ObjectId],
+) -> (ObjectId, ObjectId, i64) {
+    let ids: Vec<ObjectId> = items.iter().map(|_| doc.new_object_id()).collect();
+    let mut visible = 0;
+
+    for (i, item) in items.iter().enumerate() {
+        let mut dict = Dictionary::new();
+        dict.set("Title", text_string(&item.title));
+        dict.set("Parent", Object::Reference(parent));
+        if i > 0 {
+            dict.set("Prev", Object::Reference(ids[i - 1]));
+        }
+        if let Some(&next) = ids.get(i + 1) {
+            dict.set("Next", Object::Reference(next));
+        }
+        if let Some(page) = item.page {
+            dict.set(
+                "Dest",
+                Object::Array(vec![
+                    Object::Reference(pages[page as usize - 1]),
+                    Object::Name(b"Fit".to_vec()),
+                ]),
+            );
+        }
+        if let Some(color) = item.color {
+            dict.set("C", Object::Array(color.iter().map(|&c| Object::Real(c)).collect()));
+        }
+        let flags = if item.italic { FLAG_ITALIC } else { 0 } | if item.bold { FLAG_BOLD } else { 0 };
+        if flags != 0 {
+            dict.set("F", Object::Integer(flags));
+        }
+
+        visible += 1;
+        if !item.children.is_empty() {
+            let (first, last, count) = build_items(doc, &item.children, ids[i], pages);
+            dict.set("First", Object::Reference(first));
+            dict.set("Last", Object::Reference(last));
+            // Négatif : entrée fermée, avec `count` descendants visibles une fois ouverte.
+            dict.set("Count", Object::Integer(if item.open { count } else { -count }));
+            if item.open {
+                visible += count;
+            }
+        }
+        doc.objects.insert(ids[i], Object::Dictionary(dict));
+    }
+
+    (ids[0], ids[ids.len() - 1], visible)
+}
+
+// ── Représentation JSON ───────────────────────────────────────────────────────
+
+/// Arbre JSON des signets : `level` vaut 1 pour les entrées de premier niveau.
+pub fn bookmarks_to_json(bookmarks: &[Bookmark]) -> Value {
+    fn node(bookmark: &Bookmark, level: usize) -> Value {
+        json!({
+            "title": bookmark.title,
+            "level": level,
+            "page": bookmark.page,
+            "open": bookmark.open,
+            "color": bookmark.color,
+            "bold": bookmark.bold,
+            "italic": bookmark.italic,
+            "children": bookmark.children.iter().map(|c| node(c, level + 1)).collect::<Vec<_>>(),
+        })
+    }
+    Value::Array(bookmarks.iter().map(|b| node(b, 1)).collect())
+}
+
+/// Lit un arbre JSON de la forme produite par [`bookmarks_to_json`].
+/// Seul `title` est obligatoire ; `level` est ignoré, la hiérarchie
+/// venant de `children`.
+pub fn bookmarks_from_json(input: &str) -> Result<Vec<Bookmark>> {
+    let value: Value =
+        serde_json::from_str(input).map_err(|e| PdfError::InvalidBookmarks(e.to_string()))?;
+    parse_items(&value, "$")
+}
+
+fn parse_items(value: &Value, path: &str) -> Result<Vec<Bookmark>> {
+    let invalid = |msg: String| PdfError::InvalidBookmarks(msg);
+    let Value::Array(items) = value else {
+        return Err(invalid(format!("{} : tableau attendu", path)));
+    };
+
+    let mut bookmarks = Vec::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        let path = format!("{}[{}]", path, i);
+        let Value::Object(fields) = item else {
+            return Err(invalid(format!("{} : objet attendu", path)));
+        };
+        let flag = |key: &str| match fields.get(key) {
+            None | Some(Value::Null) => Ok(false),
+            Some(Value::Bool(b)) => Ok(*b),
+            Some(_) => Err(invalid(format!("{}.{} : booléen attendu", path, key))),
+        };
+
+        let title = match fields.get("title") {
+            Some(Value::String(s)) => s.clone(),
+            _ => return Err(invalid(format!("{}.title : texte attendu", path))),
+        };
+        let page = match fields.get("page") {
+            None | Some(Value::Null) => None,
+            Some(v) => match v.as_u64().and_then(|n| u32::try_from(n).ok()) {
+                Some(n) => Some(n),
+                None => return Err(invalid(format!("{}.page : numéro de page attendu", path))),
+            },
+        };
+        let color = match fields.get("color") {
+            None | Some(Value::Null) => None,
+            Some(Value::Array(c)) if c.len() == 3 => {
+                let mut rgb = [0.0; 3];
+                for (slot, v) in rgb.iter_mut().zip(c) {
+                    match v.as_f64().filter(|x| (0.0..=1.0).contains(x)) {
+                        Some(x) => *slot = x as f32,
+                        None => return Err(invalid(format!("{}.color : composantes entre 0 et 1 attendues", path))),
+                    }
+                }
+                Some(rgb)
+            }
+            Some(_) => return Err(invalid(format!("{}.color : [r, g, b] attendu", path))),
+        };
+        let children = match fields.get("children") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(v) => parse_items(v, &format!("{}.children", path))?,
+        };
+
+        bookmarks.push(Bookmark {
+            title,
+            page,
+            open: flag("open")?,
+            color,
+            bold: flag("bold")?,
+            italic: flag("italic")?,
+            children,
+        });
+    }
+    Ok(bookmarks)
+}
@@ -0,0 +1,464 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::encoding::{glyph_char, parse_to_unicode, BaseEncoding};
+use super::error::Result;
+use super::font::char_width;
+use super::utils::{multiply, resolve_dict, transform_point, transform_rect, Matrix};
+
+pub const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// Profondeur maximale d'imbrication des Form XObjects (protège des cycles).
+pub const MAX_FORM_DEPTH: usize = 16;
+
+// ── Polices ───────────────────────────────────────────────────────────────────
+
+/// Métriques d'une police, suffisantes pour placer ses glyphes.
+#[derive(Debug, Clone)]
+pub struct Font {
+    /// Chasses par code, en unités d'espace texte pour un corps de 1.
+    widths: HashMap<u32, f32>,
+    default_width: f32,
+    /// Police composite (Type0) : codes sur deux octets.
+    composite: bool,
+    /// Hauteurs au-dessus et au-dessous de la ligne de base, pour un corps de 1.
+    ascent: f32,
+    descent: f32,
+    /// Texte de chaque code : `/ToUnicode`, sinon encodage de la police.
+    unicode: HashMap<u32, String>,
+}
+
+impl Font {
+    /// Lit les chasses de la police (`/Widths`, `/W` des polices
+    /// composites), les hauteurs de son descripteur et le texte de ses
+    /// codes. Une police standard sans `/Widths` reçoit celles de Courier
+    /// ou de Helvetica.
+    pub fn load(doc: &Document, dict: &Dictionary) -> Self {
+        let number = |obj: &Object| doc.dereference(obj).ok().and_then(|(_, o)| o.as_float().ok());
+        let array = |d: &Dictionary, key: &[u8]| -> Vec<Object> {
+            d.get(key)
+                .ok()
+                .and_then(|o| doc.dereference(o).ok())
+                .and_then(|(_, o)| o.as_array().ok())
+                .cloned()
+                .unwrap_or_default()
+        };
+        let subtype = dict.get(b"Subtype").and_then(Object::as_name).unwrap_or_default();
+        let composite = subtype == b"Type0";
+
+        let mut widths = HashMap::new();
+        let mut unicode = HashMap::new();
+        let (descriptor, default_width) = if composite {
+            let descendant = array(dict, b"DescendantFonts")
+                .first()
+                .and_then(|d| resolve_dict(doc, d))
+                .cloned()
+                .unwrap_or_default();
+            let w = array(&descendant, b"W");
+            let mut i = 0;
+            // `c [w1 w2 …]` ou `c_first c_last w`
+            while i + 1 < w.len() {
+                let first = number(&w[i]).unwrap_or(0.0) as u32;
+                match doc.dereference(&w[i + 1]).map(|(_, o)| o) {
+                    Ok(Object::Array(list)) => {
+                        for (k, v) in list.iter().enumerate() {
+                            widths.insert(first + k as u32, number(v).unwrap_or(0.0) / 1000.0);
+                        }
+                        i += 2;
+                    }
+                    _ => {
+                        let last = number(&w[i + 1]).unwrap_or(0.0) as u32;
+                        let width = w.get(i + 2).and_then(number).unwrap_or(0.0) / 1000.0;
+                        for code in first..=last.min(first.saturating_add(0xFFFF)) {
+                            widths.insert(code, width);
+                        }
+                        i += 3;
+                    }
+                }
+            }
+            // CMaps prédéfinies dont les codes sont déjà de l'Unicode
+            let encoding = dict.get(b"Encoding").and_then(Object::as_name).unwrap_or_default();
+            let encoding = String::from_utf8_lossy(encoding);
+            if encoding.contains("UCS2") || encoding.contains("UTF16") {
+                for code in widths.keys().copied().chain(0x20..0x7F) {
+                    if let Some(c) = char::from_u32(code) {
+                        unicode.insert(code, c.to_string());
+                    }
+                }
+            }
+            let default = descendant.get(b"DW").ok().and_then(number).unwrap_or(1000.0) / 1000.0;
+            (descendant.get(b"FontDescriptor").ok().and_then(|d| resolve_dict(doc, d)).cloned(), default)
+        } else {
+            // Type3 : chasses en espace glyphe, converties par /FontMatrix.
+            let scale = if subtype == b"Type3" {
+                array(dict, b"FontMatrix").first().and_then(number).unwrap_or(0.001)
+            } else {
+                0.001
+            };
+            let first = dict.get(b"FirstChar").ok().and_then(number).unwrap_or(0.0) as u32;
+            for (k, v) in array(dict, b"Widths").iter().enumerate() {
+                widths.insert(first + k as u32, number(v).unwrap_or(0.0) * scale);
+            }
+            let descriptor = dict.get(b"FontDescriptor").ok().and_then(|d| resolve_dict(doc, d)).cloned();
+            let missing = descriptor
+                .as_ref()
+                .and_then(|d| d.get(b"MissingWidth").ok())
+                .and_then(number)
+                .map_or(0.5, |w| w * scale);
+            for (code, c) in simple_encoding(doc, dict, subtype) {
+                unicode.insert(code, c.to_string());
+            }
+            if widths.is_empty() {
+                let base = dict.get(b"BaseFont").and_then(Object::as_name).unwrap_or_default();
+                let courier = String::from_utf8_lossy(base).contains("Courier");
+                for code in 0..=255u8 {
+                    let width = if courier { 0.6 } else { f32::from(char_width(code)) / 1000.0 };
+                    widths.insert(u32::from(code), width);
+                }
+            }
+            (descriptor, missing)
+        };
+
+        let metric = |key: &[u8]| {
+            descriptor
+                .as_ref()
+                .and_then(|d| d.get(key).ok())
+                .and_then(number)
+                .map(|v| v / 1000.0)
+                .filter(|v| v.abs() > 0.01 && v.abs() < 2.0)
+        };
+        let ascent = metric(b"Ascent").filter(|&a| a > 0.0).unwrap_or(0.8);
+        let descent = metric(b"Descent").filter(|&d| d < 0.0).unwrap_or(-0.2);
+        let to_unicode = dict
+            .get(b"ToUnicode")
+            .and_then(Object::as_reference)
+            .and_then(|id| doc.get_object(id))
+            .and_then(Object::as_stream);
+        if let Ok(stream) = to_unicode {
+            unicode.extend(parse_to_unicode(&stream.decompressed_content().unwrap_or_else(|_| stream.content.clone())));
+        }
+        Font { widths, default_width, composite, ascent, descent, unicode }
+    }
+
+    /// Découpe une chaîne en codes de caractère et leurs octets.
+    pub fn codes<'a>(&self, bytes: &'a [u8]) -> Vec<(u32, &'a [u8])> {
+        let size = if self.composite { 2 } else { 1 };
+        bytes
+            .chunks(size)
+            .map(|c| (c.iter().fold(0u32, |acc, &b| (acc << 8) | u32::from(b)), c))
+            .collect()
+    }
+
+    /// Chasse du code, en unités d'espace texte pour un corps de 1.
+    pub fn width(&self, code: u32) -> f32 {
+        self.widths.get(&code).copied().unwrap_or(self.default_width)
+    }
+
+    /// Texte du code, vide s'il est inconnu.
+    pub fn text(&self, code: u32) -> &str {
+        self.unicode.get(&code).map_or("", String::as_str)
+    }
+}
+
+/// Caractères des codes d'une police simple : encodage de base (nommé ou
+/// `/BaseEncoding`) modifié par `/Differences`.
+fn simple_encoding(doc: &Document, dict: &Dictionary, subtype: &[u8]) -> Vec<(u32, char)> {
+    let encoding = dict.get(b"Encoding").ok().map(|e| doc.dereference(e).map_or(e, |(_, o)| o));
+    let (base, differences) = match encoding {
+        Some(Object::Name(name)) => (BaseEncoding::from_name(name), None),
+        Some(Object::Dictionary(enc)) => (
+            enc.get(b"BaseEncoding").and_then(Object::as_name).ok().and_then(BaseEncoding::from_name),
+            enc.get(b"Differences").and_then(Object::as_array).ok(),
+        ),
+        _ => (None, None),
+    };
+    // Sans encodage, les polices TrueType suivent en pratique WinAnsi.
+    let base = base.unwrap_or(if subtype == b"TrueType" { BaseEncoding::WinAnsi } else { BaseEncoding::Standard });
+
+    let mut chars: Vec<Option<char>> = (0..=255u8).map(|b| base.decode(b)).collect();
+    let mut code = 0usize;
+    for item in differences.into_iter().flatten() {
+        match item {
+            Object::Integer(n) => code = usize::try_from(*n).unwrap_or(256),
+            Object::Name(name) => {
+                if let Some(slot) = chars.get_mut(code) {
+                    *slot = glyph_char(&String::from_utf8_lossy(name));
+                }
+                code += 1;
+            }
+            _ => {}
+        }
+    }
+    chars.into_iter().enumerate().filter_map(|(code, c)| Some((code as u32, c?))).collect()
+}
+
+/// Polices déjà lues, par objet : une page en référence souvent peu,
+/// mais des centaines de fois.
+#[derive(Debug, Default)]
+pub struct FontCache(HashMap<ObjectId, Rc<Font>>);
+
+impl FontCache {
+    fn get(&mut self, doc: &Document, resources: &Dictionary, name: &[u8]) -> Option<Rc<Font>> {
+        let fonts = resolve_dict(doc, resources.get(b"Font").ok()?)?;
+        match fonts.get(name).ok()? {
+            Object::Reference(id) => {
+                if let Some(font) = self.0.get(id) {
+                    return Some(font.clone());
+                }
+                let font = Rc::new(Font::load(doc, doc.get_dictionary(*id).ok()?));
+                self.0.insert(*id, font.clone());
+                Some(font)
+            }
+            Object::Dictionary(dict) => Some(Rc::new(Font::load(doc, dict))),
+            _ => None,
+        }
+    }
+}
+
+// ── État graphique ────────────────────────────────────────────────────────────
+
+/// Partie de l'état graphique sauvegardée par `q` et restaurée par `Q`.
+#[derive(Debug, Clone)]
+pub struct GraphicsState {
+    pub ctm: Matrix,
+    font: Option<Rc<Font>>,
+    size: f32,
+    char_spacing: f32,
+    word_spacing: f32,
+    /// `Tz` divisé par 100.
+    scaling: f32,
+    leading: f32,
+    rise: f32,
+}
+
+impl GraphicsState {
+    fn new(ctm: Matrix) -> Self {
+        GraphicsState {
+            ctm,
+            font: None,
+            size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            scaling: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+        }
+    }
+}
+
+/// Glyphe affiché par un opérateur de texte.
+#[derive(Debug, Clone)]
+pub struct Glyph {
+    /// Texte du glyphe, vide si la police ne permet pas de le connaître.
+    pub text: String,
+    /// Début de la ligne de base, en espace utilisateur de la page.
+    pub origin: (f32, f32),
+    /// Direction du texte, en degrés (0 : horizontal de gauche à droite).
+    pub angle: f32,
+    /// Corps apparent, en points de la page.
+    pub size: f32,
+    /// Chasse, en points de la page le long de la ligne de base.
+    pub width: f32,
+    /// Octets du code dans la chaîne d'origine.
+    pub bytes: Vec<u8>,
+    /// Boîte englobante en espace utilisateur de la page.
+    pub bbox: [f32; 4],
+    /// Opérande de `TJ` produisant le même déplacement que le glyphe :
+    /// remplacer le glyphe par ce nombre laisse la suite du texte en place.
+    pub advance: f32,
+}
+
+/// Interprète l'état d'un flux de contenu : matrices, état du texte,
+/// position des glyphes. N'affiche rien ; l'appelant parcourt les
+/// opérations et décide quoi faire du texte et des XObjects.
+#[derive(Debug, Clone)]
+pub struct ContentState {
+    pub gs: GraphicsState,
+    stack: Vec<GraphicsState>,
+    tm: Matrix,
+    tlm: Matrix,
+    /// Ressources du flux (`/Font`, `/XObject`), copiées.
+    pub resources: Dictionary,
+}
+
+impl ContentState {
+    pub fn new(ctm: Matrix, resources: Dictionary) -> Self {
+        ContentState { gs: GraphicsState::new(ctm), stack: Vec::new(), tm: IDENTITY, tlm: IDENTITY, resources }
+    }
+
+    /// État initial du contenu d'un Form XObject dessiné dans l'état courant.
+    /// Un formulaire sans `/Resources` utilise celles de son parent.
+    pub fn form(&self, doc: &Document, form: &Stream) -> Self {
+        let matrix = matrix_of(form.dict.get(b"Matrix").ok()).unwrap_or(IDENTITY);
+        let resources = form
+            .dict
+            .get(b"Resources")
+            .ok()
+            .and_then(|r| resolve_dict(doc, r))
+            .cloned()
+            .unwrap_or_else(|| self.resources.clone());
+        let mut gs = self.gs.clone();
+        gs.ctm = multiply(matrix, self.gs.ctm);
+        ContentState { gs, stack: Vec::new(), tm: IDENTITY, tlm: IDENTITY, resources }
+    }
+
+    /// Boîte de `form` (`/BBox`) en espace utilisateur de la page, à
+    /// appeler sur l'état retourné par [`ContentState::form`].
+    pub fn form_bbox(&self, form: &Stream) -> Option<[f32; 4]> {
+        let bbox: [f32; 4] = numbers(form.dict.get(b"BBox").ok()?)?.try_into().ok()?;
+        Some(transform_rect(self.gs.ctm, bbox))
+    }
+
+    /// Boîte d'une image dessinée dans l'état courant (carré unité).
+    pub fn image_bbox(&self) -> [f32; 4] {
+        transform_rect(self.gs.ctm, [0.0, 0.0, 1.0, 1.0])
+    }
+
+    /// XObject nommé dans les ressources.
+    pub fn xobject<'a>(&self, doc: &'a Document, name: &[u8]) -> Option<(ObjectId, &'a Stream)> {
+        let xobjects = resolve_dict(doc, self.resources.get(b"XObject").ok()?)?;
+        let id = xobjects.get(name).and_then(Object::as_reference).ok()?;
+        Some((id, doc.get_object(id).and_then(Object::as_stream).ok()?))
+    }
+
+    /// Applique un opérateur d'état (`q`, `cm`, `BT`, `Tf`, `Td`…). Pour
+    /// `'` et `"`, seul le passage à la ligne est appliqué : le texte
+    /// s'affiche ensuite avec [`ContentState::show`].
+    pub fn apply(&mut self, doc: &Document, fonts: &mut FontCache, op: &Operation) {
+        let args: Vec<f32> = op.operands.iter().filter_map(|o| o.as_float().ok()).collect();
+        let arg = |i: usize| args.get(i).copied().unwrap_or(0.0);
+        match op.operator.as_str() {
+            "q" => self.stack.push(self.gs.clone()),
+            "Q" => {
+                if let Some(gs) = self.stack.pop() {
+                    self.gs = gs;
+                }
+            }
+            "cm" => {
+                if let Ok(m) = <[f32; 6]>::try_from(args.as_slice()) {
+                    self.gs.ctm = multiply(m, self.gs.ctm);
+                }
+            }
+            "BT" => {
+                self.tm = IDENTITY;
+                self.tlm = IDENTITY;
+            }
+            "Tm" => {
+                if let Ok(m) = <[f32; 6]>::try_from(args.as_slice()) {
+                    self.tm = m;
+                    self.tlm = m;
+                }
+            }
+            "Td" => self.move_line(arg(0), arg(1)),
+            "TD" => {
+                self.gs.leading = -arg(1);
+                self.move_line(arg(0), arg(1));
+            }
+            "T*" | "'" => self.move_line(0.0, -self.gs.leading),
+            "\"" => {
+                self.gs.word_spacing = arg(0);
+                self.gs.char_spacing = arg(1);
+                self.move_line(0.0, -self.gs.leading);
+            }
+            "Tc" => self.gs.char_spacing = arg(0),
+            "Tw" => self.gs.word_spacing = arg(0),
+            "Tz" => self.gs.scaling = arg(0) / 100.0,
+            "TL" => self.gs.leading = arg(0),
+            "Ts" => self.gs.rise = arg(0),
+            "Tf" => {
+                let name = op.operands.first().and_then(|o| o.as_name().ok()).unwrap_or_default();
+                self.gs.font = fonts.get(doc, &self.resources, name);
+                self.gs.size = arg(0);
+            }
+            _ => {}
+        }
+    }
+
+    fn move_line(&mut self, tx: f32, ty: f32) {
+        self.tlm = multiply([1.0, 0.0, 0.0, 1.0, tx, ty], self.tlm);
+        self.tm = self.tlm;
+    }
+
+    /// Place les glyphes de la chaîne et avance la matrice de texte.
+    /// Vide si aucune police n'est sélectionnée.
+    pub fn show(&mut self, bytes: &[u8]) -> Vec<Glyph> {
+        let Some(font) = self.gs.font.clone() else {
+            return Vec::new();
+        };
+        let gs = &self.gs;
+        let scale = [gs.size * gs.scaling, 0.0, 0.0, gs.size, 0.0, gs.rise];
+        let mut glyphs = Vec::new();
+        for (code, code_bytes) in font.codes(bytes) {
+            let width = font.width(code);
+            let trm = multiply(multiply(scale, self.tm), gs.ctm);
+            // L'espacement des mots ne s'applique qu'au code 32 sur un octet.
+            let spacing = gs.char_spacing + if code_bytes == b" " { gs.word_spacing } else { 0.0 };
+            let advance = if gs.size != 0.0 { -(width + spacing / gs.size) * 1000.0 } else { 0.0 };
+            let origin = transform_point(trm, 0.0, 0.0);
+            let up = transform_point(trm, 0.0, 1.0);
+            let end = transform_point(trm, width, 0.0);
+            glyphs.push(Glyph {
+                text: font.text(code).to_string(),
+                origin,
+                angle: trm[1].atan2(trm[0]).to_degrees(),
+                size: (up.0 - origin.0).hypot(up.1 - origin.1),
+                width: (end.0 - origin.0).hypot(end.1 - origin.1),
+                bytes: code_bytes.to_vec(),
+                bbox: transform_rect(trm, [0.0, font.descent, width, font.ascent]),
+                advance,
+            });
+            let tx = (width * gs.size + spacing) * gs.scaling;
+            self.tm = multiply([1.0, 0.0, 0.0, 1.0, tx, 0.0], self.tm);
+        }
+        glyphs
+    }
+
+    /// Décalage d'un nombre de `TJ`, en millièmes d'unité de texte.
+    pub fn adjust(&mut self, amount: f32) {
+        let tx = -amount / 1000.0 * self.gs.size * self.gs.scaling;
+        self.tm = multiply([1.0, 0.0, 0.0, 1.0, tx, 0.0], self.tm);
+    }
+}
+
+fn numbers(obj: &Object) -> Option<Vec<f32>> {
+    obj.as_array().ok()?.iter().map(|v| v.as_float().ok()).collect()
+}
+
+fn matrix_of(obj: Option<&Object>) -> Option<Matrix> {
+    numbers(obj?)?.try_into().ok()
+}
+
+// ── Lecture et écriture des flux ──────────────────────────────────────────────
+
+/// Décode les opérations d'un flux de contenu.
+pub fn decode(data: &[u8]) -> Result<Vec<Operation>> {
+    Ok(Content::decode(data)?.operations)
+}
+
+/// Encode des opérations en flux de contenu. Les images en ligne, que
+/// lopdf lit comme un opérateur `BI` portant un flux, sont réécrites sous
+/// leur forme `BI … ID … EI`.
+pub fn encode(operations: &[Operation]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    for op in operations {
+        match (op.operator.as_str(), op.operands.first()) {
+            ("BI", Some(Object::Stream(image))) => {
+                let entries = image
+                    .dict
+                    .iter()
+                    .flat_map(|(key, value)| [Object::Name(key.clone()), value.clone()])
+                    .collect();
+                out.extend_from_slice(b"BI ");
+                out.extend(Content { operations: vec![Operation::new("ID", entries)] }.encode()?);
+                out.push(b' ');
+                out.extend_from_slice(&image.content);
+                out.extend_from_slice(b"\nEI");
+            }
+            _ => out.extend(Content { operations: vec![op.clone()] }.encode()?),
+        }
+        out.push(b'\n');
+    }
+    Ok(out)
+}
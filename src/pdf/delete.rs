@@ -1,14 +1,18 @@
-use lopdf::{Document, ObjectId};
+use lopdf::ObjectId;
 use std::collections::HashSet;
 
+use super::encrypt::Encryption;
 use super::error::{PdfError, Result};
-use super::utils::{load_document, 
-    copy_objects, find_catalog, find_pages_root, finalize,
-    insert_catalog, insert_pages_node, set_parent,
-};
-
-pub fn delete_pages(data: &[u8], page_numbers: &[u32]) -> Result<Vec<u8>> {
-    let mut src = load_document(data)?;
+use super::extract::pages_to_document;
+use super::utils::{inline_inherited_attributes, load_document};
+
+pub fn delete_pages(
+    data: &[u8],
+    page_numbers: &[u32],
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut src = load_document(data, password)?;
     src.decompress();
 
     let all_pages = src.get_pages();
@@ -35,29 +39,10 @@ pub fn delete_pages(data: &[u8], page_numbers: &[u32]) -> Result<Vec<u8>> {
         .collect();
     kept.sort_by_key(|(n, _)| *n);
     let kept_ids: Vec<ObjectId> = kept.into_iter().map(|(_, id)| id).collect();
+    // Le nœud Pages racine n'est pas recopié : ses attributs hérités non plus.
+    inline_inherited_attributes(&mut src, &kept_ids);
 
-    let mut out = Document::with_version("1.5");
-
-    // Exclut Catalog, Pages root, et les pages supprimées
-    let deleted_ids: HashSet<ObjectId> = src
-        .get_pages()
-        .into_iter()
-        .filter(|(n, _)| to_delete.contains(n))
-        .map(|(_, id)| id)
-        .collect();
-
-    let excluded: HashSet<ObjectId> = [find_catalog(&src), find_pages_root(&src)]
-        .into_iter()
-        .flatten()
-        .chain(deleted_ids)
-        .collect();
-
-    let id_map = copy_objects(&src, &mut out, &excluded);
-
-    let new_kept_ids: Vec<ObjectId> = kept_ids.iter().map(|id| id_map[id]).collect();
-    let pages_id = insert_pages_node(&mut out, &new_kept_ids, new_kept_ids.len() as i64);
-    set_parent(&mut out, &new_kept_ids, pages_id);
-
-    let catalog_id = insert_catalog(&mut out, pages_id);
-    finalize(&mut out, catalog_id)
+    // pages_to_document écarte les pages supprimées et retire les liens
+    // internes qui les visaient.
+    pages_to_document(&src, &kept_ids, encryption)
 }
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+use super::font::WIN_ANSI_EXTRA;
+
+/// Encodages de base des polices simples (ISO 32000, annexe D).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseEncoding {
+    Standard,
+    WinAnsi,
+    MacRoman,
+}
+
+impl BaseEncoding {
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"StandardEncoding" => Some(Self::Standard),
+            b"WinAnsiEncoding" => Some(Self::WinAnsi),
+            b"MacRomanEncoding" => Some(Self::MacRoman),
+            _ => None,
+        }
+    }
+
+    /// Caractère désigné par un octet, `None` pour une position vide.
+    pub fn decode(self, byte: u8) -> Option<char> {
+        match self {
+            Self::WinAnsi => match byte {
+                0x20..=0x7E | 0xA0..=0xFF => Some(char::from(byte)),
+                0x7F..=0x9F => WIN_ANSI_EXTRA.iter().find(|&&(_, b)| b == byte).map(|&(c, _)| c),
+                _ => None,
+            },
+            Self::MacRoman => match byte {
+                0x20..=0x7E => Some(char::from(byte)),
+                0x80..=0xFF => MAC_ROMAN_HIGH.iter().flat_map(|s| s.chars()).nth(usize::from(byte - 0x80)),
+                _ => None,
+            },
+            Self::Standard => match byte {
+                b'\'' => Some('\u{2019}'),
+                b'`' => Some('\u{2018}'),
+                0x20..=0x7E => Some(char::from(byte)),
+                _ => STANDARD_HIGH.iter().find(|&&(b, _)| b == byte).and_then(|&(_, name)| glyph_char(name)),
+            },
+        }
+    }
+}
+
+/// Caractère d'un nom de glyphe : noms de la liste Adobe couvrant les
+/// encodages latins, `uniXXXX` et `uXXXX[XX]`.
+pub fn glyph_char(name: &str) -> Option<char> {
+    // Variantes stylistiques : `a.sc`, `one.oldstyle`
+    let name = name.split('.').next().unwrap_or(name);
+    if let Ok(i) = GLYPH_NAMES.binary_search_by(|&(n, _)| n.cmp(name)) {
+        return Some(GLYPH_NAMES[i].1);
+    }
+    let hex = name
+        .strip_prefix("uni")
+        .filter(|h| h.len() == 4)
+        .or_else(|| name.strip_prefix('u').filter(|h| (4..=6).contains(&h.len())))?;
+    u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+}
+
+/// Nombre maximal de codes distincts d'une CMap `ToUnicode` : une police
+/// composite en compte au plus 65 536. Un code redéfini n'est compté
+/// qu'une fois ; au-delà, le fichier est aberrant (ou malveillant) et la
+/// lecture s'arrête.
+const MAX_MAPPED_CODES: usize = 65_536;
+
+/// Nombre maximal d'entrées lues, redéfinitions comprises : des plages
+/// répétées ne doivent pas faire tourner la lecture indéfiniment.
+const MAX_CMAP_ENTRIES: usize = 16 * MAX_MAPPED_CODES;
+
+/// Lit une CMap `ToUnicode` : sections `bfchar` et `bfrange`, cibles en
+/// UTF-16BE (plusieurs caractères pour une ligature).
+pub fn parse_to_unicode(data: &[u8]) -> HashMap<u32, String> {
+    let tokens = cmap_tokens(data);
+    let mut map = HashMap::new();
+    let mut read = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i] {
+            CMapToken::Keyword(b"beginbfchar") => {
+                i += 1;
+                while let (Some(CMapToken::Hex(src)), Some(CMapToken::Hex(dst))) = (tokens.get(i), tokens.get(i + 1)) {
+                    if !map_code(&mut map, &mut read, code(src), utf16(dst)) {
+                        return map;
+                    }
+                    i += 2;
+                }
+            }
+            CMapToken::Keyword(b"beginbfrange") => {
+                i += 1;
+                while let (Some(CMapToken::Hex(lo)), Some(CMapToken::Hex(hi))) = (tokens.get(i), tokens.get(i + 1)) {
+                    let (lo, hi) = (code(lo), code(hi));
+                    // Borne les plages aberrantes (fichiers malveillants).
+                    let hi = hi.min(lo.saturating_add(0xFFFF));
+                    match tokens.get(i + 2) {
+                        Some(CMapToken::Hex(dst)) => {
+                            let units: Vec<u16> = dst.chunks(2).map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)])).collect();
+                            for (offset, src) in (lo..=hi).enumerate() {
+                                // Seule la dernière unité UTF-16 est incrémentée.
+                                let mut units = units.clone();
+                                if let Some(last) = units.last_mut() {
+                                    *last = last.wrapping_add(offset as u16);
+                                }
+                                if !map_code(&mut map, &mut read, src, String::from_utf16_lossy(&units)) {
+                                    return map;
+                                }
+                            }
+                            i += 3;
+                        }
+                        Some(CMapToken::ArrayStart) => {
+                            let mut j = i + 3;
+                            let mut src = lo;
+                            while let Some(CMapToken::Hex(dst)) = tokens.get(j) {
+                                if src <= hi && !map_code(&mut map, &mut read, src, utf16(dst)) {
+                                    return map;
+                                }
+                                src = src.saturating_add(1);
+                                j += 1;
+                            }
+                            i = j + 1;
+                        }
+                        _ => break,
+                    }
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    map
+}
+
+/// Ajoute un code à la CMap, `false` (sans l'ajouter) une fois
+/// [`MAX_CMAP_ENTRIES`] entrées lues, ou pour un nouveau code quand la CMap
+/// compte déjà [`MAX_MAPPED_CODES`] codes distincts.
+fn map_code(map: &mut HashMap<u32, String>, read: &mut usize, code: u32, text: String) -> bool {
+    if *read == MAX_CMAP_ENTRIES || (map.len() == MAX_MAPPED_CODES && !map.contains_key(&code)) {
+        return false;
+    }
+    *read += 1;
+    map.insert(code, text);
+    true
+}
+
+#[derive(Debug)]
+enum CMapToken<'a> {
+    Hex(Vec<u8>),
+    Keyword(&'a [u8]),
+    ArrayStart,
+    Other,
+}
+
+fn cmap_tokens(data: &[u8]) -> Vec<CMapToken<'_>> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            b'%' => {
+                while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            b'<' if data.get(i + 1) == Some(&b'<') => {
+                tokens.push(CMapToken::Other);
+                i += 2;
+            }
+            b'<' => {
+                let end = data[i..].iter().position(|&b| b == b'>').map_or(data.len(), |p| i + p);
+                let digits: Vec<u8> = data[i + 1..end].iter().copied().filter(u8::is_ascii_hexdigit).collect();
+                let bytes = digits
+                    .chunks(2)
+                    .map(|c| {
+                        let text = std::str::from_utf8(c).unwrap_or("0");
+                        // Chiffre final isolé : complété par 0 (ISO 32000, 7.3.4.3)
+                        u8::from_str_radix(&format!("{:0<2}", text), 16).unwrap_or(0)
+                    })
+                    .collect();
+                tokens.push(CMapToken::Hex(bytes));
+                i = end + 1;
+            }
+            b'[' => {
+                tokens.push(CMapToken::ArrayStart);
+                i += 1;
+            }
+            b if b.is_ascii_alphabetic() => {
+                let start = i;
+                while i < data.len() && (data[i].is_ascii_alphanumeric() || data[i] == b'_') {
+                    i += 1;
+                }
+                tokens.push(CMapToken::Keyword(&data[start..i]));
+            }
+            b if b.is_ascii_whitespace() => i += 1,
+            _ => {
+                tokens.push(CMapToken::Other);
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+fn code(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0u32, |acc, &b| (acc << 8) | u32::from(b))
+}
+
+fn utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks(2).map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// StandardEncoding au-delà de l'ASCII.
+const STANDARD_HIGH: &[(u8, &str)] = &[
+    (0xA1, "exclamdown"), (0xA2, "cent"), (0xA3, "sterling"), (0xA4, "fraction"), (0xA5, "yen"),
+    (0xA6, "florin"), (0xA7, "section"), (0xA8, "currency"), (0xA9, "quotesingle"),
+    (0xAA, "quotedblleft"), (0xAB, "guillemotleft"), (0xAC, "guilsinglleft"),
+    (0xAD, "guilsinglright"), (0xAE, "fi"), (0xAF, "fl"), (0xB1, "endash"), (0xB2, "dagger"),
+    (0xB3, "daggerdbl"), (0xB4, "periodcentered"), (0xB6, "paragraph"), (0xB7, "bullet"),
+    (0xB8, "quotesinglbase"), (0xB9, "quotedblbase"), (0xBA, "quotedblright"),
+    (0xBB, "guillemotright"), (0xBC, "ellipsis"), (0xBD, "perthousand"), (0xBF, "questiondown"),
+    (0xC1, "grave"), (0xC2, "acute"), (0xC3, "circumflex"), (0xC4, "tilde"), (0xC5, "macron"),
+    (0xC6, "breve"), (0xC7, "dotaccent"), (0xC8, "dieresis"), (0xCA, "ring"), (0xCB, "cedilla"),
+    (0xCD, "hungarumlaut"), (0xCE, "ogonek"), (0xCF, "caron"), (0xD0, "emdash"), (0xE1, "AE"),
+    (0xE3, "ordfeminine"), (0xE8, "Lslash"), (0xE9, "Oslash"), (0xEA, "OE"), (0xEB, "ordmasculine"),
+    (0xF1, "ae"), (0xF5, "dotlessi"), (0xF8, "lslash"), (0xF9, "oslash"), (0xFA, "oe"),
+    (0xFB, "germandbls"),
+];
+
+/// MacRomanEncoding de 0x80 à 0xFF.
+const MAC_ROMAN_HIGH: [&str; 8] = [
+    "\u{C4}\u{C5}\u{C7}\u{C9}\u{D1}\u{D6}\u{DC}\u{E1}\u{E0}\u{E2}\u{E4}\u{E3}\u{E5}\u{E7}\u{E9}\u{E8}",
+    "\u{EA}\u{EB}\u{ED}\u{EC}\u{EE}\u{EF}\u{F1}\u{F3}\u{F2}\u{F4}\u{F6}\u{F5}\u{FA}\u{F9}\u{FB}\u{FC}",
+    "\u{2020}\u{B0}\u{A2}\u{A3}\u{A7}\u{2022}\u{B6}\u{DF}\u{AE}\u{A9}\u{2122}\u{B4}\u{A8}\u{2260}\u{C6}\u{D8}",
+    "\u{221E}\u{B1}\u{2264}\u{2265}\u{A5}\u{B5}\u{2202}\u{2211}\u{220F}\u{3C0}\u{222B}\u{AA}\u{BA}\u{3A9}\u{E6}\u{F8}",
+    "\u{BF}\u{A1}\u{AC}\u{221A}\u{192}\u{2248}\u{2206}\u{AB}\u{BB}\u{2026}\u{A0}\u{C0}\u{C3}\u{D5}\u{152}\u{153}",
+    "\u{2013}\u{2014}\u{201C}\u{201D}\u{2018}\u{2019}\u{F7}\u{25CA}\u{FF}\u{178}\u{2044}\u{20AC}\u{2039}\u{203A}\u{FB01}\u{FB02}",
+    "\u{2021}\u{B7}\u{201A}\u{201E}\u{2030}\u{C2}\u{CA}\u{C1}\u{CB}\u{C8}\u{CD}\u{CE}\u{CF}\u{CC}\u{D3}\u{D4}",
+    "\u{F8FF}\u{D2}\u{DA}\u{DB}\u{D9}\u{131}\u{2C6}\u{2DC}\u{AF}\u{2D8}\u{2D9}\u{2DA}\u{B8}\u{2DD}\u{2DB}\u{2C7}",
+];
+
+/// Noms de glyphes → caractères, triés par nom.
+const GLYPH_NAMES: &[(&str, char)] = &[
+    ("A", 'A'), ("AE", '\u{C6}'), ("Aacute", '\u{C1}'), ("Acircumflex", '\u{C2}'),
+    ("Adieresis", '\u{C4}'), ("Agrave", '\u{C0}'), ("Aring", '\u{C5}'), ("Atilde", '\u{C3}'),
+    ("B", 'B'), ("C", 'C'), ("Ccedilla", '\u{C7}'), ("D", 'D'), ("Delta", '\u{2206}'), ("E", 'E'),
+    ("Eacute", '\u{C9}'), ("Ecircumflex", '\u{CA}'), ("Edieresis", '\u{CB}'), ("Egrave", '\u{C8}'),
+    ("Eth", '\u{D0}'), ("Euro", '\u{20AC}'), ("F", 'F'), ("G", 'G'), ("H", 'H'), ("I", 'I'),
+    ("Iacute", '\u{CD}'), ("Icircumflex", '\u{CE}'), ("Idieresis", '\u{CF}'), ("Igrave", '\u{CC}'),
+    ("J", 'J'), ("K", 'K'), ("L", 'L'), ("Lslash", '\u{141}'), ("M", 'M'), ("N", 'N'),
+    ("Ntilde", '\u{D1}'), ("O", 'O'), ("OE", '\u{152}'), ("Oacute", '\u{D3}'),
+    ("Ocircumflex", '\u{D4}'), ("Odieresis", '\u{D6}'), ("Ograve", '\u{D2}'),
+    ("Omega", '\u{2126}'), ("Oslash", '\u{D8}'), ("Otilde", '\u{D5}'), ("P", 'P'), ("Q", 'Q'),
+    ("R", 'R'), ("S", 'S'), ("Scaron", '\u{160}'), ("T", 'T'), ("Thorn", '\u{DE}'), ("U", 'U'),
+    ("Uacute", '\u{DA}'), ("Ucircumflex", '\u{DB}'), ("Udieresis", '\u{DC}'), ("Ugrave", '\u{D9}'),
+    ("V", 'V'), ("W", 'W'), ("X", 'X'), ("Y", 'Y'), ("Yacute", '\u{DD}'), ("Ydieresis", '\u{178}'),
+    ("Z", 'Z'), ("Zcaron", '\u{17D}'), ("a", 'a'), ("aacute", '\u{E1}'), ("acircumflex", '\u{E2}'),
+    ("acute", '\u{B4}'), ("adieresis", '\u{E4}'), ("ae", '\u{E6}'), ("agrave", '\u{E0}'),
+    ("ampersand", '&'), ("apple", '\u{F8FF}'), ("approxequal", '\u{2248}'), ("aring", '\u{E5}'),
+    ("asciicircum", '^'), ("asciitilde", '~'), ("asterisk", '*'), ("at", '@'),
+    ("atilde", '\u{E3}'), ("b", 'b'), ("backslash", '\\'), ("bar", '|'), ("braceleft", '{'),
+    ("braceright", '}'), ("bracketleft", '['), ("bracketright", ']'), ("breve", '\u{2D8}'),
+    ("brokenbar", '\u{A6}'), ("bullet", '\u{2022}'), ("c", 'c'), ("caron", '\u{2C7}'),
+    ("ccedilla", '\u{E7}'), ("cedilla", '\u{B8}'), ("cent", '\u{A2}'), ("circumflex", '\u{2C6}'),
+    ("colon", ':'), ("comma", ','), ("copyright", '\u{A9}'), ("currency", '\u{A4}'), ("d", 'd'),
+    ("dagger", '\u{2020}'), ("daggerdbl", '\u{2021}'), ("degree", '\u{B0}'),
+    ("dieresis", '\u{A8}'), ("divide", '\u{F7}'), ("dollar", '$'), ("dotaccent", '\u{2D9}'),
+    ("dotlessi", '\u{131}'), ("e", 'e'), ("eacute", '\u{E9}'), ("ecircumflex", '\u{EA}'),
+    ("edieresis", '\u{EB}'), ("egrave", '\u{E8}'), ("eight", '8'), ("ellipsis", '\u{2026}'),
+    ("emdash", '\u{2014}'), ("endash", '\u{2013}'), ("equal", '='), ("eth", '\u{F0}'),
+    ("exclam", '!'), ("exclamdown", '\u{A1}'), ("f", 'f'), ("ff", '\u{FB00}'), ("ffi", '\u{FB03}'),
+    ("ffl", '\u{FB04}'), ("fi", '\u{FB01}'), ("five", '5'), ("fl", '\u{FB02}'),
+    ("florin", '\u{192}'), ("four", '4'), ("fraction", '\u{2044}'), ("g", 'g'),
+    ("germandbls", '\u{DF}'), ("grave", '`'), ("greater", '>'), ("greaterequal", '\u{2265}'),
+    ("guillemotleft", '\u{AB}'), ("guillemotright", '\u{BB}'), ("guilsinglleft", '\u{2039}'),
+    ("guilsinglright", '\u{203A}'), ("h", 'h'), ("hungarumlaut", '\u{2DD}'), ("hyphen", '-'),
+    ("i", 'i'), ("iacute", '\u{ED}'), ("icircumflex", '\u{EE}'), ("idieresis", '\u{EF}'),
+    ("igrave", '\u{EC}'), ("infinity", '\u{221E}'), ("integral", '\u{222B}'), ("j", 'j'),
+    ("k", 'k'), ("l", 'l'), ("less", '<'), ("lessequal", '\u{2264}'), ("logicalnot", '\u{AC}'),
+    ("lozenge", '\u{25CA}'), ("lslash", '\u{142}'), ("m", 'm'), ("macron", '\u{AF}'),
+    ("minus", '\u{2212}'), ("mu", '\u{B5}'), ("multiply", '\u{D7}'), ("n", 'n'),
+    ("nbspace", '\u{A0}'), ("nine", '9'), ("nonbreakingspace", '\u{A0}'), ("notequal", '\u{2260}'),
+    ("ntilde", '\u{F1}'), ("numbersign", '#'), ("o", 'o'), ("oacute", '\u{F3}'),
+    ("ocircumflex", '\u{F4}'), ("odieresis", '\u{F6}'), ("oe", '\u{153}'), ("ogonek", '\u{2DB}'),
+    ("ograve", '\u{F2}'), ("one", '1'), ("onehalf", '\u{BD}'), ("onequarter", '\u{BC}'),
+    ("onesuperior", '\u{B9}'), ("ordfeminine", '\u{AA}'), ("ordmasculine", '\u{BA}'),
+    ("oslash", '\u{F8}'), ("otilde", '\u{F5}'), ("p", 'p'), ("paragraph", '\u{B6}'),
+    ("parenleft", '('), ("parenright", ')'), ("partialdiff", '\u{2202}'), ("percent", '%'),
+    ("period", '.'), ("periodcentered", '\u{B7}'), ("perthousand", '\u{2030}'), ("pi", '\u{3C0}'),
+    ("plus", '+'), ("plusminus", '\u{B1}'), ("product", '\u{220F}'), ("q", 'q'), ("question", '?'),
+    ("questiondown", '\u{BF}'), ("quotedbl", '"'), ("quotedblbase", '\u{201E}'),
+    ("quotedblleft", '\u{201C}'), ("quotedblright", '\u{201D}'), ("quoteleft", '\u{2018}'),
+    ("quoteright", '\u{2019}'), ("quotesinglbase", '\u{201A}'), ("quotesingle", '\''), ("r", 'r'),
+    ("radical", '\u{221A}'), ("registered", '\u{AE}'), ("ring", '\u{2DA}'), ("s", 's'),
+    ("scaron", '\u{161}'), ("section", '\u{A7}'), ("semicolon", ';'), ("seven", '7'),
+    ("sfthyphen", '\u{AD}'), ("six", '6'), ("slash", '/'), ("space", ' '), ("sterling", '\u{A3}'),
+    ("summation", '\u{2211}'), ("t", 't'), ("thorn", '\u{FE}'), ("three", '3'),
+    ("threequarters", '\u{BE}'), ("threesuperior", '\u{B3}'), ("tilde", '\u{2DC}'),
+    ("trademark", '\u{2122}'), ("two", '2'), ("twosuperior", '\u{B2}'), ("u", 'u'),
+    ("uacute", '\u{FA}'), ("ucircumflex", '\u{FB}'), ("udieresis", '\u{FC}'), ("ugrave", '\u{F9}'),
+    ("underscore", '_'), ("v", 'v'), ("w", 'w'), ("x", 'x'), ("y", 'y'), ("yacute", '\u{FD}'),
+    ("ydieresis", '\u{FF}'), ("yen", '\u{A5}'), ("z", 'z'), ("zcaron", '\u{17E}'), ("zero", '0'),
+];
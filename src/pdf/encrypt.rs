@@ -0,0 +1,178 @@
+use lopdf::encryption::crypt_filters::{Aes128CryptFilter, Aes256CryptFilter, CryptFilter};
+use lopdf::{Document, EncryptionState, EncryptionVersion, Object, Permissions, StringFormat};
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::error::{PdfError, Result};
+use super::utils::{find_catalog, finalize, load_document};
+
+/// Algorithme de chiffrement du document produit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionAlgorithm {
+    /// RC4 128 bits (révision 3), pour les lecteurs anciens.
+    Rc4,
+    /// AES-128 (révision 4).
+    Aes128,
+    /// AES-256 (révision 6, PDF 2.0).
+    Aes256,
+}
+
+impl EncryptionAlgorithm {
+    /// Résout un nom d'algorithme (`aes256` par défaut, `aes128`, `rc4`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "" | "aes256" | "aes-256" => Some(Self::Aes256),
+            "aes128" | "aes-128" => Some(Self::Aes128),
+            "rc4" => Some(Self::Rc4),
+            _ => None,
+        }
+    }
+}
+
+/// Paramètres de chiffrement appliqués par [`finalize`].
+#[derive(Debug, Clone)]
+pub struct Encryption {
+    pub algorithm: EncryptionAlgorithm,
+    /// Mot de passe d'ouverture ; vide = ouverture libre, restrictions actives.
+    pub user_password: String,
+    /// Mot de passe propriétaire ; vide = mot de passe aléatoire jamais communiqué.
+    pub owner_password: String,
+    pub permissions: Permissions,
+}
+
+impl Encryption {
+    /// Paramètres décrits par des champs de formulaire : nom d'algorithme
+    /// (voir [`EncryptionAlgorithm::from_name`]) et liste de permissions
+    /// (voir [`parse_permissions`]). Les mots de passe sont pris tels quels.
+    pub fn from_fields(
+        algorithm: &str,
+        user_password: String,
+        owner_password: String,
+        permissions: &str,
+    ) -> Result<Self> {
+        let algorithm = EncryptionAlgorithm::from_name(algorithm.trim())
+            .ok_or_else(|| PdfError::UnknownAlgorithm(algorithm.trim().to_string()))?;
+        let permissions = parse_permissions(permissions)?;
+        Ok(Self { algorithm, user_password, owner_password, permissions })
+    }
+}
+
+/// Parse une liste de permissions : `print`, `copy`, `modify`, `annotate`,
+/// ou `all` / `none`. Une chaîne vide n'accorde aucune permission.
+///
+/// Exemples : `"print, copy"`, `"all"`
+pub fn parse_permissions(input: &str) -> Result<Permissions> {
+    // L'accès pour l'accessibilité (lecteurs d'écran) reste toujours autorisé.
+    let mut permissions = Permissions::COPYABLE_FOR_ACCESSIBILITY;
+
+    for part in input.split(',') {
+        let part = part.trim().to_ascii_lowercase();
+        permissions |= match part.as_str() {
+            "" | "none" => Permissions::empty(),
+            "print" => Permissions::PRINTABLE | Permissions::PRINTABLE_IN_HIGH_QUALITY,
+            "copy" => Permissions::COPYABLE,
+            "modify" => Permissions::MODIFIABLE | Permissions::ASSEMBLABLE,
+            "annotate" => Permissions::ANNOTABLE | Permissions::FILLABLE,
+            "all" => Permissions::all(),
+            _ => return Err(PdfError::UnknownPermission(part)),
+        };
+    }
+
+    Ok(permissions)
+}
+
+/// Chiffre un PDF existant. Un document déjà chiffré est d'abord
+/// déverrouillé avec `password`, puis rechiffré avec les nouveaux paramètres.
+pub fn encrypt_pdf(data: &[u8], encryption: &Encryption, password: Option<&str>) -> Result<Vec<u8>> {
+    let mut doc = load_document(data, password)?;
+
+    let catalog_id = find_catalog(&doc)
+        .ok_or_else(|| PdfError::InvalidFile("(catalogue introuvable)".to_string()))?;
+    finalize(&mut doc, catalog_id, Some(encryption))
+}
+
+/// Chiffre tous les objets de `doc` et ajoute le dictionnaire `/Encrypt`.
+///
+/// Un `/ID` neuf est posé dans le trailer : les révisions 3 et 4 en
+/// dérivent la clé, et un document reconstruit n'en a pas. L'AES-256
+/// (révision 6) n'existe qu'à partir de PDF 2.0 : l'en-tête est relevé en
+/// conséquence, faute de quoi des lecteurs stricts refusent le fichier.
+pub fn apply_encryption(doc: &mut Document, encryption: &Encryption) -> Result<()> {
+    let mut rng = rand::rng();
+
+    let mut file_id = [0u8; 16];
+    rng.fill(&mut file_id);
+    let file_id = Object::String(file_id.to_vec(), StringFormat::Hexadecimal);
+    doc.trailer.set("ID", Object::Array(vec![file_id.clone(), file_id]));
+
+    // Sans mot de passe propriétaire, quiconque pourrait lever les
+    // restrictions avec un mot de passe vide.
+    let owner_password = if encryption.owner_password.is_empty() {
+        let mut secret = [0u8; 24];
+        rng.fill(&mut secret);
+        secret.iter().map(|b| format!("{:02x}", b)).collect()
+    } else {
+        encryption.owner_password.clone()
+    };
+    let owner_password = owner_password.as_str();
+    let user_password = encryption.user_password.as_str();
+    let permissions = encryption.permissions;
+
+    let mut file_encryption_key = [0u8; 32];
+    rng.fill(&mut file_encryption_key);
+
+    let version = match encryption.algorithm {
+        EncryptionAlgorithm::Rc4 => EncryptionVersion::V2 {
+            document: doc,
+            owner_password,
+            user_password,
+            key_length: 128,
+            permissions,
+        },
+        EncryptionAlgorithm::Aes128 => {
+            let filter: Arc<dyn CryptFilter> = Arc::new(Aes128CryptFilter);
+            EncryptionVersion::V4 {
+                document: doc,
+                encrypt_metadata: true,
+                crypt_filters: BTreeMap::from([(b"StdCF".to_vec(), filter)]),
+                stream_filter: b"StdCF".to_vec(),
+                string_filter: b"StdCF".to_vec(),
+                owner_password,
+                user_password,
+                permissions,
+            }
+        }
+        EncryptionAlgorithm::Aes256 => {
+            let filter: Arc<dyn CryptFilter> = Arc::new(Aes256CryptFilter);
+            EncryptionVersion::V5 {
+                encrypt_metadata: true,
+                crypt_filters: BTreeMap::from([(b"StdCF".to_vec(), filter)]),
+                file_encryption_key: &file_encryption_key,
+                stream_filter: b"StdCF".to_vec(),
+                string_filter: b"StdCF".to_vec(),
+                owner_password,
+                user_password,
+                permissions,
+            }
+        }
+    };
+
+    let state = EncryptionState::try_from(version)?;
+    doc.encrypt(&state)?;
+    if encryption.algorithm == EncryptionAlgorithm::Aes256 && header_version(doc) < (2, 0) {
+        doc.version = "2.0".to_string();
+    }
+    Ok(())
+}
+
+/// Version `(majeure, mineure)` de l'en-tête `%PDF-` ; `(1, 0)` si illisible.
+fn header_version(doc: &Document) -> (u32, u32) {
+    let mut parts = doc.version.trim().splitn(2, '.');
+    let major = parts.next().and_then(|p| p.parse().ok());
+    let minor = parts.next().and_then(|p| p.parse().ok());
+    match (major, minor) {
+        (Some(major), Some(minor)) => (major, minor),
+        _ => (1, 0),
+    }
+}
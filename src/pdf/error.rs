@@ -1,3 +1,5 @@
+use super::forms::FieldError;
+
 /// Erreurs métier de la couche PDF.
 #[derive(Debug, thiserror::Error)]
 pub enum PdfError {
@@ -25,6 +27,57 @@ pub enum PdfError {
     #[error("Numéro de page invalide : '{0}'")]
     InvalidPageNumber(String),
 
+    #[error("Algorithme invalide : '{0}' (aes256, aes128, rc4)")]
+    UnknownAlgorithm(String),
+
+    #[error("Permission inconnue : '{0}' (print, copy, modify, annotate, all, none)")]
+    UnknownPermission(String),
+
+    #[error("Clé de métadonnée invalide : '{0}' (lettres, chiffres, '-' et '_', lettre ou '_' en tête ; CreationDate, ModDate et Trapped sont réservées)")]
+    InvalidMetadataKey(String),
+
+    #[error("Signets invalides : {0}")]
+    InvalidBookmarks(String),
+
+    #[error("Le document ne contient pas de formulaire")]
+    NoForm,
+
+    #[error("Données de formulaire invalides ({} erreur(s))", .0.len())]
+    InvalidFormData(Vec<FieldError>),
+
+    #[error("Les widgets de formulaire se traitent avec /fill-form et /flatten-form")]
+    WidgetAnnotations,
+
+    #[error("CSV invalide : {0}")]
+    InvalidCsv(String),
+
+    #[error("Le CSV dépasse {0} enregistrements")]
+    TooManyRecords(usize),
+
+    #[error("Ligne {line} du CSV : données de formulaire invalides ({} erreur(s))", .errors.len())]
+    InvalidRecord { line: usize, errors: Vec<FieldError> },
+
+    #[error("Modèle de nom de fichier invalide : {0}")]
+    InvalidNameTemplate(String),
+
+    #[error("Archive trop volumineuse (4 Go et 65 535 fichiers au plus)")]
+    ArchiveTooLarge,
+
+    #[error("Zones à caviarder invalides : {0}")]
+    InvalidRegions(String),
+
+    #[error("Motif invalide : {0}")]
+    InvalidPattern(String),
+
+    #[error("Document protégé par mot de passe : renseignez le champ 'password'")]
+    Encrypted,
+
+    #[error("Mot de passe incorrect")]
+    WrongPassword,
+
+    #[error("Chiffrement non pris en charge : dictionnaire /Encrypt introuvable ou invalide")]
+    UnsupportedEncryption,
+
     #[error(transparent)]
     Lopdf(#[from] lopdf::Error),
 
@@ -32,5 +85,12 @@ pub enum PdfError {
     Io(#[from] std::io::Error),
 }
 
+impl PdfError {
+    /// Document chiffré qui n'a pas pu être ouvert : erreur du client, pas du serveur.
+    pub fn is_password_error(&self) -> bool {
+        matches!(self, Self::Encrypted | Self::WrongPassword | Self::UnsupportedEncryption)
+    }
+}
+
 /// Alias de résultat local pour éviter la répétition du type d'erreur.
 pub type Result<T> = std::result::Result<T, PdfError>;
@@ -1,14 +1,22 @@
-use lopdf::{Document, ObjectId};
-use std::collections::HashSet;
+use lopdf::{Document, Object, ObjectId};
+use std::collections::{HashMap, HashSet};
 
+use super::encrypt::Encryption;
+use super::bookmarks::named_destination;
 use super::error::{PdfError, Result};
 use super::utils::{load_document, 
-    copy_objects, find_catalog, find_pages_root, finalize,
-    insert_catalog, insert_pages_node, set_parent,
+    copy_objects, find_catalog, find_pages_root, finalize, inline_inherited_attributes,
+    insert_catalog, insert_pages_node, prune_unreachable, remap_object, resolve_dict, set_parent,
+    visit_dicts_mut,
 };
 
-pub fn extract_pages(data: &[u8], page_numbers: &[u32]) -> Result<Vec<u8>> {
-    let mut src = load_document(data)?;
+pub fn extract_pages(
+    data: &[u8],
+    page_numbers: &[u32],
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut src = load_document(data, password)?;
     src.decompress();
 
     let all_pages = src.get_pages();
@@ -24,20 +32,136 @@ pub fn extract_pages(data: &[u8], page_numbers: &[u32]) -> Result<Vec<u8>> {
                 .ok_or(PdfError::PageOutOfRange { page: n, total: total as u32 })
         })
         .collect::<Result<_>>()?;
+    inline_inherited_attributes(&mut src, &page_ids);
 
+    pages_to_document(&src, &page_ids, encryption)
+}
+
+/// Nouveau document formé des pages `page_ids` de `src`, dans cet ordre.
+/// L'ancien nœud racine n'étant pas recopié, leurs attributs hérités
+/// doivent avoir été recopiés au préalable (`inline_inherited_attributes`).
+/// Les autres pages n'y figurent pas, ni les liens internes qui les visaient.
+pub fn pages_to_document(
+    src: &Document,
+    page_ids: &[ObjectId],
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
     let mut out = Document::with_version("1.5");
 
-    let excluded: HashSet<ObjectId> = [find_catalog(&src), find_pages_root(&src)]
-        .into_iter()
-        .flatten()
+    // Ni le catalogue, ni l'arbre des pages, ni les pages écartées : ce
+    // qu'elles seules référencent disparaît à l'élagage.
+    let selected: HashSet<ObjectId> = page_ids.iter().copied().collect();
+    let excluded: HashSet<ObjectId> = src
+        .objects
+        .iter()
+        .filter(|(id, obj)| {
+            !selected.contains(id)
+                && obj.as_dict().is_ok_and(|d| {
+                    matches!(d.get(b"Type").and_then(Object::as_name), Ok(b"Page" | b"Pages" | b"Catalog"))
+                })
+        })
+        .map(|(&id, _)| id)
+        .chain(find_catalog(src))
+        .chain(find_pages_root(src))
         .collect();
 
-    let id_map = copy_objects(&src, &mut out, &excluded);
+    let id_map = copy_objects(src, &mut out, &excluded);
 
     let new_page_ids: Vec<ObjectId> = page_ids.iter().map(|id| id_map[id]).collect();
     let pages_id = insert_pages_node(&mut out, &new_page_ids, new_page_ids.len() as i64);
     set_parent(&mut out, &new_page_ids, pages_id);
 
+    let kept: HashSet<ObjectId> = new_page_ids.iter().copied().collect();
+    retarget_links(src, &mut out, &id_map, &kept);
+
     let catalog_id = insert_catalog(&mut out, pages_id);
-    finalize(&mut out, catalog_id)
+    out.trailer.set("Root", catalog_id);
+    prune_unreachable(&mut out);
+    finalize(&mut out, catalog_id, encryption)
+}
+
+/// Liens internes du document produit : les destinations nommées, dont
+/// l'arbre n'est pas recopié, deviennent explicites ; celles qui visent une
+/// page écartée sont retirées, avec les annotations `/Link` qui les portent.
+fn retarget_links(
+    src: &Document,
+    out: &mut Document,
+    id_map: &HashMap<ObjectId, ObjectId>,
+    kept: &HashSet<ObjectId>,
+) {
+    let ids: Vec<ObjectId> = out.objects.keys().copied().collect();
+    for id in ids {
+        let mut obj = out.objects[&id].clone();
+        let doc = &*out;
+        let live = |dest: &Object| live_destination(src, doc, id_map, kept, dest);
+        let dead_goto = |action: &Object| {
+            resolve_dict(doc, action).is_some_and(|a| {
+                a.get(b"S").and_then(Object::as_name).ok() == Some(b"GoTo")
+                    && a.get(b"D").map_or(true, |d| live(d).is_none())
+            })
+        };
+        let dead_link = |annot: &Object| {
+            resolve_dict(doc, annot).is_some_and(|a| {
+                a.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Link")
+                    && (a.get(b"Dest").is_ok_and(|d| live(d).is_none())
+                        || a.get(b"A").is_ok_and(dead_goto))
+            })
+        };
+        visit_dicts_mut(&mut obj, &mut |dict| {
+            if let Ok(dest) = dict.get(b"Dest") {
+                match live(dest) {
+                    Some(dest) => dict.set("Dest", dest),
+                    None => {
+                        dict.remove(b"Dest");
+                    }
+                }
+            }
+            if dict.get(b"S").and_then(Object::as_name).ok() == Some(b"GoTo") {
+                if let Some(dest) = dict.get(b"D").ok().and_then(live) {
+                    dict.set("D", dest);
+                }
+            }
+            if let Ok(Object::Array(annots)) = dict.get_mut(b"Annots") {
+                annots.retain(|annot| !dead_link(annot));
+            }
+            let dead: Vec<Vec<u8>> = dict
+                .iter()
+                .filter(|(key, value)| key.as_slice() != b"Annots" && dead_goto(value))
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in dead {
+                dict.remove(&key);
+            }
+        });
+        out.objects.insert(id, obj);
+    }
+}
+
+/// Destination explicite `[page …]` équivalente à `dest` dans le document
+/// produit, ou `None` si sa page n'en fait pas partie.
+fn live_destination(
+    src: &Document,
+    out: &Document,
+    id_map: &HashMap<ObjectId, ObjectId>,
+    kept: &HashSet<ObjectId>,
+    dest: &Object,
+) -> Option<Object> {
+    let mut dest = dest.clone();
+    // Mêmes indirections que pour les signets : référence, dictionnaire
+    // `/D`, nom résolu dans le document source.
+    for _ in 0..4 {
+        dest = match dest {
+            Object::Reference(id) => out.get_object(id).ok()?.clone(),
+            Object::Array(ref arr) => {
+                let page = arr.first()?.as_reference().ok()?;
+                return kept.contains(&page).then_some(dest);
+            }
+            Object::Dictionary(dict) => dict.get(b"D").ok()?.clone(),
+            Object::Name(name) | Object::String(name, _) => {
+                remap_object(named_destination(src, &name)?.clone(), id_map)
+            }
+            _ => return None,
+        };
+    }
+    None
 }
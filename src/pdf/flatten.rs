@@ -0,0 +1,243 @@
+use lopdf::{Document, Object, ObjectId};
+use std::collections::{BTreeMap, HashSet};
+
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::forms::{
+    acroform_mut, form_fields, needs_appearances, regenerate_appearance, FieldError,
+    FieldErrorReason, FormField,
+};
+use super::utils::{
+    add_page_xobject, find_catalog, finalize, load_document, matrix_operands, annotation_pages,
+    prune_unreachable, resolve_dict, transform_rect, wrap_page_content, Matrix,
+};
+
+// Drapeaux /F des annotations (ISO 32000, table 165).
+const ANNOT_HIDDEN: i64 = 1 << 1;
+const ANNOT_NO_VIEW: i64 = 1 << 5;
+
+/// Aplatit le formulaire : l'apparence de chaque widget est dessinée dans
+/// le contenu de sa page, puis le champ disparaît. `only` limite
+/// l'opération aux champs nommés (un nom désigne aussi ses sous-champs).
+pub fn flatten_form(
+    data: &[u8],
+    only: Option<&[String]>,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut doc = load_document(data, password)?;
+    let catalog_id = find_catalog(&doc)
+        .ok_or_else(|| PdfError::InvalidFile("(catalogue introuvable)".to_string()))?;
+
+    flatten_document(&mut doc, only)?;
+    prune_unreachable(&mut doc);
+    finalize(&mut doc, catalog_id, encryption)
+}
+
+/// `client` désigne `client` et tous les champs `client.…`.
+fn matches_name(field: &FormField, name: &str) -> bool {
+    field
+        .name
+        .strip_prefix(name)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+/// Aplatit les champs en place et retourne leur nombre.
+pub fn flatten_document(doc: &mut Document, only: Option<&[String]>) -> Result<usize> {
+    let fields = form_fields(doc);
+    if fields.is_empty() {
+        return Err(PdfError::NoForm);
+    }
+    let selected: Vec<&FormField> = match only {
+        None => fields.iter().collect(),
+        Some(names) => {
+            let errors: Vec<FieldError> = names
+                .iter()
+                .filter(|n| !fields.iter().any(|f| matches_name(f, n)))
+                .map(|n| FieldError {
+                    field: n.clone(),
+                    kind: None,
+                    reason: FieldErrorReason::UnknownField,
+                    allowed: Vec::new(),
+                })
+                .collect();
+            if !errors.is_empty() {
+                return Err(PdfError::InvalidFormData(errors));
+            }
+            fields.iter().filter(|f| names.iter().any(|n| matches_name(f, n))).collect()
+        }
+    };
+
+    // Apparences absentes ou laissées au lecteur : elles seraient perdues.
+    let stale = needs_appearances(doc);
+    for field in &selected {
+        let missing = field
+            .widgets
+            .iter()
+            .any(|&w| doc.get_dictionary(w).is_ok_and(|d| !d.has(b"AP")));
+        if stale || missing {
+            regenerate_appearance(doc, field)?;
+        }
+    }
+
+    let page_ids: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    let annot_pages = annotation_pages(doc);
+
+    let mut by_page: BTreeMap<ObjectId, Vec<ObjectId>> = BTreeMap::new();
+    let mut removed: HashSet<ObjectId> = HashSet::new();
+    for field in &selected {
+        for &widget in &field.widgets {
+            removed.insert(widget);
+            if let Some(&page_id) = annot_pages.get(&widget) {
+                by_page.entry(page_id).or_default().push(widget);
+            }
+        }
+    }
+    for (page_id, widgets) in by_page {
+        draw_annotations(doc, page_id, &widgets, "Fld")?;
+    }
+
+    for &page_id in &page_ids {
+        remove_annots(doc, page_id, &removed)?;
+    }
+
+    if selected.len() == fields.len() {
+        let catalog_id = find_catalog(doc)
+            .ok_or_else(|| PdfError::InvalidFile("(catalogue introuvable)".to_string()))?;
+        doc.get_dictionary_mut(catalog_id)?.remove(b"AcroForm");
+    } else {
+        for field in &selected {
+            detach_field(doc, field.id)?;
+        }
+    }
+    Ok(selected.len())
+}
+
+/// Retire de `/Annots` les annotations de `removed`.
+pub fn remove_annots(doc: &mut Document, page_id: ObjectId, removed: &HashSet<ObjectId>) -> Result<()> {
+    let keep = |a: &Object| !a.as_reference().is_ok_and(|id| removed.contains(&id));
+    let page = doc.get_dictionary_mut(page_id)?;
+    match page.get_mut(b"Annots") {
+        Ok(Object::Array(annots)) => {
+            annots.retain(keep);
+            if annots.is_empty() {
+                page.remove(b"Annots");
+            }
+        }
+        Ok(Object::Reference(id)) => {
+            let id = *id;
+            if let Ok(Object::Array(annots)) = doc.get_object_mut(id) {
+                annots.retain(keep);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Dessine l'apparence normale des annotations `annots` dans le contenu
+/// de la page, sous forme de Form XObjects nommés `{prefix}n`, et retourne
+/// le nombre d'annotations dessinées. Les annotations elles-mêmes restent
+/// à retirer par l'appelant.
+pub fn draw_annotations(doc: &mut Document, page_id: ObjectId, annots: &[ObjectId], prefix: &str) -> Result<usize> {
+    let mut ops = Vec::new();
+    let mut drawn = 0;
+    for &annot in annots {
+        let Some((xobject, matrix)) = annotation_appearance(doc, annot) else {
+            continue;
+        };
+        // Certains producteurs omettent /Subtype sur les flux d'apparence.
+        if let Ok(Object::Stream(stream)) = doc.get_object_mut(xobject) {
+            stream.dict.set("Type", Object::Name(b"XObject".to_vec()));
+            stream.dict.set("Subtype", Object::Name(b"Form".to_vec()));
+        }
+        let name = add_page_xobject(doc, page_id, xobject, prefix)?;
+        ops.extend(format!("q {} cm /{} Do Q\n", matrix_operands(matrix), String::from_utf8_lossy(&name)).into_bytes());
+        drawn += 1;
+    }
+    // Le contenu d'origine est isolé dans q … Q : son état graphique final
+    // ne doit pas déformer les annotations dessinées après lui.
+    if drawn > 0 {
+        wrap_page_content(doc, page_id, b"q\n".to_vec(), [b"\nQ\n".as_slice(), &ops].concat())?;
+    }
+    Ok(drawn)
+}
+
+/// Flux d'apparence normale de l'annotation et matrice qui place sa `BBox`
+/// (transformée par sa `/Matrix`) sur le `Rect` de l'annotation
+/// (ISO 32000, 12.5.5). `None` pour une annotation masquée ou sans apparence.
+fn annotation_appearance(doc: &Document, annot: ObjectId) -> Option<(ObjectId, Matrix)> {
+    let dict = doc.get_dictionary(annot).ok()?;
+    let flags = dict.get(b"F").and_then(Object::as_i64).unwrap_or(0);
+    if flags & (ANNOT_HIDDEN | ANNOT_NO_VIEW) != 0 {
+        return None;
+    }
+
+    let appearances = resolve_dict(doc, dict.get(b"AP").ok()?)?;
+    // Apparence à états (cases, boutons radio) : celui désigné par /AS.
+    let stream_id = match appearances.get(b"N").ok()? {
+        Object::Reference(id) if doc.get_object(*id).ok()?.as_stream().is_ok() => *id,
+        states => {
+            let state = dict.get(b"AS").and_then(Object::as_name).ok()?;
+            resolve_dict(doc, states)?.get(state).and_then(Object::as_reference).ok()?
+        }
+    };
+    let stream = doc.get_object(stream_id).ok()?.as_stream().ok()?;
+
+    let floats = |key: &[u8]| -> Option<Vec<f32>> {
+        let arr = stream.dict.get(key).ok()?.as_array().ok()?;
+        arr.iter().map(|v| v.as_float().ok()).collect()
+    };
+    let bbox: [f32; 4] = floats(b"BBox")?.try_into().ok()?;
+    let form_matrix: Matrix = floats(b"Matrix")
+        .and_then(|m| m.try_into().ok())
+        .unwrap_or([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+    let rect: Vec<f32> = dict
+        .get(b"Rect")
+        .and_then(Object::as_array)
+        .ok()?
+        .iter()
+        .map(|v| v.as_float().ok())
+        .collect::<Option<_>>()?;
+    let [x0, y0, x1, y1] = rect[..] else {
+        return None;
+    };
+    let (left, bottom, right, top) = (x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1));
+
+    let t = transform_rect(form_matrix, bbox);
+    let (width, height) = (t[2] - t[0], t[3] - t[1]);
+    if width <= 0.0 || height <= 0.0 {
+        return None;
+    }
+    let (a, d) = ((right - left) / width, (top - bottom) / height);
+    Some((stream_id, [a, 0.0, 0.0, d, left - t[0] * a, bottom - t[1] * d]))
+}
+
+/// Retire le champ de l'arbre du formulaire, ainsi que ses ancêtres
+/// devenus sans enfants.
+pub fn detach_field(doc: &mut Document, id: ObjectId) -> Result<()> {
+    let mut current = id;
+    // Borne la remontée pour se protéger des arbres cycliques.
+    for _ in 0..64 {
+        let parent = doc
+            .get_dictionary(current)?
+            .get(b"Parent")
+            .and_then(Object::as_reference)
+            .ok();
+        let Some(parent) = parent else {
+            if let Some(Ok(Object::Array(fields))) = acroform_mut(doc).map(|f| f.get_mut(b"Fields")) {
+                fields.retain(|f| f.as_reference().ok() != Some(current));
+            }
+            return Ok(());
+        };
+        let Ok(Object::Array(kids)) = doc.get_dictionary_mut(parent)?.get_mut(b"Kids") else {
+            return Ok(());
+        };
+        kids.retain(|k| k.as_reference().ok() != Some(current));
+        if !kids.is_empty() {
+            return Ok(());
+        }
+        current = parent;
+    }
+    Ok(())
+}
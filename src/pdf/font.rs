@@ -0,0 +1,69 @@
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+/// Polices standard utilisées pour le texte généré (table des matières,
+/// apparences de champs…) : présentes dans tous les lecteurs, jamais embarquées.
+pub const HELVETICA: &str = "Helvetica";
+pub const HELVETICA_BOLD: &str = "Helvetica-Bold";
+
+/// Chasses de Helvetica pour les caractères 32 à 126, en millièmes de
+/// corps (AFM Adobe). Helvetica-Bold est proche : ces valeurs suffisent
+/// pour la mise en page.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// Caractères de WinAnsiEncoding hors ASCII et Latin-1 (plage 0x80–0x9F).
+pub const WIN_ANSI_EXTRA: &[(char, u8)] = &[
+    ('€', 0x80), ('‚', 0x82), ('ƒ', 0x83), ('„', 0x84), ('…', 0x85), ('†', 0x86),
+    ('‡', 0x87), ('ˆ', 0x88), ('‰', 0x89), ('Š', 0x8A), ('‹', 0x8B), ('Œ', 0x8C),
+    ('Ž', 0x8E), ('‘', 0x91), ('’', 0x92), ('“', 0x93), ('”', 0x94), ('•', 0x95),
+    ('–', 0x96), ('—', 0x97), ('˜', 0x98), ('™', 0x99), ('š', 0x9A), ('›', 0x9B),
+    ('œ', 0x9C), ('ž', 0x9E), ('Ÿ', 0x9F),
+];
+
+/// Encode `text` en WinAnsiEncoding ; les caractères absents de
+/// l'encodage deviennent `?`, les caractères de contrôle des espaces.
+pub fn win_ansi_encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x20..=0x7E | 0xA0..=0xFF => c as u8,
+            0x00..=0x1F => b' ',
+            _ => WIN_ANSI_EXTRA
+                .iter()
+                .find(|(extra, _)| *extra == c)
+                .map_or(b'?', |&(_, byte)| byte),
+        })
+        .collect()
+}
+
+/// Largeur en points d'un texte encodé en WinAnsi, en Helvetica de corps `size`.
+pub fn text_width(encoded: &[u8], size: f32) -> f32 {
+    let units: u32 = encoded.iter().map(|&b| u32::from(char_width(b))).sum();
+    units as f32 * size / 1000.0
+}
+
+/// Chasse Helvetica d'un octet WinAnsi, en millièmes de corps.
+pub fn char_width(byte: u8) -> u16 {
+    match byte {
+        0x20..=0x7E => HELVETICA_WIDTHS[usize::from(byte - 0x20)],
+        0x85 | 0x89 | 0x97 => 1000,
+        // Capitales accentuées (À–Þ)
+        0xC0..=0xDE => 667,
+        _ => 556,
+    }
+}
+
+/// Ajoute au document une police standard encodée en WinAnsi.
+pub fn add_standard_font(doc: &mut Document, base_font: &str) -> ObjectId {
+    doc.add_object(Object::Dictionary(Dictionary::from_iter(vec![
+        ("Type", Object::Name(b"Font".to_vec())),
+        ("Subtype", Object::Name(b"Type1".to_vec())),
+        ("BaseFont", Object::Name(base_font.as_bytes().to_vec())),
+        ("Encoding", Object::Name(b"WinAnsiEncoding".to_vec())),
+    ])))
+}
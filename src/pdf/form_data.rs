@@ -0,0 +1,342 @@
+use lopdf::{text_string, Document, Object, ObjectId};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::error::{PdfError, Result};
+use super::forms::{form_fields, FieldKind, FieldValue, FormField};
+use super::utils::{annotation_pages, annotation_rect, inherited_attribute, load_document, xml_escape};
+
+/// Format d'export des données de formulaire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Fdf,
+    Xfdf,
+}
+
+impl ExportFormat {
+    /// `""` (JSON par défaut), `json`, `csv`, `fdf` ou `xfdf`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "" | "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            "fdf" => Some(Self::Fdf),
+            "xfdf" => Some(Self::Xfdf),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Fdf => "application/vnd.fdf",
+            Self::Xfdf => "application/vnd.adobe.xfdf",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Fdf => "fdf",
+            Self::Xfdf => "xfdf",
+        }
+    }
+
+    /// FDF et XFDF décrivent un seul formulaire.
+    pub fn single_document(self) -> bool {
+        matches!(self, Self::Fdf | Self::Xfdf)
+    }
+}
+
+/// Exporte les champs de chaque formulaire de `files` ; `names` donne le
+/// nom de fichier de chacun. JSON et CSV acceptent plusieurs documents
+/// (une entrée, une ligne par fichier), FDF et XFDF un seul.
+pub fn export_form_data(
+    files: &[Vec<u8>],
+    names: &[String],
+    format: ExportFormat,
+    password: Option<&str>,
+) -> Result<Vec<u8>> {
+    let mut documents = Vec::with_capacity(files.len());
+    for data in files {
+        let doc = load_document(data, password)?;
+        let fields = form_fields(&doc);
+        if fields.is_empty() {
+            return Err(PdfError::NoForm);
+        }
+        documents.push((doc, fields));
+    }
+    let name = |i: usize| names.get(i).map(String::as_str).unwrap_or_default();
+
+    Ok(match format {
+        ExportFormat::Json => {
+            let entries: Vec<Value> = documents
+                .iter()
+                .enumerate()
+                .map(|(i, (doc, fields))| json!({ "file": name(i), "fields": fields_to_json(doc, fields) }))
+                .collect();
+            Value::Array(entries).to_string().into_bytes()
+        }
+        ExportFormat::Csv => {
+            let rows: Vec<(&str, &[FormField])> =
+                documents.iter().enumerate().map(|(i, (_, fields))| (name(i), fields.as_slice())).collect();
+            to_csv(&rows)
+        }
+        ExportFormat::Fdf => {
+            let (doc, fields) = &documents[0];
+            to_fdf(&field_tree(doc, fields), name(0))
+        }
+        ExportFormat::Xfdf => {
+            let (doc, fields) = &documents[0];
+            to_xfdf(&field_tree(doc, fields), name(0))
+        }
+    })
+}
+
+// ── JSON ──────────────────────────────────────────────────────────────────────
+
+/// Un objet par champ terminal, avec la page (1-indexée) et le rectangle
+/// de chacun de ses widgets.
+fn fields_to_json(doc: &Document, fields: &[FormField]) -> Value {
+    let page_numbers: HashMap<ObjectId, u32> =
+        doc.get_pages().into_iter().map(|(num, id)| (id, num)).collect();
+    let annot_pages = annotation_pages(doc);
+
+    Value::Array(
+        fields
+            .iter()
+            .map(|field| {
+                let widgets: Vec<Value> = field
+                    .widgets
+                    .iter()
+                    .map(|w| {
+                        let page = annot_pages.get(w).and_then(|p| page_numbers.get(p));
+                        json!({ "page": page, "rect": annotation_rect(doc, *w) })
+                    })
+                    .collect();
+                json!({
+                    "name": field.name,
+                    "type": field.kind.name(),
+                    "value": field.value.to_json(),
+                    "options": field.options,
+                    "read_only": field.read_only(),
+                    "widgets": widgets,
+                })
+            })
+            .collect(),
+    )
+}
+
+// ── CSV ───────────────────────────────────────────────────────────────────────
+
+/// Une ligne par document ; les colonnes réunissent les champs de tous
+/// les documents, dans l'ordre de première apparition.
+fn to_csv(rows: &[(&str, &[FormField])]) -> Vec<u8> {
+    let mut columns: Vec<&str> = Vec::new();
+    for field in rows.iter().flat_map(|(_, fields)| fields.iter()).filter(|f| carries_data(f)) {
+        if !columns.contains(&field.name.as_str()) {
+            columns.push(&field.name);
+        }
+    }
+
+    // BOM : sans lui, les tableurs lisent l'UTF-8 comme du Windows-1252.
+    let mut out = String::from("\u{FEFF}");
+    let header: Vec<String> = std::iter::once("file").chain(columns.iter().copied()).map(csv_cell).collect();
+    out.push_str(&header.join(","));
+    out.push_str("\r\n");
+    for (file, fields) in rows {
+        let cells: Vec<String> = std::iter::once(file.to_string())
+            .chain(columns.iter().map(|column| {
+                fields
+                    .iter()
+                    .find(|f| f.name == *column)
+                    .map(|f| value_text(&f.value))
+                    .unwrap_or_default()
+            }))
+            .map(|cell| csv_cell(&cell))
+            .collect();
+        out.push_str(&cells.join(","));
+        out.push_str("\r\n");
+    }
+    out.into_bytes()
+}
+
+fn value_text(value: &FieldValue) -> String {
+    match value {
+        FieldValue::Empty => String::new(),
+        FieldValue::Bool(b) => b.to_string(),
+        FieldValue::Text(s) => s.clone(),
+        FieldValue::List(items) => items.join("; "),
+    }
+}
+
+/// Cellule RFC 4180. Une valeur commençant par `=`, `+`, `-` ou `@` est
+/// préfixée d'une apostrophe : un tableur l'évaluerait comme une formule.
+fn csv_cell(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+// ── FDF / XFDF ────────────────────────────────────────────────────────────────
+
+/// Nœud de l'arbre des noms partiels : FDF et XFDF imbriquent les champs
+/// (`client` → `nom`) au lieu d'utiliser le nom complet.
+struct FieldNode {
+    name: String,
+    value: Option<Object>,
+    kids: Vec<FieldNode>,
+}
+
+/// Boutons poussoirs et signatures ne portent pas de données exportables.
+fn carries_data(field: &FormField) -> bool {
+    !matches!(field.kind, FieldKind::PushButton | FieldKind::Signature)
+}
+
+fn field_tree(doc: &Document, fields: &[FormField]) -> Vec<FieldNode> {
+    let mut roots: Vec<FieldNode> = Vec::new();
+    for field in fields.iter().filter(|f| carries_data(f)) {
+        let mut level = &mut roots;
+        let parts: Vec<&str> = field.name.split('.').collect();
+        let (last, parents) = parts.split_last().unwrap_or((&"", &[]));
+        for part in parents {
+            let index = match level.iter().position(|n| n.name == *part) {
+                Some(i) => i,
+                None => {
+                    level.push(FieldNode { name: part.to_string(), value: None, kids: Vec::new() });
+                    level.len() - 1
+                }
+            };
+            level = &mut level[index].kids;
+        }
+        level.push(FieldNode { name: last.to_string(), value: export_value(doc, field), kids: Vec::new() });
+    }
+    roots
+}
+
+/// Valeur au format FDF : nom d'état pour les cases et boutons radio,
+/// chaîne ou tableau de chaînes pour les autres. `None` si le champ est vide.
+fn export_value(doc: &Document, field: &FormField) -> Option<Object> {
+    match (field.kind, &field.value) {
+        (_, FieldValue::Empty) => None,
+        (FieldKind::Checkbox | FieldKind::Radio, _) => {
+            inherited_attribute(doc, field.id, b"V").filter(|v| v.as_name().is_ok())
+        }
+        (_, FieldValue::Bool(b)) => Some(text_string(&b.to_string())),
+        (_, FieldValue::Text(s)) => Some(text_string(s)),
+        (_, FieldValue::List(items)) => Some(Object::Array(items.iter().map(|i| text_string(i)).collect())),
+    }
+}
+
+fn to_fdf(tree: &[FieldNode], file: &str) -> Vec<u8> {
+    let mut out = b"%FDF-1.2\n%\xE2\xE3\xCF\xD3\n1 0 obj\n<< /FDF << ".to_vec();
+    if !file.is_empty() {
+        out.extend_from_slice(b"/F ");
+        write_string(&mut out, &text_string(file));
+        out.push(b' ');
+    }
+    out.extend_from_slice(b"/Fields ");
+    write_fdf_fields(&mut out, tree);
+    out.extend_from_slice(b" >> >>\nendobj\ntrailer\n<< /Root 1 0 R >>\n%%EOF\n");
+    out
+}
+
+fn write_fdf_fields(out: &mut Vec<u8>, nodes: &[FieldNode]) {
+    out.push(b'[');
+    for node in nodes {
+        out.extend_from_slice(b"\n<< /T ");
+        write_string(out, &text_string(&node.name));
+        match &node.value {
+            Some(Object::Array(items)) => {
+                out.extend_from_slice(b" /V [");
+                for item in items {
+                    out.push(b' ');
+                    write_string(out, item);
+                }
+                out.extend_from_slice(b" ]");
+            }
+            Some(Object::Name(state)) => {
+                out.extend_from_slice(b" /V ");
+                write_name(out, state);
+            }
+            Some(text) => {
+                out.extend_from_slice(b" /V ");
+                write_string(out, text);
+            }
+            None => {}
+        }
+        if !node.kids.is_empty() {
+            out.extend_from_slice(b" /Kids ");
+            write_fdf_fields(out, &node.kids);
+        }
+        out.extend_from_slice(b" >>");
+    }
+    out.extend_from_slice(b"\n]");
+}
+
+/// Chaîne en hexadécimal : aucun caractère à échapper.
+fn write_string(out: &mut Vec<u8>, text: &Object) {
+    out.push(b'<');
+    for byte in text.as_str().unwrap_or_default() {
+        out.extend_from_slice(format!("{:02X}", byte).as_bytes());
+    }
+    out.push(b'>');
+}
+
+/// Nom PDF : délimiteurs et octets hors 33–126 en séquences `#xx`.
+fn write_name(out: &mut Vec<u8>, name: &[u8]) {
+    out.push(b'/');
+    for &byte in name {
+        if b"()<>[]{}/%#".contains(&byte) || !(33..=126).contains(&byte) {
+            out.extend_from_slice(format!("#{:02X}", byte).as_bytes());
+        } else {
+            out.push(byte);
+        }
+    }
+}
+
+fn to_xfdf(tree: &[FieldNode], file: &str) -> Vec<u8> {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<xfdf xmlns=\"http://ns.adobe.com/xfdf/\" xml:space=\"preserve\">\n",
+    );
+    if !file.is_empty() {
+        out.push_str(&format!("<f href=\"{}\"/>\n", xml_escape(file)));
+    }
+    out.push_str("<fields>\n");
+    write_xfdf_fields(&mut out, tree);
+    out.push_str("</fields>\n</xfdf>\n");
+    out.into_bytes()
+}
+
+fn write_xfdf_fields(out: &mut String, nodes: &[FieldNode]) {
+    for node in nodes {
+        out.push_str(&format!("<field name=\"{}\">\n", xml_escape(&node.name)));
+        let values: Vec<String> = match &node.value {
+            Some(Object::Array(items)) => items.iter().map(object_text).collect(),
+            Some(value) => vec![object_text(value)],
+            None => Vec::new(),
+        };
+        for value in values {
+            out.push_str(&format!("<value>{}</value>\n", xml_escape(&value)));
+        }
+        write_xfdf_fields(out, &node.kids);
+        out.push_str("</field>\n");
+    }
+}
+
+fn object_text(value: &Object) -> String {
+    match value {
+        Object::Name(name) => String::from_utf8_lossy(name).into_owned(),
+        other => lopdf::decode_text_string(other).unwrap_or_default(),
+    }
+}
@@ -0,0 +1,780 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{decode_text_string, text_string, Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::flatten::flatten_document;
+use super::font::{add_standard_font, text_width, win_ansi_encode, HELVETICA};
+use super::utils::{
+    find_catalog, finalize, inherited_attribute, load_document, prune_unreachable, resolve_dict,
+};
+
+// Drapeaux /Ff (ISO 32000, tables 221, 226, 228 et 230).
+const FF_READ_ONLY: i64 = 1;
+const FF_MULTILINE: i64 = 1 << 12;
+const FF_PASSWORD: i64 = 1 << 13;
+const FF_RADIO: i64 = 1 << 15;
+const FF_PUSHBUTTON: i64 = 1 << 16;
+const FF_COMBO: i64 = 1 << 17;
+const FF_EDIT: i64 = 1 << 18;
+const FF_MULTI_SELECT: i64 = 1 << 21;
+
+/// Type d'un champ terminal, d'après `/FT` et `/Ff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Checkbox,
+    Radio,
+    PushButton,
+    ComboBox,
+    ListBox,
+    Signature,
+}
+
+impl FieldKind {
+    /// Nom utilisé dans les réponses JSON.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Checkbox => "checkbox",
+            Self::Radio => "radio",
+            Self::PushButton => "button",
+            Self::ComboBox => "combo",
+            Self::ListBox => "list",
+            Self::Signature => "signature",
+        }
+    }
+}
+
+/// Valeur d'un champ, lue dans le document ou fournie en JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// Champ vide (`null` en JSON).
+    Empty,
+    Bool(bool),
+    Text(String),
+    /// Sélection multiple d'une liste.
+    List(Vec<String>),
+}
+
+impl FieldValue {
+    /// `null`, booléen, texte, nombre (converti en texte) ou tableau de textes.
+    pub fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => Some(Self::Empty),
+            Value::Bool(b) => Some(Self::Bool(*b)),
+            Value::String(s) => Some(Self::Text(s.clone())),
+            Value::Number(n) => Some(Self::Text(n.to_string())),
+            Value::Array(items) => items
+                .iter()
+                .map(|v| v.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .map(Self::List),
+            Value::Object(_) => None,
+        }
+    }
+
+    /// Inverse de [`FieldValue::from_json`].
+    pub fn to_json(&self) -> Value {
+        match self {
+            Self::Empty => Value::Null,
+            Self::Bool(b) => Value::Bool(*b),
+            Self::Text(s) => Value::String(s.clone()),
+            Self::List(items) => json!(items),
+        }
+    }
+}
+
+/// Champ terminal du formulaire (celui qui porte la valeur).
+#[derive(Debug, Clone)]
+pub struct FormField {
+    pub id: ObjectId,
+    /// Nom complet, parties séparées par des points (`client.nom`).
+    pub name: String,
+    pub kind: FieldKind,
+    pub flags: i64,
+    pub value: FieldValue,
+    /// Valeurs acceptées : valeurs d'export des cases, boutons radio et listes.
+    pub options: Vec<String>,
+    /// Annotations `Widget` affichant le champ.
+    pub widgets: Vec<ObjectId>,
+}
+
+impl FormField {
+    pub fn read_only(&self) -> bool {
+        self.flags & FF_READ_ONLY != 0
+    }
+}
+
+/// Cause d'un rejet de valeur par [`fill_document`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldErrorReason {
+    UnknownField,
+    InvalidValue,
+    ReadOnly,
+    NotFillable,
+}
+
+impl FieldErrorReason {
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::UnknownField => "unknown_field",
+            Self::InvalidValue => "invalid_value",
+            Self::ReadOnly => "read_only",
+            Self::NotFillable => "not_fillable",
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::UnknownField => "Champ inconnu",
+            Self::InvalidValue => "Valeur invalide pour ce champ",
+            Self::ReadOnly => "Champ en lecture seule",
+            Self::NotFillable => "Ce type de champ ne se remplit pas",
+        }
+    }
+}
+
+/// Valeur rejetée, avec les valeurs acceptées quand elles sont connues.
+#[derive(Debug, Clone)]
+pub struct FieldError {
+    pub field: String,
+    /// Type du champ, `None` s'il est inconnu.
+    pub kind: Option<FieldKind>,
+    pub reason: FieldErrorReason,
+    pub allowed: Vec<String>,
+}
+
+/// Représentation JSON des erreurs, pour les réponses `400`.
+pub fn field_errors_to_json(errors: &[FieldError]) -> Value {
+    Value::Array(
+        errors
+            .iter()
+            .map(|e| {
+                json!({
+                    "field": e.field,
+                    "type": e.kind.map(FieldKind::name),
+                    "reason": e.reason.code(),
+                    "message": e.reason.message(),
+                    "allowed": e.allowed,
+                })
+            })
+            .collect(),
+    )
+}
+
+// ── Lecture ───────────────────────────────────────────────────────────────────
+
+/// Dictionnaire `/AcroForm` du catalogue.
+fn acroform(doc: &Document) -> Option<&Dictionary> {
+    let catalog = doc.get_dictionary(find_catalog(doc)?).ok()?;
+    resolve_dict(doc, catalog.get(b"AcroForm").ok()?)
+}
+
+/// Dictionnaire `/AcroForm`, modifiable, qu'il soit direct ou référencé.
+pub fn acroform_mut(doc: &mut Document) -> Option<&mut Dictionary> {
+    let catalog_id = find_catalog(doc)?;
+    match doc.get_dictionary(catalog_id).ok()?.get(b"AcroForm").ok()? {
+        Object::Reference(id) => {
+            let id = *id;
+            doc.get_dictionary_mut(id).ok()
+        }
+        _ => match doc.get_dictionary_mut(catalog_id).ok()?.get_mut(b"AcroForm").ok()? {
+            Object::Dictionary(form) => Some(form),
+            _ => None,
+        },
+    }
+}
+
+/// Champs terminaux du formulaire, dans l'ordre de l'arbre `/Fields`.
+/// Vide si le document n'a pas de formulaire.
+pub fn form_fields(doc: &Document) -> Vec<FormField> {
+    let Some(roots) = acroform(doc).and_then(|f| f.get(b"Fields").and_then(Object::as_array).ok()) else {
+        return Vec::new();
+    };
+    let mut fields = Vec::new();
+    let mut visited = HashSet::new();
+    for root in roots.iter().filter_map(|r| r.as_reference().ok()) {
+        collect_fields(doc, root, "", &mut visited, &mut fields);
+    }
+    fields
+}
+
+fn collect_fields(
+    doc: &Document,
+    id: ObjectId,
+    prefix: &str,
+    visited: &mut HashSet<ObjectId>,
+    out: &mut Vec<FormField>,
+) {
+    // Protège des arbres cycliques.
+    if !visited.insert(id) {
+        return;
+    }
+    let Ok(dict) = doc.get_dictionary(id) else {
+        return;
+    };
+
+    let name = match dict.get(b"T").ok().and_then(|t| decode_text_string(t).ok()) {
+        Some(t) if prefix.is_empty() => t,
+        Some(t) => format!("{}.{}", prefix, t),
+        None => prefix.to_string(),
+    };
+
+    // Les enfants sans /T sont les widgets du champ, les autres des sous-champs.
+    let kids: Vec<ObjectId> = dict
+        .get(b"Kids")
+        .and_then(Object::as_array)
+        .map(|k| k.iter().filter_map(|r| r.as_reference().ok()).collect())
+        .unwrap_or_default();
+    let (children, mut widgets): (Vec<ObjectId>, Vec<ObjectId>) = kids
+        .into_iter()
+        .partition(|k| doc.get_dictionary(*k).is_ok_and(|d| d.has(b"T")));
+    for child in children {
+        collect_fields(doc, child, &name, visited, out);
+    }
+    // Champ et widget fusionnés dans un même dictionnaire
+    if dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Widget".as_slice()) {
+        widgets.insert(0, id);
+    }
+    if widgets.is_empty() {
+        return;
+    }
+
+    let flags = inherited_attribute(doc, id, b"Ff").and_then(|f| f.as_i64().ok()).unwrap_or(0);
+    let field_type = inherited_attribute(doc, id, b"FT");
+    let kind = match field_type.as_ref().and_then(|t| t.as_name().ok()) {
+        Some(b"Tx") => FieldKind::Text,
+        Some(b"Btn") if flags & FF_PUSHBUTTON != 0 => FieldKind::PushButton,
+        Some(b"Btn") if flags & FF_RADIO != 0 => FieldKind::Radio,
+        Some(b"Btn") => FieldKind::Checkbox,
+        Some(b"Ch") if flags & FF_COMBO != 0 => FieldKind::ComboBox,
+        Some(b"Ch") => FieldKind::ListBox,
+        Some(b"Sig") => FieldKind::Signature,
+        _ => return,
+    };
+
+    let mut field = FormField { id, name, kind, flags, value: FieldValue::Empty, options: Vec::new(), widgets };
+    field.options = match kind {
+        FieldKind::Checkbox | FieldKind::Radio => button_states(doc, &field)
+            .into_iter()
+            .map(|(export, _)| export)
+            .fold(Vec::new(), |mut acc, e| {
+                if !acc.contains(&e) {
+                    acc.push(e);
+                }
+                acc
+            }),
+        FieldKind::ComboBox | FieldKind::ListBox => choice_options(doc, id).into_iter().map(|(e, _)| e).collect(),
+        _ => Vec::new(),
+    };
+    field.value = read_value(doc, &field);
+    out.push(field);
+}
+
+/// Nom d'état « activé » d'un widget de case à cocher ou de bouton radio :
+/// la clé de `/AP /N` autre que `Off`.
+fn on_state(doc: &Document, widget: ObjectId) -> Option<Vec<u8>> {
+    let widget = doc.get_dictionary(widget).ok()?;
+    let normal = resolve_dict(doc, resolve_dict(doc, widget.get(b"AP").ok()?)?.get(b"N").ok()?)?;
+    normal.iter().map(|(k, _)| k).find(|k| k.as_slice() != b"Off").cloned()
+}
+
+/// Couples (valeur d'export, état du widget) d'un bouton. Avec `/Opt`, la
+/// valeur d'export du widget n est `Opt[n]` ; sinon c'est le nom de l'état.
+fn button_states(doc: &Document, field: &FormField) -> Vec<(String, Vec<u8>)> {
+    let opt = inherited_attribute(doc, field.id, b"Opt")
+        .and_then(|o| o.as_array().ok().cloned())
+        .unwrap_or_default();
+    let states: Vec<(String, Vec<u8>)> = field
+        .widgets
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &w)| {
+            let state = on_state(doc, w)?;
+            let export = opt
+                .get(i)
+                .and_then(|o| decode_text_string(o).ok())
+                .unwrap_or_else(|| String::from_utf8_lossy(&state).into_owned());
+            Some((export, state))
+        })
+        .collect();
+    // Case sans apparence : l'état conventionnel est `Yes`.
+    if states.is_empty() && field.kind == FieldKind::Checkbox {
+        return vec![("Yes".to_string(), b"Yes".to_vec())];
+    }
+    states
+}
+
+/// Couples (valeur d'export, libellé affiché) d'une liste ou d'un menu.
+fn choice_options(doc: &Document, id: ObjectId) -> Vec<(String, String)> {
+    let Some(Object::Array(items)) = inherited_attribute(doc, id, b"Opt") else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| match doc.dereference(item).ok()?.1 {
+            Object::Array(pair) if pair.len() == 2 => {
+                Some((decode_text_string(&pair[0]).ok()?, decode_text_string(&pair[1]).ok()?))
+            }
+            other => {
+                let text = decode_text_string(other).ok()?;
+                Some((text.clone(), text))
+            }
+        })
+        .collect()
+}
+
+fn read_value(doc: &Document, field: &FormField) -> FieldValue {
+    let Some(value) = inherited_attribute(doc, field.id, b"V") else {
+        return match field.kind {
+            FieldKind::Checkbox => FieldValue::Bool(false),
+            _ => FieldValue::Empty,
+        };
+    };
+    match (field.kind, &value) {
+        (FieldKind::Checkbox, Object::Name(state)) => FieldValue::Bool(state.as_slice() != b"Off"),
+        (FieldKind::Radio, Object::Name(state)) => button_states(doc, field)
+            .into_iter()
+            .find(|(_, s)| s == state)
+            .map_or(FieldValue::Empty, |(export, _)| FieldValue::Text(export)),
+        (_, Object::Array(items)) => {
+            FieldValue::List(items.iter().filter_map(|i| decode_text_string(i).ok()).collect())
+        }
+        (_, other) => match doc.dereference(other).ok().and_then(|(_, v)| decode_text_string(v).ok()) {
+            Some(text) => FieldValue::Text(text),
+            None => FieldValue::Empty,
+        },
+    }
+}
+
+// ── Remplissage ───────────────────────────────────────────────────────────────
+
+/// Remplit le formulaire avec `values` (nom complet → valeur JSON) et
+/// retourne le PDF rempli, aplati si `flatten` est vrai.
+pub fn fill_form(
+    data: &[u8],
+    values: &Map<String, Value>,
+    flatten: bool,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut doc = load_document(data, password)?;
+    let catalog_id = find_catalog(&doc)
+        .ok_or_else(|| PdfError::InvalidFile("(catalogue introuvable)".to_string()))?;
+    fill_document(&mut doc, values)?;
+    if flatten {
+        flatten_document(&mut doc, None)?;
+    }
+    // Les apparences remplacées ne sont plus référencées.
+    prune_unreachable(&mut doc);
+    finalize(&mut doc, catalog_id, encryption)
+}
+
+/// Remplit le formulaire en place : `/V`, état des widgets et flux
+/// d'apparence. Toutes les valeurs sont vérifiées avant la première
+/// modification ; les rejets sont retournés ensemble.
+pub fn fill_document(doc: &mut Document, values: &Map<String, Value>) -> Result<()> {
+    let fields = form_fields(doc);
+    if fields.is_empty() {
+        return Err(PdfError::NoForm);
+    }
+
+    let mut errors = Vec::new();
+    let mut updates: Vec<(&FormField, FieldValue)> = Vec::new();
+    for (name, json) in values {
+        let matching: Vec<&FormField> = fields.iter().filter(|f| &f.name == name).collect();
+        if matching.is_empty() {
+            errors.push(FieldError {
+                field: name.clone(),
+                kind: None,
+                reason: FieldErrorReason::UnknownField,
+                allowed: Vec::new(),
+            });
+            continue;
+        }
+        for field in matching {
+            match FieldValue::from_json(json).ok_or(FieldErrorReason::InvalidValue).and_then(|v| check_value(doc, field, v)) {
+                Ok(value) => updates.push((field, value)),
+                Err(reason) => errors.push(FieldError {
+                    field: name.clone(),
+                    kind: Some(field.kind),
+                    reason,
+                    allowed: field.options.clone(),
+                }),
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(PdfError::InvalidFormData(errors));
+    }
+
+    let mut fonts = FontCache::default();
+    for (field, value) in updates {
+        apply_value(doc, field, &value, &mut fonts)?;
+    }
+
+    // Un formulaire XFA prendrait le pas sur les valeurs AcroForm écrites.
+    if let Some(form) = acroform_mut(doc) {
+        form.remove(b"XFA");
+    }
+    Ok(())
+}
+
+/// Vérifie `value` pour le type du champ et la normalise.
+fn check_value(doc: &Document, field: &FormField, value: FieldValue) -> std::result::Result<FieldValue, FieldErrorReason> {
+    if matches!(field.kind, FieldKind::PushButton | FieldKind::Signature) {
+        return Err(FieldErrorReason::NotFillable);
+    }
+    if field.read_only() {
+        return Err(FieldErrorReason::ReadOnly);
+    }
+    let allowed = |s: &String| field.options.contains(s);
+
+    match (field.kind, value) {
+        (_, FieldValue::Empty) => Ok(FieldValue::Empty),
+        (FieldKind::Text, FieldValue::Text(s)) => {
+            let max_len = inherited_attribute(doc, field.id, b"MaxLen").and_then(|m| m.as_i64().ok());
+            match max_len {
+                Some(max) if s.chars().count() as i64 > max => Err(FieldErrorReason::InvalidValue),
+                _ => Ok(FieldValue::Text(s)),
+            }
+        }
+        (FieldKind::Checkbox, FieldValue::Bool(b)) => Ok(FieldValue::Bool(b)),
+        (FieldKind::Checkbox | FieldKind::Radio, FieldValue::Text(s)) if s == "Off" => Ok(FieldValue::Empty),
+        (FieldKind::Checkbox | FieldKind::Radio, FieldValue::Text(s)) if allowed(&s) => Ok(FieldValue::Text(s)),
+        (FieldKind::ComboBox, FieldValue::Text(s)) if allowed(&s) || field.flags & FF_EDIT != 0 => {
+            Ok(FieldValue::Text(s))
+        }
+        (FieldKind::ListBox, FieldValue::Text(s)) if allowed(&s) => Ok(FieldValue::Text(s)),
+        (FieldKind::ListBox, FieldValue::List(items))
+            if field.flags & FF_MULTI_SELECT != 0 && items.iter().all(allowed) =>
+        {
+            Ok(FieldValue::List(items))
+        }
+        _ => Err(FieldErrorReason::InvalidValue),
+    }
+}
+
+/// Régénère l'apparence des widgets d'après la valeur actuelle du champ,
+/// pour les documents qui comptent sur le lecteur (`/NeedAppearances`).
+pub fn regenerate_appearance(doc: &mut Document, field: &FormField) -> Result<()> {
+    apply_value(doc, field, &field.value, &mut FontCache::default())
+}
+
+/// `/NeedAppearances` : les apparences enregistrées peuvent être périmées.
+pub fn needs_appearances(doc: &Document) -> bool {
+    acroform(doc)
+        .and_then(|f| f.get(b"NeedAppearances").and_then(Object::as_bool).ok())
+        .unwrap_or(false)
+}
+
+fn apply_value(doc: &mut Document, field: &FormField, value: &FieldValue, fonts: &mut FontCache) -> Result<()> {
+    match field.kind {
+        FieldKind::Checkbox | FieldKind::Radio => {
+            let states = button_states(doc, field);
+            let state: Vec<u8> = match value {
+                FieldValue::Bool(true) => states.first().map_or(b"Yes".to_vec(), |(_, s)| s.clone()),
+                FieldValue::Text(export) => states
+                    .iter()
+                    .find(|(e, _)| e == export)
+                    .map_or(b"Off".to_vec(), |(_, s)| s.clone()),
+                _ => b"Off".to_vec(),
+            };
+            doc.get_dictionary_mut(field.id)?.set("V", Object::Name(state.clone()));
+            for &widget in &field.widgets {
+                let own = on_state(doc, widget);
+                if own.is_none() && state != b"Off" {
+                    add_check_appearance(doc, widget, &state)?;
+                }
+                let shown = if own.is_none_or(|s| s == state) { state.clone() } else { b"Off".to_vec() };
+                doc.get_dictionary_mut(widget)?.set("AS", Object::Name(shown));
+            }
+        }
+        FieldKind::Text | FieldKind::ComboBox | FieldKind::ListBox => {
+            let options = choice_options(doc, field.id);
+            let selected: Vec<String> = match value {
+                FieldValue::Text(s) => vec![s.clone()],
+                FieldValue::List(items) => items.clone(),
+                _ => Vec::new(),
+            };
+
+            let dict = doc.get_dictionary_mut(field.id)?;
+            match value {
+                FieldValue::Empty => {
+                    dict.remove(b"V");
+                }
+                FieldValue::List(items) => {
+                    dict.set("V", Object::Array(items.iter().map(|s| text_string(s)).collect()));
+                }
+                _ => dict.set("V", text_string(&selected[0])),
+            }
+            if field.kind == FieldKind::ListBox {
+                // Indices de sélection, lus par certains lecteurs à la place de /V
+                let indices: Vec<Object> = options
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (export, _))| selected.contains(export))
+                    .map(|(i, _)| Object::Integer(i as i64))
+                    .collect();
+                dict.set("I", Object::Array(indices));
+            }
+
+            // Libellés affichés : valeur saisie, option choisie ou liste entière.
+            let display = |export: &String| {
+                options.iter().find(|(e, _)| e == export).map_or(export.clone(), |(_, d)| d.clone())
+            };
+            let (lines, highlighted): (Vec<String>, Vec<usize>) = match field.kind {
+                FieldKind::ListBox => (
+                    options.iter().map(|(_, d)| d.clone()).collect(),
+                    options.iter().enumerate().filter(|(_, (e, _))| selected.contains(e)).map(|(i, _)| i).collect(),
+                ),
+                FieldKind::ComboBox => (selected.iter().take(1).map(display).collect(), Vec::new()),
+                _ => (selected, Vec::new()),
+            };
+            for &widget in &field.widgets {
+                text_appearance(doc, field, widget, &lines, &highlighted, fonts)?;
+            }
+        }
+        FieldKind::PushButton | FieldKind::Signature => {}
+    }
+    Ok(())
+}
+
+// ── Apparences ────────────────────────────────────────────────────────────────
+
+/// Police Helvetica ajoutée au document quand celle du `/DA` est inutilisable.
+#[derive(Default)]
+struct FontCache {
+    fallback: Option<ObjectId>,
+}
+
+/// Police, corps et couleur lus dans l'apparence par défaut (`/DA`).
+struct DefaultAppearance {
+    font: Vec<u8>,
+    size: f32,
+    color: Vec<Operation>,
+}
+
+fn default_appearance(doc: &Document, field: &FormField, widget: ObjectId) -> DefaultAppearance {
+    let da = doc
+        .get_dictionary(widget)
+        .ok()
+        .and_then(|w| w.get(b"DA").ok().cloned())
+        .or_else(|| inherited_attribute(doc, field.id, b"DA"))
+        .or_else(|| acroform(doc).and_then(|f| f.get(b"DA").ok().cloned()));
+    let ops = da
+        .and_then(|da| da.as_str().ok().map(<[u8]>::to_vec))
+        .and_then(|bytes| Content::decode(&bytes).ok())
+        .map(|c| c.operations)
+        .unwrap_or_default();
+
+    let mut appearance = DefaultAppearance { font: b"Helv".to_vec(), size: 0.0, color: Vec::new() };
+    for op in ops {
+        match op.operator.as_str() {
+            "Tf" if op.operands.len() == 2 => {
+                if let Ok(name) = op.operands[0].as_name() {
+                    appearance.font = name.to_vec();
+                }
+                appearance.size = op.operands[1].as_float().unwrap_or(0.0);
+            }
+            "g" | "rg" | "k" => appearance.color.push(op),
+            _ => {}
+        }
+    }
+    appearance
+}
+
+/// Police du `/DR` utilisable avec un texte encodé en WinAnsi : police
+/// simple sans encodage propre ou avec un encodage standard.
+fn resource_font(doc: &Document, name: &[u8]) -> Option<Object> {
+    let resources = resolve_dict(doc, acroform(doc)?.get(b"DR").ok()?)?;
+    let entry = resolve_dict(doc, resources.get(b"Font").ok()?)?.get(name).ok()?;
+    let font = resolve_dict(doc, entry)?;
+    let simple = matches!(font.get(b"Subtype").and_then(Object::as_name), Ok(b"Type1" | b"TrueType"));
+    let encoding_ok = match font.get(b"Encoding") {
+        Err(_) => true,
+        Ok(Object::Name(e)) => matches!(e.as_slice(), b"WinAnsiEncoding" | b"StandardEncoding"),
+        Ok(_) => true,
+    };
+    (simple && encoding_ok).then(|| entry.clone())
+}
+
+/// Génère le flux d'apparence d'un champ texte ou d'une liste : une ligne
+/// par élément de `lines`, fond surligné pour les lignes de `highlighted`.
+fn text_appearance(
+    doc: &mut Document,
+    field: &FormField,
+    widget: ObjectId,
+    lines: &[String],
+    highlighted: &[usize],
+    fonts: &mut FontCache,
+) -> Result<()> {
+    let rect: Vec<f32> = doc
+        .get_dictionary(widget)?
+        .get(b"Rect")
+        .and_then(Object::as_array)
+        .map(|r| r.iter().filter_map(|v| v.as_float().ok()).collect())
+        .unwrap_or_default();
+    let [x0, y0, x1, y1] = rect[..] else {
+        return Ok(());
+    };
+    let (width, height) = ((x1 - x0).abs(), (y1 - y0).abs());
+
+    let da = default_appearance(doc, field, widget);
+    let (font_name, font) = match resource_font(doc, &da.font) {
+        Some(font) => (da.font.clone(), font),
+        None => {
+            let id = *fonts.fallback.get_or_insert_with(|| add_standard_font(doc, HELVETICA));
+            (b"Helv".to_vec(), Object::Reference(id))
+        }
+    };
+
+    let multiline = field.kind == FieldKind::ListBox || field.flags & FF_MULTILINE != 0;
+    let padding = 2.0;
+    let mut encoded: Vec<Vec<u8>> = lines
+        .iter()
+        .map(|l| {
+            if field.flags & FF_PASSWORD != 0 {
+                vec![b'*'; l.chars().count()]
+            } else {
+                win_ansi_encode(l)
+            }
+        })
+        .collect();
+
+    // Corps 0 : taille automatique, ajustée à la hauteur puis à la largeur.
+    let size = if da.size > 0.0 {
+        da.size
+    } else if multiline {
+        12.0f32.min(height - 2.0 * padding).max(4.0)
+    } else {
+        let fit_height = (height - 2.0 * padding) / 1.15;
+        let widest = encoded.iter().map(|l| text_width(l, 1.0)).fold(0.0, f32::max);
+        let fit_width = if widest > 0.0 { (width - 2.0 * padding) / widest } else { f32::MAX };
+        12.0f32.min(fit_height).min(fit_width).max(4.0)
+    };
+    if field.kind == FieldKind::Text && multiline {
+        encoded = encoded.iter().flat_map(|l| wrap_line(l, size, width - 2.0 * padding)).collect();
+    }
+    let leading = size * 1.15;
+    let alignment = inherited_attribute(doc, field.id, b"Q")
+        .or_else(|| acroform(doc).and_then(|f| f.get(b"Q").ok().cloned()))
+        .and_then(|q| q.as_i64().ok())
+        .unwrap_or(0);
+
+    let mut ops = vec![
+        Operation::new("BMC", vec![Object::Name(b"Tx".to_vec())]),
+        Operation::new("q", vec![]),
+        Operation::new("re", vec![1.into(), 1.into(), Object::Real(width - 2.0), Object::Real(height - 2.0)]),
+        Operation::new("W", vec![]),
+        Operation::new("n", vec![]),
+    ];
+    // Première ligne en haut du champ s'il est multiligne, centrée sinon.
+    let first_baseline = if multiline {
+        height - padding - size
+    } else {
+        (height - size) / 2.0 + 0.22 * size
+    };
+    for (i, line) in encoded.into_iter().enumerate() {
+        let y = first_baseline - i as f32 * leading;
+        if highlighted.contains(&i) {
+            ops.extend([
+                Operation::new("rg", vec![Object::Real(0.6), Object::Real(0.75), Object::Real(0.9)]),
+                Operation::new("re", vec![1.into(), Object::Real(y - 0.22 * size - 1.0), Object::Real(width - 2.0), Object::Real(leading)]),
+                Operation::new("f", vec![]),
+            ]);
+        }
+        let line_width = text_width(&line, size);
+        let x = match alignment {
+            1 => (width - line_width) / 2.0,
+            2 => width - padding - line_width,
+            _ => padding,
+        };
+        ops.push(Operation::new("BT", vec![]));
+        ops.push(Operation::new("Tf", vec![Object::Name(font_name.clone()), Object::Real(size)]));
+        ops.extend(da.color.iter().cloned());
+        ops.push(Operation::new("Td", vec![Object::Real(x), Object::Real(y)]));
+        ops.push(Operation::new("Tj", vec![Object::String(line, StringFormat::Literal)]));
+        ops.push(Operation::new("ET", vec![]));
+    }
+    ops.push(Operation::new("Q", vec![]));
+    ops.push(Operation::new("EMC", vec![]));
+
+    let resources = Dictionary::from_iter(vec![(
+        "Font",
+        Object::Dictionary(Dictionary::from_iter(vec![(font_name, font)])),
+    )]);
+    let stream_id = add_form_xobject(doc, width, height, resources, Content { operations: ops }.encode()?);
+    doc.get_dictionary_mut(widget)?.set(
+        "AP",
+        Object::Dictionary(Dictionary::from_iter(vec![("N", Object::Reference(stream_id))])),
+    );
+    Ok(())
+}
+
+/// Découpe une ligne encodée aux espaces pour tenir dans `max_width`.
+fn wrap_line(line: &[u8], size: f32, max_width: f32) -> Vec<Vec<u8>> {
+    let mut lines = Vec::new();
+    let mut current: Vec<u8> = Vec::new();
+    for word in line.split(|&b| b == b' ') {
+        let candidate = if current.is_empty() {
+            word.to_vec()
+        } else {
+            [current.as_slice(), b" ", word].concat()
+        };
+        if !current.is_empty() && text_width(&candidate, size) > max_width {
+            lines.push(std::mem::replace(&mut current, word.to_vec()));
+        } else {
+            current = candidate;
+        }
+    }
+    lines.push(current);
+    lines
+}
+
+/// Apparences d'une case sans `/AP` : coche dessinée (état `state`) et
+/// case vide (`Off`).
+fn add_check_appearance(doc: &mut Document, widget: ObjectId, state: &[u8]) -> Result<()> {
+    let rect: Vec<f32> = doc
+        .get_dictionary(widget)?
+        .get(b"Rect")
+        .and_then(Object::as_array)
+        .map(|r| r.iter().filter_map(|v| v.as_float().ok()).collect())
+        .unwrap_or_default();
+    let [x0, y0, x1, y1] = rect[..] else {
+        return Ok(());
+    };
+    let (w, h) = ((x1 - x0).abs(), (y1 - y0).abs());
+
+    let check = format!(
+        "q 0 G {} w 1 J 1 j {} {} m {} {} l {} {} l S Q",
+        (w.min(h) / 10.0).max(0.5),
+        w * 0.2, h * 0.5, w * 0.4, h * 0.25, w * 0.8, h * 0.8,
+    );
+    let on = add_form_xobject(doc, w, h, Dictionary::new(), check.into_bytes());
+    let off = add_form_xobject(doc, w, h, Dictionary::new(), Vec::new());
+    doc.get_dictionary_mut(widget)?.set(
+        "AP",
+        Object::Dictionary(Dictionary::from_iter(vec![(
+            "N",
+            Object::Dictionary(Dictionary::from_iter(vec![
+                (state.to_vec(), Object::Reference(on)),
+                (b"Off".to_vec(), Object::Reference(off)),
+            ])),
+        )])),
+    );
+    Ok(())
+}
+
+fn add_form_xobject(doc: &mut Document, width: f32, height: f32, resources: Dictionary, content: Vec<u8>) -> ObjectId {
+    let dict = Dictionary::from_iter(vec![
+        ("Type", Object::Name(b"XObject".to_vec())),
+        ("Subtype", Object::Name(b"Form".to_vec())),
+        ("BBox", Object::Array(vec![0.into(), 0.into(), Object::Real(width), Object::Real(height)])),
+        ("Resources", Object::Dictionary(resources)),
+    ]);
+    doc.add_object(Stream::new(dict, content))
+}
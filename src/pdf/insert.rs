@@ -0,0 +1,81 @@
+use lopdf::{Document, ObjectId};
+use std::collections::HashSet;
+
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::utils::{
+    copy_objects, find_catalog, find_pages_root, finalize, inline_inherited_attributes,
+    insert_catalog, insert_pages_node, load_document, set_parent,
+};
+
+/// Insère des pages de `source` dans `target`, après la page `after` de
+/// `target` (0 = avant la page 1).
+///
+/// `pages` liste les pages de `source` à insérer, dans l'ordre voulu ; si
+/// `None`, tout le document est inséré.
+pub fn insert_pages(
+    target: &[u8],
+    source: &[u8],
+    after: u32,
+    pages: Option<&[u32]>,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut dst = load_document(target, password)?;
+    dst.decompress();
+    let mut src = load_document(source, password).map_err(|e| {
+        if e.is_password_error() { e } else { PdfError::InvalidFile(format!("(document à insérer) : {}", e)) }
+    })?;
+    src.decompress();
+
+    let dst_pages: Vec<ObjectId> = dst.get_pages().into_values().collect();
+    if after as usize > dst_pages.len() {
+        return Err(PdfError::PageOutOfRange { page: after, total: dst_pages.len() as u32 });
+    }
+
+    let src_all = src.get_pages();
+    let src_pages: Vec<ObjectId> = match pages {
+        Some(numbers) => numbers
+            .iter()
+            .map(|&n| {
+                src_all
+                    .get(&n)
+                    .copied()
+                    .ok_or(PdfError::PageOutOfRange { page: n, total: src_all.len() as u32 })
+            })
+            .collect::<Result<_>>()?,
+        None => src_all.values().copied().collect(),
+    };
+
+    // Les deux arbres de pages sont remplacés par un seul : les attributs
+    // hérités doivent être recopiés sur les pages avant la reconstruction.
+    inline_inherited_attributes(&mut dst, &dst_pages);
+    inline_inherited_attributes(&mut src, &src_pages);
+
+    let mut out = Document::with_version("1.5");
+
+    let dst_map = copy_objects(&dst, &mut out, &structure_ids(&dst));
+    let src_map = copy_objects(&src, &mut out, &structure_ids(&src));
+
+    let (before, rest) = dst_pages.split_at(after as usize);
+    let new_page_ids: Vec<ObjectId> = before
+        .iter()
+        .map(|id| dst_map[id])
+        .chain(src_pages.iter().map(|id| src_map[id]))
+        .chain(rest.iter().map(|id| dst_map[id]))
+        .collect();
+
+    let pages_id = insert_pages_node(&mut out, &new_page_ids, new_page_ids.len() as i64);
+    set_parent(&mut out, &new_page_ids, pages_id);
+
+    let catalog_id = insert_catalog(&mut out, pages_id);
+    finalize(&mut out, catalog_id, encryption)
+}
+
+/// Catalog et nœud Pages racine : reconstruits, jamais copiés.
+fn structure_ids(doc: &Document) -> HashSet<ObjectId> {
+    [find_catalog(doc), find_pages_root(doc)]
+        .into_iter()
+        .flatten()
+        .collect()
+}
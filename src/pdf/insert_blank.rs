@@ -0,0 +1,120 @@
+use lopdf::{Document, Object, ObjectId};
+use std::collections::HashSet;
+
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::utils::{
+    copy_objects, find_catalog, find_pages_root, finalize, inline_inherited_attributes,
+    insert_blank_page, insert_catalog, insert_pages_node, load_document, page_rotation,
+    set_parent, visible_box,
+};
+
+/// Dimensions des pages vierges insérées.
+#[derive(Debug, Clone, Copy)]
+pub enum BlankSize {
+    /// Même format et même rotation que la page précédente (la suivante
+    /// pour une insertion avant la page 1).
+    Neighbour,
+    /// Format fixe, en points : `(largeur, hauteur)`.
+    Fixed(f32, f32),
+}
+
+impl BlankSize {
+    /// Résout un nom de format (`auto`, `a3`, `a4`, `a5`, `letter`, `legal`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "" | "auto" => Some(Self::Neighbour),
+            "a3"     => Some(Self::Fixed(841.89, 1190.55)),
+            "a4"     => Some(Self::Fixed(595.28, 841.89)),
+            "a5"     => Some(Self::Fixed(419.53, 595.28)),
+            "letter" => Some(Self::Fixed(612.0, 792.0)),
+            "legal"  => Some(Self::Fixed(612.0, 1008.0)),
+            _ => None,
+        }
+    }
+}
+
+/// Insère une page vierge après chaque page de `after` (0 = avant la page 1),
+/// puis complète le document par des pages vierges jusqu'à un multiple de
+/// `pad_to` pages si demandé.
+pub fn insert_blank_pages(
+    data: &[u8],
+    after: &[u32],
+    pad_to: Option<u32>,
+    size: BlankSize,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut src = load_document(data, password)?;
+    src.decompress();
+
+    let all_pages = src.get_pages();
+    let total = all_pages.len() as u32;
+
+    for &n in after {
+        if n > total {
+            return Err(PdfError::PageOutOfRange { page: n, total });
+        }
+    }
+    let after: HashSet<u32> = after.iter().copied().collect();
+
+    let page_ids: Vec<ObjectId> = all_pages.values().copied().collect();
+    inline_inherited_attributes(&mut src, &page_ids);
+
+    // Boîte et rotation d'une page vierge : une page voisine tournée donne
+    // une page vierge tournée, sans quoi l'orientation alternerait.
+    let blank_box = |doc: &Document, neighbour: Option<ObjectId>| match (size, neighbour) {
+        (BlankSize::Fixed(w, h), _) => ([0.0, 0.0, w, h], 0),
+        (BlankSize::Neighbour, Some(id)) => (visible_box(doc, id), page_rotation(doc, id)),
+        (BlankSize::Neighbour, None) => ([0.0, 0.0, 595.28, 841.89], 0),
+    };
+
+    // Séquence finale : Ok(page existante) ou Err(boîte et rotation d'une
+    // page vierge)
+    let mut sequence: Vec<std::result::Result<ObjectId, ([f32; 4], i64)>> = Vec::new();
+    if after.contains(&0) {
+        sequence.push(Err(blank_box(&src, page_ids.first().copied())));
+    }
+    for (i, &id) in page_ids.iter().enumerate() {
+        sequence.push(Ok(id));
+        if after.contains(&(i as u32 + 1)) {
+            sequence.push(Err(blank_box(&src, Some(id))));
+        }
+    }
+    if let Some(n) = pad_to.filter(|&n| n > 1) {
+        let last = page_ids.last().copied();
+        while !sequence.len().is_multiple_of(n as usize) {
+            sequence.push(Err(blank_box(&src, last)));
+        }
+    }
+
+    let mut out = Document::with_version("1.5");
+
+    let excluded: HashSet<ObjectId> = [find_catalog(&src), find_pages_root(&src)]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let id_map = copy_objects(&src, &mut out, &excluded);
+
+    let new_page_ids: Vec<ObjectId> = sequence
+        .into_iter()
+        .map(|entry| match entry {
+            Ok(id) => id_map[&id],
+            Err((media_box, rotation)) => {
+                let page_id = insert_blank_page(&mut out, media_box);
+                if rotation != 0 {
+                    if let Ok(page) = out.get_dictionary_mut(page_id) {
+                        page.set("Rotate", Object::Integer(rotation));
+                    }
+                }
+                page_id
+            }
+        })
+        .collect();
+    let pages_id = insert_pages_node(&mut out, &new_page_ids, new_page_ids.len() as i64);
+    set_parent(&mut out, &new_page_ids, pages_id);
+
+    let catalog_id = insert_catalog(&mut out, pages_id);
+    finalize(&mut out, catalog_id, encryption)
+}
@@ -0,0 +1,393 @@
+use lopdf::{text_string, Dictionary, Document, Object};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+use super::archive::ZipArchive;
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::flatten::flatten_document;
+use super::forms::{acroform_mut, fill_document, form_fields, FieldKind, FormField};
+use super::merge::concatenate;
+use super::utils::{find_catalog, finalize, load_document, prune_unreachable};
+
+/// Nombre maximum d'enregistrements par publipostage.
+pub const MAX_RECORDS: usize = 1000;
+
+/// Modèle de nom par défaut des fichiers de l'archive.
+const DEFAULT_NAME: &str = "formulaire-{#}";
+
+/// Résultat du publipostage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MailMergeOutput {
+    /// Un seul PDF, les formulaires remplis à la suite.
+    Pdf,
+    /// Une archive ZIP, un PDF par enregistrement nommé d'après le modèle
+    /// (`{colonne}` remplacé par la valeur, `{#}` par le numéro).
+    Zip { names: Option<String> },
+}
+
+/// Remplit le formulaire `template` une fois par enregistrement du CSV
+/// (en-têtes = noms complets des champs), aplatit chaque exemplaire si
+/// `flatten` est vrai, puis les assemble selon `output`.
+pub fn mail_merge(
+    template: &[u8],
+    csv: &str,
+    flatten: bool,
+    output: &MailMergeOutput,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let template = load_document(template, password)?;
+    let fields = form_fields(&template);
+    if fields.is_empty() {
+        return Err(PdfError::NoForm);
+    }
+
+    let (columns, records) = parse_csv(csv)?;
+    if records.is_empty() {
+        return Err(PdfError::InvalidCsv("aucun enregistrement après la ligne d'en-tête".to_string()));
+    }
+    if records.len() > MAX_RECORDS {
+        return Err(PdfError::TooManyRecords(MAX_RECORDS));
+    }
+    // Les colonnes sans champ correspondant sont ignorées : elles peuvent
+    // servir au nom des fichiers. Aucune correspondance trahit un mauvais CSV.
+    if !columns.iter().any(|c| fields.iter().any(|f| &f.name == c)) {
+        return Err(PdfError::InvalidCsv(
+            "aucune colonne ne correspond à un champ du formulaire".to_string(),
+        ));
+    }
+
+    let fill = |n: usize, record: &Record| -> Result<Document> {
+        let mut doc = template.clone();
+        let values = record_values(&fields, &columns, &record.cells);
+        fill_document(&mut doc, &values).map_err(|e| match e {
+            PdfError::InvalidFormData(errors) => PdfError::InvalidRecord { line: record.line, errors },
+            other => other,
+        })?;
+        if flatten {
+            flatten_document(&mut doc, None)?;
+        } else if *output == MailMergeOutput::Pdf {
+            // Noms distincts d'un exemplaire à l'autre : `3.nom` au lieu de `nom`.
+            nest_fields(&mut doc, &n.to_string());
+        }
+        prune_unreachable(&mut doc);
+        Ok(doc)
+    };
+
+    match output {
+        MailMergeOutput::Pdf => {
+            let documents = records.iter().enumerate().map(|(i, record)| fill(i + 1, record));
+            concatenate(documents, &[], None, encryption)
+        }
+        MailMergeOutput::Zip { names } => {
+            let template_name = names.as_deref().filter(|t| !t.trim().is_empty()).unwrap_or(DEFAULT_NAME);
+            let parts = parse_name_template(template_name, &columns)?;
+            let mut archive = ZipArchive::new();
+            let mut used = HashSet::new();
+            for (i, record) in records.iter().enumerate() {
+                let mut doc = fill(i + 1, record)?;
+                let catalog_id = find_catalog(&doc)
+                    .ok_or_else(|| PdfError::InvalidFile("(catalogue introuvable)".to_string()))?;
+                let bytes = finalize(&mut doc, catalog_id, encryption)?;
+                let name = file_name(&render_name(&parts, &record.cells, i + 1), i + 1, &mut used);
+                archive.add(&name, &bytes)?;
+            }
+            archive.finish()
+        }
+    }
+}
+
+/// Valeurs JSON d'un enregistrement, converties selon le type du champ
+/// comme si elles venaient de `/fill-form`.
+fn record_values(fields: &[FormField], columns: &[String], cells: &[String]) -> Map<String, Value> {
+    let mut values = Map::new();
+    for (column, cell) in columns.iter().zip(cells) {
+        let Some(field) = fields.iter().find(|f| &f.name == column) else {
+            continue;
+        };
+        let cell = cell.trim();
+        let value = match field.kind {
+            FieldKind::Checkbox => match cell.to_lowercase().as_str() {
+                "" | "0" | "false" | "non" | "no" | "off" => Value::Bool(false),
+                "1" | "true" | "oui" | "yes" | "x" => Value::Bool(true),
+                _ => Value::String(cell.to_string()),
+            },
+            _ if cell.is_empty() => Value::Null,
+            // Sélection multiple : options séparées par des points-virgules.
+            FieldKind::ListBox if cell.contains(';') => Value::Array(
+                cell.split(';')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| Value::String(s.to_string()))
+                    .collect(),
+            ),
+            _ => Value::String(cell.to_string()),
+        };
+        values.insert(column.clone(), value);
+    }
+    values
+}
+
+/// Place les champs racine sous un champ parent nommé `name`.
+fn nest_fields(doc: &mut Document, name: &str) {
+    let Some(roots) = acroform_mut(doc).and_then(|f| f.get(b"Fields").and_then(Object::as_array).ok().cloned())
+    else {
+        return;
+    };
+    let parent = doc.add_object(Dictionary::from_iter(vec![
+        ("T", text_string(name)),
+        ("Kids", Object::Array(roots.clone())),
+    ]));
+    for root in roots.iter().filter_map(|r| r.as_reference().ok()) {
+        if let Ok(dict) = doc.get_dictionary_mut(root) {
+            dict.set("Parent", Object::Reference(parent));
+        }
+    }
+    if let Some(form) = acroform_mut(doc) {
+        form.set("Fields", Object::Array(vec![Object::Reference(parent)]));
+    }
+}
+
+// ── CSV ───────────────────────────────────────────────────────────────────────
+
+struct Record {
+    /// Ligne du fichier où commence l'enregistrement, pour les messages d'erreur.
+    line: usize,
+    cells: Vec<String>,
+}
+
+/// Lit un CSV RFC 4180 : en-têtes puis enregistrements. Le séparateur
+/// (virgule, point-virgule ou tabulation) est déduit de la ligne d'en-tête,
+/// les tableurs français exportant avec des points-virgules.
+fn parse_csv(input: &str) -> Result<(Vec<String>, Vec<Record>)> {
+    let input = input.strip_prefix('\u{FEFF}').unwrap_or(input);
+    let separator = detect_separator(input.lines().next().unwrap_or_default());
+
+    let mut rows: Vec<Record> = Vec::new();
+    let mut cells: Vec<String> = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    cell.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if cell.is_empty() => quoted = true,
+            '\n' if quoted => {
+                line += 1;
+                cell.push(c);
+            }
+            '\r' if !quoted => {}
+            '\n' => {
+                cells.push(std::mem::take(&mut cell));
+                rows.push(Record { line: start, cells: std::mem::take(&mut cells) });
+                line += 1;
+                start = line;
+            }
+            c if c == separator && !quoted => cells.push(std::mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+    if quoted {
+        return Err(PdfError::InvalidCsv(format!("guillemet non refermé (ligne {})", start)));
+    }
+    if !cell.is_empty() || !cells.is_empty() {
+        cells.push(cell);
+        rows.push(Record { line: start, cells });
+    }
+    // Lignes vides, fréquentes en fin de fichier
+    rows.retain(|r| r.cells.iter().any(|c| !c.trim().is_empty()));
+
+    let mut rows = rows.into_iter();
+    let Some(header) = rows.next() else {
+        return Err(PdfError::InvalidCsv("fichier vide".to_string()));
+    };
+    let columns: Vec<String> = header.cells.iter().map(|c| c.trim().to_string()).collect();
+    let mut records = Vec::new();
+    for mut record in rows {
+        if record.cells.len() > columns.len() {
+            return Err(PdfError::InvalidCsv(format!(
+                "ligne {} : {} colonnes pour {} en-têtes",
+                record.line,
+                record.cells.len(),
+                columns.len()
+            )));
+        }
+        record.cells.resize(columns.len(), String::new());
+        records.push(record);
+    }
+    Ok((columns, records))
+}
+
+/// Séparateur le plus fréquent de la ligne d'en-tête, hors guillemets (un
+/// en-tête `"Nom, prénom";Ville` est séparé par des points-virgules) ; la
+/// virgule à défaut.
+fn detect_separator(header_line: &str) -> char {
+    let mut counts = [(',', 0), (';', 0), ('\t', 0)];
+    let mut quoted = false;
+    for c in header_line.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if !quoted {
+            if let Some(entry) = counts.iter_mut().find(|(s, _)| *s == c) {
+                entry.1 += 1;
+            }
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|&(_, n)| n > 0)
+        .max_by_key(|&(_, n)| n)
+        .map_or(',', |(c, _)| c)
+}
+
+// ── Noms de fichiers ──────────────────────────────────────────────────────────
+
+enum NamePart {
+    Text(String),
+    Column(usize),
+    Number,
+}
+
+/// Découpe le modèle `attestation-{nom}` ; chaque `{colonne}` doit exister.
+fn parse_name_template(template: &str, columns: &[String]) -> Result<Vec<NamePart>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        if open > 0 {
+            parts.push(NamePart::Text(rest[..open].to_string()));
+        }
+        let Some(close) = rest[open..].find('}') else {
+            return Err(PdfError::InvalidNameTemplate(format!("accolade non refermée dans '{}'", template)));
+        };
+        let name = rest[open + 1..open + close].trim();
+        parts.push(match columns.iter().position(|c| c == name) {
+            Some(index) => NamePart::Column(index),
+            None if name == "#" => NamePart::Number,
+            None => return Err(PdfError::InvalidNameTemplate(format!("colonne inconnue '{}'", name))),
+        });
+        rest = &rest[open + close + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(NamePart::Text(rest.to_string()));
+    }
+    Ok(parts)
+}
+
+fn render_name(parts: &[NamePart], cells: &[String], number: usize) -> String {
+    parts
+        .iter()
+        .map(|part| match part {
+            NamePart::Text(text) => text.clone(),
+            NamePart::Column(index) => cells[*index].trim().to_string(),
+            NamePart::Number => number.to_string(),
+        })
+        .collect()
+}
+
+/// Nom de fichier sûr et unique dans l'archive : pas de séparateur de
+/// chemin ni de caractère refusé par Windows, 100 caractères au plus,
+/// suffixe `-2`, `-3`… en cas de doublon.
+fn file_name(raw: &str, number: usize, used: &mut HashSet<String>) -> String {
+    let cleaned: String = raw
+        .chars()
+        .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+        .take(100)
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('.');
+    let stem = match cleaned.strip_suffix(".pdf").or_else(|| cleaned.strip_suffix(".PDF")) {
+        Some(stem) if !stem.trim().is_empty() => stem.to_string(),
+        Some(_) => format!("formulaire-{}", number),
+        None if cleaned.is_empty() => format!("formulaire-{}", number),
+        None => cleaned.to_string(),
+    };
+
+    let mut name = format!("{}.pdf", stem);
+    let mut suffix = 2;
+    while !used.insert(name.to_lowercase()) {
+        name = format!("{}-{}.pdf", stem, suffix);
+        suffix += 1;
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(records: &[Record]) -> Vec<Vec<&str>> {
+        records.iter().map(|r| r.cells.iter().map(String::as_str).collect()).collect()
+    }
+
+    #[test]
+    fn detects_the_header_separator() {
+        for (input, expected) in [
+            ("nom,ville\nDupont,Lyon\n", ","),
+            ("nom;ville\nDupont;Lyon\n", ";"),
+            ("nom\tville\nDupont\tLyon\n", "\t"),
+        ] {
+            let (columns, records) = parse_csv(input).unwrap();
+            assert_eq!(columns, ["nom", "ville"], "séparateur {:?}", expected);
+            assert_eq!(cells(&records), [["Dupont", "Lyon"]]);
+        }
+    }
+
+    #[test]
+    fn ignores_separators_inside_quoted_headers() {
+        let (columns, records) = parse_csv("\"Nom, prénom\";Ville\n\"Dupont, Jean\";Lyon\n").unwrap();
+        assert_eq!(columns, ["Nom, prénom", "Ville"]);
+        assert_eq!(cells(&records), [["Dupont, Jean", "Lyon"]]);
+    }
+
+    #[test]
+    fn single_column_defaults_to_comma() {
+        let (columns, records) = parse_csv("nom\nDupont\n").unwrap();
+        assert_eq!(columns, ["nom"]);
+        assert_eq!(cells(&records), [["Dupont"]]);
+    }
+
+    #[test]
+    fn strips_the_utf8_bom() {
+        let (columns, _) = parse_csv("\u{FEFF}nom;ville\nDupont;Lyon").unwrap();
+        assert_eq!(columns, ["nom", "ville"]);
+    }
+
+    #[test]
+    fn reads_quoted_cells() {
+        let input = "nom,note\n\"Dupont\",\"dit \"\"Jo\"\", 2 lignes\nfin\"\nMartin,\"\"\n";
+        let (_, records) = parse_csv(input).unwrap();
+        assert_eq!(cells(&records), [["Dupont", "dit \"Jo\", 2 lignes\nfin"], ["Martin", ""]]);
+        // Un saut de ligne entre guillemets ne commence pas d'enregistrement.
+        assert_eq!(records.iter().map(|r| r.line).collect::<Vec<_>>(), [2, 4]);
+    }
+
+    #[test]
+    fn accepts_crlf_and_skips_blank_lines() {
+        let (columns, records) = parse_csv("nom;ville\r\nDupont;Lyon\r\n\r\n;\r\nMartin;Paris").unwrap();
+        assert_eq!(columns, ["nom", "ville"]);
+        assert_eq!(cells(&records), [["Dupont", "Lyon"], ["Martin", "Paris"]]);
+    }
+
+    #[test]
+    fn pads_short_records() {
+        let (_, records) = parse_csv("nom,ville,code\nDupont,Lyon\n").unwrap();
+        assert_eq!(cells(&records), [["Dupont", "Lyon", ""]]);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(matches!(parse_csv(""), Err(PdfError::InvalidCsv(_))));
+        assert!(matches!(parse_csv("\n\n"), Err(PdfError::InvalidCsv(_))));
+        assert!(matches!(parse_csv("nom\n\"Dupont\n"), Err(PdfError::InvalidCsv(m)) if m.contains("ligne 2")));
+        assert!(matches!(parse_csv("nom,ville\nDupont,Lyon,69\n"), Err(PdfError::InvalidCsv(m)) if m.contains("ligne 2")));
+    }
+}
@@ -1,36 +1,103 @@
-use lopdf::{Document, ObjectId};
+use lopdf::{Dictionary, Document, Object, ObjectId};
 use std::collections::HashSet;
 
+use super::bookmarks::{outline, Bookmark};
+use super::encrypt::Encryption;
 use super::error::{PdfError, Result};
+use super::toc::{build_toc_pages, TocEntry};
 use super::utils::{
     copy_objects, find_catalog, find_pages_root,
     finalize, insert_catalog, insert_pages_node,
-    load_document, set_parent,
+    load_document, page_rotation, remap_object, resolve_dict, set_parent, visible_box,
 };
 
+/// Contenu de la table des matières insérée en tête du document fusionné.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TocMode {
+    /// Une entrée par fichier.
+    Files,
+    /// Une entrée par fichier, suivie des signets du fichier.
+    Bookmarks,
+}
+
 /// Fusionne plusieurs PDFs (fournis en bytes bruts) en un seul document.
 /// Le parsing est effectué ici — une seule fois par fichier — pour éviter
 /// les échecs sur les PDFs avec xref en stream (PDF ≥ 1.5, scanners, etc.)
-pub fn merge_documents(files: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+///
+/// Avec `toc`, une table des matières cliquable est insérée en tête ;
+/// `titles` donne le titre de chaque fichier (`Document n` à défaut).
+pub fn merge_documents(
+    files: Vec<Vec<u8>>,
+    titles: &[String],
+    toc: Option<TocMode>,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let documents = files.iter().enumerate().map(|(i, data)| {
+        load_document(data, password).map_err(|e| if e.is_password_error() { e } else { PdfError::InvalidFile(
+            format!("Fichier #{} invalide ou corrompu : {}", i + 1, e)) })
+    });
+    concatenate(documents, titles, toc, encryption)
+}
+
+/// Concatène des documents déjà chargés. Ils sont consommés un à un :
+/// un itérateur paresseux évite de les garder tous en mémoire.
+///
+/// Les champs de formulaire de chaque document sont réunis dans un seul
+/// `/AcroForm` ; deux champs de même nom complet seraient liés par les lecteurs.
+pub fn concatenate(
+    documents: impl IntoIterator<Item = Result<Document>>,
+    titles: &[String],
+    toc: Option<TocMode>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
     let mut merged = Document::with_version("1.5");
     let mut sub_roots: Vec<ObjectId> = Vec::new();
     let mut total_pages: i64 = 0;
+    let mut entries: Vec<TocEntry> = Vec::new();
+    let mut acroform: Option<Dictionary> = None;
 
-    for (i, data) in files.iter().enumerate() {
-        let mut src = load_document(data)
-            .map_err(|e| PdfError::InvalidFile(
-                format!("Fichier #{} invalide ou corrompu : {}", i + 1, e)))?;
+    for (i, src) in documents.into_iter().enumerate() {
+        let mut src = src?;
         src.decompress();
 
         // N'exclure que le Catalog — le nœud Pages racine doit être copié
         // pour devenir enfant du nouveau Pages racine fusionné.
-        let excluded: HashSet<ObjectId> = find_catalog(&src).into_iter().collect();
+        let catalog = find_catalog(&src);
+        let excluded: HashSet<ObjectId> = catalog.into_iter().collect();
 
         let id_map = copy_objects(&src, &mut merged, &excluded);
 
+        let form = catalog
+            .and_then(|id| src.get_dictionary(id).ok())
+            .and_then(|c| c.get(b"AcroForm").ok())
+            .map(|f| remap_object(f.clone(), &id_map));
+        if let Some(form) = form.as_ref().and_then(|f| resolve_dict(&merged, f)).cloned() {
+            merge_acroform(&merged, &mut acroform, form);
+        }
+
         if let Some(old_root) = find_pages_root(&src) {
             let new_root = id_map[&old_root];
 
+            if let Some(mode) = toc {
+                let src_pages: Vec<ObjectId> = src.get_pages().into_values().collect();
+                let offset = total_pages as u32;
+                if let Some(first) = src_pages.first() {
+                    let title = titles
+                        .get(i)
+                        .filter(|t| !t.is_empty())
+                        .cloned()
+                        .unwrap_or_else(|| format!("Document {}", i + 1));
+                    entries.push(TocEntry { title, level: 0, page_id: id_map[first], page: offset + 1 });
+                }
+                if mode == TocMode::Bookmarks {
+                    push_bookmarks(&mut entries, &outline(&src), 1, &|page| {
+                        let id = src_pages.get(page as usize - 1)?;
+                        Some((id_map[id], offset + page))
+                    });
+                }
+            }
+
             total_pages += merged
                 .objects
                 .get(&new_root)
@@ -43,9 +110,98 @@ pub fn merge_documents(files: Vec<Vec<u8>>) -> Result<Vec<u8>> {
         }
     }
 
-    let pages_id = insert_pages_node(&mut merged, &sub_roots, total_pages);
-    set_parent(&mut merged, &sub_roots, pages_id);
+    // La table des matières prend le format de la première page, tel
+    // qu'affiché : une page tournée d'un quart de tour est à l'italienne.
+    let toc_pages = match entries.first() {
+        Some(first) => {
+            let [left, bottom, right, top] = visible_box(&merged, first.page_id);
+            let media_box = match page_rotation(&merged, first.page_id) {
+                90 | 270 => [left, bottom, left + (top - bottom), bottom + (right - left)],
+                _ => [left, bottom, right, top],
+            };
+            build_toc_pages(&mut merged, &entries, media_box)?
+        }
+        None => Vec::new(),
+    };
+    let kids: Vec<ObjectId> = toc_pages.iter().chain(&sub_roots).copied().collect();
+    total_pages += toc_pages.len() as i64;
+
+    let pages_id = insert_pages_node(&mut merged, &kids, total_pages);
+    set_parent(&mut merged, &kids, pages_id);
 
     let catalog_id = insert_catalog(&mut merged, pages_id);
-    finalize(&mut merged, catalog_id)
+    if let Some(form) = acroform {
+        let form_id = merged.add_object(form);
+        merged.get_dictionary_mut(catalog_id)?.set("AcroForm", Object::Reference(form_id));
+    }
+    finalize(&mut merged, catalog_id, encryption)
+}
+
+/// Ajoute les champs de `form` au formulaire fusionné. Le premier document
+/// fournit `/DA`, `/Q` et `/NeedAppearances` ; les polices `/DR` des
+/// suivants complètent les siennes.
+fn merge_acroform(doc: &Document, merged: &mut Option<Dictionary>, form: Dictionary) {
+    let array = |dict: &Dictionary, key: &[u8]| -> Vec<Object> {
+        dict.get(key)
+            .ok()
+            .and_then(|a| doc.dereference(a).ok())
+            .and_then(|(_, a)| a.as_array().ok().cloned())
+            .unwrap_or_default()
+    };
+    let fonts = |dict: &Dictionary| -> Dictionary {
+        dict.get(b"DR")
+            .ok()
+            .and_then(|r| resolve_dict(doc, r))
+            .and_then(|r| r.get(b"Font").ok())
+            .and_then(|f| resolve_dict(doc, f))
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    let Some(target) = merged else {
+        let mut first = form.clone();
+        first.set("Fields", Object::Array(array(&form, b"Fields")));
+        let mut resources = first
+            .get(b"DR")
+            .ok()
+            .and_then(|r| resolve_dict(doc, r))
+            .cloned()
+            .unwrap_or_default();
+        resources.set("Font", Object::Dictionary(fonts(&form)));
+        first.set("DR", Object::Dictionary(resources));
+        // Le XFA ne décrit que le premier document.
+        first.remove(b"XFA");
+        *merged = Some(first);
+        return;
+    };
+
+    if let Ok(Object::Array(fields)) = target.get_mut(b"Fields") {
+        fields.extend(array(&form, b"Fields"));
+    }
+    if let Ok(Object::Dictionary(resources)) = target.get_mut(b"DR") {
+        if let Ok(Object::Dictionary(target_fonts)) = resources.get_mut(b"Font") {
+            for (name, font) in fonts(&form) {
+                if !target_fonts.has(&name) {
+                    target_fonts.set(name, font);
+                }
+            }
+        }
+    }
+}
+
+/// Ajoute les signets à la table des matières, en profondeur d'abord.
+/// `target` convertit une page du fichier source en page du document fusionné.
+fn push_bookmarks(
+    entries: &mut Vec<TocEntry>,
+    bookmarks: &[Bookmark],
+    level: usize,
+    target: &dyn Fn(u32) -> Option<(ObjectId, u32)>,
+) {
+    for bookmark in bookmarks {
+        // Un signet sans destination n'a pas de ligne, ses enfants si.
+        if let Some((page_id, page)) = bookmark.page.and_then(target) {
+            entries.push(TocEntry { title: bookmark.title.clone(), level, page_id, page });
+        }
+        push_bookmarks(entries, &bookmark.children, level + 1, target);
+    }
 }
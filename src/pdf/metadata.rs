@@ -0,0 +1,352 @@
+use lopdf::{decode_text_string, text_string, Dictionary, Document, Object, ObjectId, Stream};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::utils::{find_catalog, finalize, load_document, xml_escape};
+
+/// Clés standard du dictionnaire Info modifiables par `/metadata`.
+pub const STANDARD_KEYS: &[&str] = &["Title", "Author", "Subject", "Keywords", "Creator", "Producer"];
+
+/// Clés gérées par le service lui-même, jamais modifiables directement.
+const RESERVED_KEYS: &[&str] = &["CreationDate", "ModDate", "Trapped"];
+
+/// Correspondance clé Info → propriété XMP simple (hors tableaux `dc:`).
+const XMP_SIMPLE: &[(&str, &str)] = &[
+    ("Keywords", "pdf:Keywords"),
+    ("Producer", "pdf:Producer"),
+    ("Creator", "xmp:CreatorTool"),
+];
+
+/// Lit les métadonnées du document : toutes les entrées texte du
+/// dictionnaire Info, complétées par le paquet XMP pour les clés standard
+/// absentes de Info.
+pub fn read_metadata(data: &[u8], password: Option<&str>) -> Result<BTreeMap<String, String>> {
+    let doc = load_document(data, password)?;
+
+    let mut entries = BTreeMap::new();
+    if let Some(info) = info_dict(&doc) {
+        for (key, value) in info.iter() {
+            let value = match value {
+                Object::Name(name) => String::from_utf8_lossy(name).into_owned(),
+                other => match decode_text_string(other) {
+                    Ok(s) => s,
+                    Err(_) => continue,
+                },
+            };
+            entries.insert(String::from_utf8_lossy(key).into_owned(), value);
+        }
+    }
+
+    if let Some(xmp) = xmp_packet(&doc) {
+        for &key in STANDARD_KEYS {
+            if !entries.contains_key(key) {
+                if let Some(value) = xmp_property(&xmp, xmp_tag(key)) {
+                    entries.insert(key.to_string(), value);
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Met à jour les métadonnées : chaque entrée de `changes` remplace la
+/// valeur de la clé, une valeur vide la supprime.
+///
+/// Le dictionnaire Info et le paquet XMP sont écrits à partir des mêmes
+/// valeurs ; `ModDate` est mis à l'heure courante.
+pub fn write_metadata(
+    data: &[u8],
+    changes: &BTreeMap<String, String>,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    for key in changes.keys() {
+        validate_key(key)?;
+    }
+
+    let mut doc = load_document(data, password)?;
+    let catalog_id = find_catalog(&doc)
+        .ok_or_else(|| PdfError::InvalidFile("(catalogue introuvable)".to_string()))?;
+
+    let (pdf_now, xmp_now) = now_dates();
+    let old_xmp = xmp_packet(&doc);
+
+    let info_id = ensure_info(&mut doc);
+    let info = doc.get_object_mut(info_id)?.as_dict_mut()?;
+    for (key, value) in changes {
+        if value.is_empty() {
+            info.remove(key.as_bytes());
+        } else {
+            info.set(key.as_bytes().to_vec(), text_string(value));
+        }
+    }
+    info.set("ModDate", Object::string_literal(pdf_now));
+
+    let mut values = BTreeMap::new();
+    for (key, value) in info.iter() {
+        if let Ok(s) = decode_text_string(value) {
+            values.insert(String::from_utf8_lossy(key).into_owned(), s);
+        }
+    }
+
+    let packet = build_xmp(&values, &xmp_now, old_xmp.as_deref());
+    let metadata = Stream::new(
+        Dictionary::from_iter(vec![
+            ("Type", Object::Name(b"Metadata".to_vec())),
+            ("Subtype", Object::Name(b"XML".to_vec())),
+        ]),
+        packet.into_bytes(),
+    )
+    // Les paquets XMP restent lisibles en clair par les outils qui scannent le fichier.
+    .with_compression(false);
+
+    let existing = doc
+        .get_object(catalog_id)?
+        .as_dict()?
+        .get(b"Metadata")
+        .and_then(Object::as_reference)
+        .ok();
+    match existing {
+        Some(id) => {
+            doc.objects.insert(id, Object::Stream(metadata));
+        }
+        None => {
+            let id = doc.add_object(Object::Stream(metadata));
+            doc.get_object_mut(catalog_id)?
+                .as_dict_mut()?
+                .set("Metadata", Object::Reference(id));
+        }
+    }
+
+    finalize(&mut doc, catalog_id, encryption)
+}
+
+/// Une clé Info est un nom PDF : lettres, chiffres, `-` et `_` uniquement.
+fn validate_key(key: &str) -> Result<()> {
+    // La clé devient aussi un nom d'élément XMP (`pdfx:<clé>`) : elle ne
+    // peut commencer ni par un chiffre ni par un tiret.
+    let valid = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.len() <= 64
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && !RESERVED_KEYS.contains(&key);
+    if valid {
+        Ok(())
+    } else {
+        Err(PdfError::InvalidMetadataKey(key.to_string()))
+    }
+}
+
+fn info_dict(doc: &Document) -> Option<&Dictionary> {
+    match doc.trailer.get(b"Info").ok()? {
+        Object::Reference(id) => doc.get_object(*id).ok()?.as_dict().ok(),
+        Object::Dictionary(dict) => Some(dict),
+        _ => None,
+    }
+}
+
+/// Retourne l'objet Info du document, en le créant (ou en rendant indirect
+/// un Info direct) si nécessaire.
+fn ensure_info(doc: &mut Document) -> ObjectId {
+    if let Ok(Object::Reference(id)) = doc.trailer.get(b"Info") {
+        if doc.get_object(*id).and_then(Object::as_dict).is_ok() {
+            return *id;
+        }
+    }
+    let dict = match doc.trailer.get(b"Info") {
+        Ok(Object::Dictionary(dict)) => dict.clone(),
+        _ => Dictionary::new(),
+    };
+    let id = doc.add_object(Object::Dictionary(dict));
+    doc.trailer.set("Info", Object::Reference(id));
+    id
+}
+
+fn xmp_packet(doc: &Document) -> Option<String> {
+    let catalog = doc.get_object(find_catalog(doc)?).ok()?.as_dict().ok()?;
+    let id = catalog.get(b"Metadata").ok()?.as_reference().ok()?;
+    let stream = doc.get_object(id).ok()?.as_stream().ok()?;
+    let content = stream.decompressed_content().unwrap_or_else(|_| stream.content.clone());
+    Some(String::from_utf8_lossy(&content).into_owned())
+}
+
+fn xmp_tag(key: &str) -> &str {
+    match key {
+        "Title" => "dc:title",
+        "Author" => "dc:creator",
+        "Subject" => "dc:description",
+        _ => XMP_SIMPLE.iter().find(|(k, _)| *k == key).map_or("", |(_, t)| t),
+    }
+}
+
+/// Valeur d'une propriété XMP, sous forme d'attribut (`tag="…"`) ou
+/// d'élément ; pour un tableau `rdf:Alt`/`rdf:Seq`, le premier `rdf:li`.
+fn xmp_property(xmp: &str, tag: &str) -> Option<String> {
+    if tag.is_empty() {
+        return None;
+    }
+    let attr = format!("{}=\"", tag);
+    if let Some(start) = xmp.find(&attr).map(|i| i + attr.len()) {
+        let end = xmp[start..].find('"')? + start;
+        return Some(xml_unescape(&xmp[start..end]));
+    }
+
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut from = 0;
+    while let Some(pos) = xmp[from..].find(&open).map(|i| i + from) {
+        from = pos + open.len();
+        // `<dc:title>` mais pas `<dc:titleX>`
+        if !matches!(xmp[from..].chars().next(), Some('>' | ' ' | '\n' | '\r' | '\t')) {
+            continue;
+        }
+        let body_start = xmp[from..].find('>')? + from + 1;
+        let body_end = xmp[body_start..].find(&close)? + body_start;
+        let body = &xmp[body_start..body_end];
+        let text = match body.find("<rdf:li") {
+            Some(li) => {
+                let start = body[li..].find('>')? + li + 1;
+                let end = body[start..].find("</rdf:li>")? + start;
+                &body[start..end]
+            }
+            None => body,
+        };
+        return Some(xml_unescape(text.trim()));
+    }
+    None
+}
+
+/// Construit le paquet XMP correspondant à `values` (entrées de Info).
+///
+/// Les clés personnalisées vont dans l'espace `pdfx:` ; l'identification
+/// PDF/A (`pdfaid:`) de l'ancien paquet est conservée.
+fn build_xmp(values: &BTreeMap<String, String>, modify_date: &str, old: Option<&str>) -> String {
+    let mut props = String::new();
+    let mut push = |line: String| {
+        props.push_str("      ");
+        props.push_str(&line);
+        props.push('\n');
+    };
+
+    let lang_alt = |tag: &str, value: &str| {
+        format!(
+            "<{tag}><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></{tag}>",
+            xml_escape(value)
+        )
+    };
+    if let Some(title) = values.get("Title") {
+        push(lang_alt("dc:title", title));
+    }
+    if let Some(author) = values.get("Author") {
+        push(format!(
+            "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>",
+            xml_escape(author)
+        ));
+    }
+    if let Some(subject) = values.get("Subject") {
+        push(lang_alt("dc:description", subject));
+    }
+    for (key, tag) in XMP_SIMPLE {
+        if let Some(value) = values.get(*key) {
+            push(format!("<{tag}>{}</{tag}>", xml_escape(value)));
+        }
+    }
+    if let Some(created) = values.get("CreationDate").and_then(|d| pdf_date_to_iso(d)) {
+        push(format!("<xmp:CreateDate>{}</xmp:CreateDate>", created));
+    }
+    push(format!("<xmp:ModifyDate>{}</xmp:ModifyDate>", modify_date));
+    push(format!("<xmp:MetadataDate>{}</xmp:MetadataDate>", modify_date));
+
+    for (key, value) in values {
+        let standard = STANDARD_KEYS.contains(&key.as_str()) || RESERVED_KEYS.contains(&key.as_str());
+        if !standard {
+            push(format!("<pdfx:{key}>{}</pdfx:{key}>", xml_escape(value)));
+        }
+    }
+
+    for tag in ["pdfaid:part", "pdfaid:conformance"] {
+        if let Some(value) = old.and_then(|xmp| xmp_property(xmp, tag)) {
+            push(format!("<{tag}>{}</{tag}>", xml_escape(&value)));
+        }
+    }
+
+    format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n\
+  <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n\
+    <rdf:Description rdf:about=\"\"\n\
+        xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n\
+        xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\"\n\
+        xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"\n\
+        xmlns:pdfx=\"http://ns.adobe.com/pdfx/1.3/\"\n\
+        xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">\n\
+{props}    </rdf:Description>\n\
+  </rdf:RDF>\n\
+</x:xmpmeta>\n\
+<?xpacket end=\"w\"?>"
+    )
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Date courante au format PDF (`D:AAAAMMJJHHmmSSZ`) et ISO 8601 (XMP), en UTC.
+fn now_dates() -> (String, String) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (y, mo, d) = civil_from_days(secs.div_euclid(86_400));
+    let t = secs.rem_euclid(86_400);
+    let (h, mi, s) = (t / 3600, t % 3600 / 60, t % 60);
+    (
+        format!("D:{:04}{:02}{:02}{:02}{:02}{:02}Z", y, mo, d, h, mi, s),
+        format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", y, mo, d, h, mi, s),
+    )
+}
+
+/// Jours depuis le 1970-01-01 → (année, mois, jour), calendrier grégorien.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+
+/// `D:20240131120000+01'00'` → `2024-01-31T12:00:00+01:00`.
+fn pdf_date_to_iso(date: &str) -> Option<String> {
+    let digits = date.strip_prefix("D:").unwrap_or(date);
+    let field = |range: std::ops::Range<usize>, default: &'static str| -> Option<&str> {
+        match digits.get(range) {
+            Some(s) if s.bytes().all(|b| b.is_ascii_digit()) => Some(s),
+            Some(_) => None,
+            None => Some(default),
+        }
+    };
+    let year = digits.get(0..4).filter(|s| s.bytes().all(|b| b.is_ascii_digit()))?;
+    let (mo, d) = (field(4..6, "01")?, field(6..8, "01")?);
+    let (h, mi, s) = (field(8..10, "00")?, field(10..12, "00")?, field(12..14, "00")?);
+
+    let zone = match digits.get(14..) {
+        Some(z) if z.starts_with(['+', '-']) => {
+            let tz: String = z.chars().filter(|c| c.is_ascii_digit()).collect();
+            let (th, tm) = (tz.get(0..2).unwrap_or("00"), tz.get(2..4).unwrap_or("00"));
+            format!("{}{}:{}", &z[..1], th, tm)
+        }
+        Some(z) if z.starts_with('Z') => "Z".to_string(),
+        _ => String::new(),
+    };
+    Some(format!("{year}-{mo}-{d}T{h}:{mi}:{s}{zone}"))
+}
@@ -0,0 +1,55 @@
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::utils::{load_document, rebuild, transform_page, visible_box, Matrix};
+
+/// Axe de symétrie.
+#[derive(Debug, Clone, Copy)]
+pub enum MirrorAxis {
+    /// Miroir gauche ↔ droite.
+    Horizontal,
+    /// Miroir haut ↔ bas.
+    Vertical,
+    /// Les deux à la fois (équivaut à une rotation de 180° du contenu).
+    Both,
+}
+
+/// Applique un effet miroir au contenu des pages listées (toutes si `None`).
+///
+/// Le contenu est enveloppé dans une matrice de symétrie centrée sur la boîte
+/// visible de la page, qui reste donc en place ; les rectangles des
+/// annotations sont transformés de la même façon pour que les liens
+/// restent alignés sur leur cible.
+pub fn mirror_pages(
+    data: &[u8],
+    page_numbers: Option<&[u32]>,
+    axis: MirrorAxis,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut src = load_document(data, password)?;
+    src.decompress();
+
+    let all_pages = src.get_pages();
+    let total = all_pages.len() as u32;
+
+    let selected: Vec<u32> = match page_numbers {
+        Some(p) => p.to_vec(),
+        None => all_pages.keys().copied().collect(),
+    };
+
+    for n in selected {
+        let &page_id = all_pages
+            .get(&n)
+            .ok_or(PdfError::PageOutOfRange { page: n, total })?;
+
+        let [x0, y0, x1, y1] = visible_box(&src, page_id);
+        let m: Matrix = match axis {
+            MirrorAxis::Horizontal => [-1.0, 0.0, 0.0, 1.0, x0 + x1, 0.0],
+            MirrorAxis::Vertical   => [1.0, 0.0, 0.0, -1.0, 0.0, y0 + y1],
+            MirrorAxis::Both       => [-1.0, 0.0, 0.0, -1.0, x0 + x1, y0 + y1],
+        };
+        transform_page(&mut src, page_id, m)?;
+    }
+
+    rebuild(src, encryption)
+}
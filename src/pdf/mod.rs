@@ -5,3 +5,27 @@ pub mod extract;
 pub mod delete;
 pub mod rotate;
 pub mod reorder;
+pub mod overlay;
+pub mod insert_blank;
+pub mod insert;
+pub mod replace;
+pub mod mirror;
+pub mod encrypt;
+pub mod metadata;
+pub mod sanitize;
+pub mod active_content;
+pub mod bookmarks;
+pub mod font;
+pub mod toc;
+pub mod forms;
+pub mod flatten;
+pub mod form_data;
+pub mod archive;
+pub mod mail_merge;
+pub mod annotations;
+pub mod encoding;
+pub mod content;
+pub mod text;
+pub mod search;
+pub mod split;
+pub mod redact;
@@ -0,0 +1,111 @@
+use lopdf::ObjectId;
+use std::collections::HashSet;
+
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::utils::{
+    add_page_xobject, copy_objects, find_catalog, find_pages_root, fmt_num,
+    load_document, page_to_form_xobject, prune_unreachable, rebuild, visible_box, wrap_page_content,
+};
+
+/// Correspondance entre pages de base et pages de l'overlay.
+#[derive(Debug, Clone, Copy)]
+pub enum OverlayMode {
+    /// La même page de l'overlay (1-indexée) sur toutes les pages de base.
+    Single(u32),
+    /// Page N de l'overlay sur la page N de base ; les pages de base en
+    /// surnombre restent intactes.
+    PageForPage,
+    /// Les pages de l'overlay sont répétées en boucle.
+    Cycle,
+}
+
+/// Position de l'overlay par rapport au contenu existant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Over,
+    Under,
+}
+
+/// Superpose (ou place en dessous) les pages de `overlay` sur celles de `base`.
+///
+/// Chaque page de l'overlay est importée comme Form XObject — ressources
+/// comprises, via `copy_objects` — puis dessinée sur les pages de base,
+/// coin inférieur gauche aligné sur celui de la page de base.
+pub fn overlay_pages(
+    base: &[u8],
+    overlay: &[u8],
+    mode: OverlayMode,
+    layer: Layer,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut doc = load_document(base, password)?;
+    doc.decompress();
+
+    let mut ov = load_document(overlay, password).map_err(|e| {
+        if e.is_password_error() { e } else { PdfError::InvalidFile(format!("(overlay) : {}", e)) }
+    })?;
+    ov.decompress();
+
+    let ov_pages: Vec<ObjectId> = ov.get_pages().into_values().collect();
+    if ov_pages.is_empty() {
+        return Err(PdfError::InvalidFile("(overlay) : aucune page".to_owned()));
+    }
+    if let OverlayMode::Single(n) = mode {
+        if n < 1 || n as usize > ov_pages.len() {
+            return Err(PdfError::PageOutOfRange { page: n, total: ov_pages.len() as u32 });
+        }
+    }
+
+    // Conversion des pages de l'overlay en Form XObjects, avant la copie
+    // pour que leurs références soient remappées avec le reste.
+    let mut forms: Vec<(ObjectId, [f32; 4])> = Vec::with_capacity(ov_pages.len());
+    for &page_id in &ov_pages {
+        let form_id = page_to_form_xobject(&mut ov, page_id)?;
+        forms.push((form_id, visible_box(&ov, page_id)));
+    }
+
+    // Le Catalog et l'arbre de pages de l'overlay n'ont pas leur place
+    // dans le document de base.
+    let excluded: HashSet<ObjectId> = [find_catalog(&ov), find_pages_root(&ov)]
+        .into_iter()
+        .flatten()
+        .chain(ov_pages.iter().copied())
+        .collect();
+    let id_map = copy_objects(&ov, &mut doc, &excluded);
+
+    for (i, page_id) in doc.get_pages().into_values().enumerate() {
+        let index = match mode {
+            OverlayMode::Single(n) => n as usize - 1,
+            OverlayMode::PageForPage if i < forms.len() => i,
+            OverlayMode::PageForPage => continue,
+            OverlayMode::Cycle => i % forms.len(),
+        };
+        let (form_id, ov_box) = forms[index];
+        let name = add_page_xobject(&mut doc, page_id, id_map[&form_id], "Ov")?;
+
+        let base_box = visible_box(&doc, page_id);
+        let draw = format!(
+            "q 1 0 0 1 {} {} cm /{} Do Q\n",
+            fmt_num(base_box[0] - ov_box[0]),
+            fmt_num(base_box[1] - ov_box[1]),
+            String::from_utf8_lossy(&name),
+        )
+        .into_bytes();
+
+        match layer {
+            Layer::Over => {
+                let mut after = b"\nQ\n".to_vec();
+                after.extend(draw);
+                wrap_page_content(&mut doc, page_id, b"q\n".to_vec(), after)?;
+            }
+            Layer::Under => wrap_page_content(&mut doc, page_id, draw, Vec::new())?,
+        }
+    }
+
+    // Seuls les Form XObjects dessinés et leurs ressources restent de
+    // l'overlay : ses annotations, signets, formulaire… sont élagués.
+    prune_unreachable(&mut doc);
+    rebuild(doc, encryption)
+}
@@ -0,0 +1,724 @@
+use lopdf::content::Operation;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use regex::{Regex, RegexBuilder};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+
+use super::annotations::{add_replies, annotations};
+use super::content::{decode, encode, ContentState, FontCache, Glyph, IDENTITY, MAX_FORM_DEPTH};
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::flatten::{detach_field, remove_annots};
+use super::text::{page_glyphs, PageText};
+use super::utils::{
+    annotation_rect, fmt_num, inherited_attribute, intersects, invert, load_document, page_content,
+    prune_unreachable, rebuild, resolve_dict, transform_rect, Matrix,
+};
+
+/// Nombre maximum de zones par requête.
+pub const MAX_REGIONS: usize = 1000;
+
+/// Nombre maximum de motifs par requête.
+pub const MAX_PATTERNS: usize = 20;
+
+/// Motifs prédéfinis : nom → expression régulière.
+const PRESETS: &[(&str, &str)] = &[
+    ("email", r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}"),
+    // Groupes de quatre caractères, espacés ou non
+    ("iban", r"\b[A-Z]{2}[0-9]{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b"),
+    // Numéro de sécurité sociale français, clé comprise
+    ("nir", r"\b[12] ?[0-9]{2} ?(?:0[1-9]|1[0-2]|[2-9][0-9]) ?(?:[0-9]{2}|2[AB]) ?[0-9]{3} ?[0-9]{3}(?: ?[0-9]{2})?\b"),
+];
+
+/// Zone à caviarder : rectangle `[x0, y0, x1, y1]` normalisé, en points,
+/// dans l'espace utilisateur par défaut de la page (origine en bas à
+/// gauche de la MediaBox, sans tenir compte de `/Rotate`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    /// Page, 1-indexée.
+    pub page: u32,
+    pub rect: [f32; 4],
+}
+
+/// Éléments retirés d'une page.
+#[derive(Debug, Clone, Copy, Default)]
+struct Removed {
+    glyphs: usize,
+    images_masked: usize,
+    images_removed: usize,
+    annotations: usize,
+}
+
+impl Removed {
+    fn add(&mut self, other: &Removed) {
+        self.glyphs += other.glyphs;
+        self.images_masked += other.images_masked;
+        self.images_removed += other.images_removed;
+        self.annotations += other.annotations;
+    }
+
+    fn to_json(self) -> Value {
+        json!({
+            "glyphs": self.glyphs,
+            "images_masked": self.images_masked,
+            "images_removed": self.images_removed,
+            "annotations": self.annotations,
+        })
+    }
+}
+
+/// Compile les motifs à caviarder : expressions régulières et motifs
+/// prédéfinis (`email`, `iban`, `nir`).
+pub fn compile_patterns(patterns: &[String], presets: &[String]) -> Result<Vec<Regex>> {
+    if patterns.len() + presets.len() > MAX_PATTERNS {
+        return Err(PdfError::InvalidPattern(format!("{} motifs au plus", MAX_PATTERNS)));
+    }
+    let mut sources: Vec<&str> = patterns.iter().map(String::as_str).collect();
+    for name in presets {
+        let name = name.trim();
+        match PRESETS.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+            Some((_, pattern)) => sources.push(pattern),
+            None => {
+                let known: Vec<&str> = PRESETS.iter().map(|(n, _)| *n).collect();
+                return Err(PdfError::InvalidPattern(format!("motif prédéfini inconnu '{}' ({})", name, known.join(", "))));
+            }
+        }
+    }
+    sources
+        .into_iter()
+        .map(|source| {
+            // Taille bornée : le motif vient du client.
+            RegexBuilder::new(source)
+                .size_limit(1 << 20)
+                .build()
+                .map_err(|e| PdfError::InvalidPattern(e.to_string()))
+        })
+        .collect()
+}
+
+/// Lit les zones au format `[{ "page": 1, "rect": [x0, y0, x1, y1] }]`.
+pub fn regions_from_json(input: &str) -> Result<Vec<Region>> {
+    let invalid = |msg: String| PdfError::InvalidRegions(msg);
+    let value: Value = serde_json::from_str(input).map_err(|e| invalid(e.to_string()))?;
+    let Value::Array(items) = value else {
+        return Err(invalid("tableau attendu".to_string()));
+    };
+    if items.is_empty() {
+        return Err(invalid("aucune zone".to_string()));
+    }
+    if items.len() > MAX_REGIONS {
+        return Err(invalid(format!("{} zones au plus", MAX_REGIONS)));
+    }
+
+    let mut regions = Vec::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        let page = item
+            .get("page")
+            .and_then(Value::as_u64)
+            .and_then(|n| u32::try_from(n).ok())
+            .filter(|&n| n > 0)
+            .ok_or_else(|| invalid(format!("[{}].page : numéro de page attendu", i)))?;
+        let values: Vec<f64> = item
+            .get("rect")
+            .and_then(Value::as_array)
+            .map(|a| a.iter().filter_map(Value::as_f64).collect())
+            .unwrap_or_default();
+        let [x0, y0, x1, y1] = values[..] else {
+            return Err(invalid(format!("[{}].rect : [x0, y0, x1, y1] attendu", i)));
+        };
+        let rect = [x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)].map(|v| v as f32);
+        if rect[2] - rect[0] <= 0.0 || rect[3] - rect[1] <= 0.0 {
+            return Err(invalid(format!("[{}].rect : surface nulle", i)));
+        }
+        regions.push(Region { page, rect });
+    }
+    Ok(regions)
+}
+
+/// Occurrence d'un motif dans le texte d'une page.
+#[derive(Debug, Clone)]
+pub struct TextMatch {
+    pub page: u32,
+    pub text: String,
+    /// Boîte des glyphes de l'occurrence, une par ligne.
+    pub rects: Vec<[f32; 4]>,
+    /// Boîtes de chacun de ses glyphes.
+    glyphs: Vec<[f32; 4]>,
+}
+
+/// Occurrences des motifs, page par page.
+fn find_matches(doc: &Document, patterns: &[Regex]) -> Result<Vec<TextMatch>> {
+    let mut matches = Vec::new();
+    if patterns.is_empty() {
+        return Ok(matches);
+    }
+    for (page, page_id) in doc.get_pages() {
+        let glyphs = page_glyphs(doc, page_id)?;
+        let text = PageText::new(&glyphs);
+        let mut found: Vec<regex::Match> = patterns
+            .iter()
+            .flat_map(|p| p.find_iter(&text.text))
+            .filter(|m| !m.is_empty())
+            .collect();
+        // Dans l'ordre de lecture, quel que soit le motif.
+        found.sort_by_key(|m| (m.start(), m.end()));
+        for m in found {
+            let lines = text.glyph_lines(m.range());
+            let rects = lines
+                .iter()
+                .map(|line| line.iter().map(|&i| glyphs[i].bbox).reduce(union).unwrap_or_default())
+                .collect();
+            let boxes = lines.iter().flatten().map(|&i| glyphs[i].bbox).collect();
+            matches.push(TextMatch { page, text: m.as_str().to_string(), rects, glyphs: boxes });
+        }
+    }
+    Ok(matches)
+}
+
+fn union(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    [a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])]
+}
+
+/// Mode simulation : occurrences des motifs sans modifier le document,
+/// `{ "count", "matches": [{ "page", "text", "rects" }] }`.
+pub fn preview_matches(data: &[u8], patterns: &[Regex], password: Option<&str>) -> Result<Value> {
+    let doc = load_document(data, password)?;
+    let matches = find_matches(&doc, patterns)?;
+    let round = |r: &[f32; 4]| r.map(|v| (f64::from(v) * 100.0).round() / 100.0);
+    Ok(json!({
+        "count": matches.len(),
+        "matches": matches
+            .iter()
+            .map(|m| json!({ "page": m.page, "text": m.text, "rects": m.rects.iter().map(round).collect::<Vec<_>>() }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+/// Ce qui est à caviarder sur une page.
+#[derive(Debug, Default)]
+struct PageTargets {
+    /// Zones demandées : tout ce qui les touche est retiré.
+    regions: Vec<[f32; 4]>,
+    /// Zones des occurrences : images et annotations qui les touchent sont
+    /// retirées, mais seuls les glyphes de l'occurrence pour le texte.
+    matches: Vec<[f32; 4]>,
+    /// Boîtes des glyphes des occurrences, comparées bit à bit : le même
+    /// calcul sur le même flux donne les mêmes valeurs.
+    glyphs: HashSet<[u32; 4]>,
+}
+
+impl PageTargets {
+    fn zones(&self) -> impl Iterator<Item = &[f32; 4]> {
+        self.regions.iter().chain(&self.matches)
+    }
+}
+
+/// Caviarde les zones et les occurrences des motifs : le texte et les
+/// images qu'elles recouvrent sont retirés du contenu (pas seulement
+/// masqués), les annotations qui les chevauchent supprimées, puis chaque
+/// zone est peinte en noir. Pour une occurrence, seuls ses glyphes sont
+/// retirés du texte, pas ceux qui la touchent.
+///
+/// Retourne le PDF et le rapport JSON des éléments retirés :
+/// `{ "glyphs", "images_masked", "images_removed", "annotations" }`, plus
+/// `matches` (nombre d'occurrences) si des motifs sont fournis. Seuls les
+/// totaux y figurent : le rapport part dans un en-tête HTTP, dont la taille
+/// ne doit pas croître avec le nombre de pages.
+pub fn redact(
+    data: &[u8],
+    regions: &[Region],
+    patterns: &[Regex],
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<(Vec<u8>, Value)> {
+    let mut doc = load_document(data, password)?;
+    doc.decompress();
+    let pages = doc.get_pages();
+    let total = pages.len() as u32;
+
+    let mut by_page: BTreeMap<u32, PageTargets> = BTreeMap::new();
+    for region in regions {
+        if region.page > total {
+            return Err(PdfError::PageOutOfRange { page: region.page, total });
+        }
+        by_page.entry(region.page).or_default().regions.push(region.rect);
+    }
+    let matches = find_matches(&doc, patterns)?;
+    for m in &matches {
+        let targets = by_page.entry(m.page).or_default();
+        targets.matches.extend(&m.rects);
+        targets.glyphs.extend(m.glyphs.iter().map(|b| b.map(f32::to_bits)));
+    }
+
+    let mut totals = Removed::default();
+    for (&number, targets) in &by_page {
+        totals.add(&redact_page(&mut doc, pages[&number], targets)?);
+    }
+
+    // Annotations chevauchant une zone, avec leurs réponses et fenêtres Popup.
+    let all = annotations(&doc);
+    let mut targets: HashSet<ObjectId> = all
+        .iter()
+        .filter(|a| {
+            let rect = annotation_rect(&doc, a.id).map(|r| r.map(|v| v as f32));
+            by_page
+                .get(&a.page)
+                .is_some_and(|t| rect.is_some_and(|r| t.zones().any(|&z| intersects(r, z))))
+        })
+        .map(|a| a.id)
+        .collect();
+    add_replies(&all, &mut targets);
+    let mut dropped = HashSet::new();
+    for annot in all.iter().filter(|a| targets.contains(&a.id)) {
+        totals.annotations += 1;
+        // Un widget retiré de la page emporte son champ : sa valeur resterait
+        // sinon lisible dans le formulaire.
+        if annot.subtype == "Widget" {
+            detach_field(&mut doc, annot.id)?;
+        }
+        dropped.insert(annot.id);
+        dropped.extend(annot.popup);
+    }
+    for &page_id in pages.values() {
+        remove_annots(&mut doc, page_id, &dropped)?;
+    }
+
+    prune_unreachable(&mut doc);
+    let bytes = rebuild(doc, encryption)?;
+
+    let mut report = totals.to_json();
+    if !patterns.is_empty() {
+        report["matches"] = json!(matches.len());
+    }
+    Ok((bytes, report))
+}
+
+/// Réécrit le contenu de la page sans ce que couvrent les cibles, puis
+/// peint leurs zones en noir par-dessus.
+fn redact_page(doc: &mut Document, page_id: ObjectId, targets: &PageTargets) -> Result<Removed> {
+    let resources = inherited_attribute(doc, page_id, b"Resources")
+        .and_then(|r| resolve_dict(doc, &r).cloned())
+        .unwrap_or_default();
+    let operations = decode(&page_content(doc, page_id))?;
+
+    let mut redactor = Redactor { targets, fonts: FontCache::default(), removed: Removed::default() };
+    let state = ContentState::new(IDENTITY, resources);
+    let (operations, resources, _) = redactor.rewrite(doc, operations, state, 0)?;
+
+    // Le contenu d'origine est isolé dans q … Q : les zones sont peintes
+    // dans l'espace par défaut de la page, quel que soit son état final.
+    let mut content = b"q\n".to_vec();
+    content.extend(encode(&operations)?);
+    content.extend_from_slice(b"Q\nq 0 g\n");
+    for r in targets.zones() {
+        let values = [r[0], r[1], r[2] - r[0], r[3] - r[1]].map(fmt_num).join(" ");
+        content.extend(format!("{} re f\n", values).into_bytes());
+    }
+    content.extend_from_slice(b"Q\n");
+
+    // Un nouveau flux plutôt qu'une modification : l'ancien peut être
+    // partagé avec d'autres pages.
+    let stream_id = doc.add_object(Stream::new(Dictionary::new(), content));
+    let page = doc.get_dictionary_mut(page_id)?;
+    page.set("Contents", Object::Reference(stream_id));
+    page.set("Resources", Object::Dictionary(resources));
+    Ok(redactor.removed)
+}
+
+/// Sort d'un XObject dessiné par `Do`.
+enum XObjectAction {
+    Keep,
+    Remove,
+    /// Dessiner à la place la copie caviardée enregistrée sous ce nom.
+    Replace(Vec<u8>),
+}
+
+struct Redactor<'a> {
+    targets: &'a PageTargets,
+    fonts: FontCache,
+    removed: Removed,
+}
+
+impl Redactor<'_> {
+    fn hits(&self, bbox: [f32; 4]) -> bool {
+        self.targets.zones().any(|&r| intersects(bbox, r))
+    }
+
+    fn hits_glyph(&self, glyph: &Glyph) -> bool {
+        self.targets.regions.iter().any(|&r| intersects(glyph.bbox, r))
+            || self.targets.glyphs.contains(&glyph.bbox.map(f32::to_bits))
+    }
+
+    /// Réécrit les opérations d'un flux et retourne les nouvelles
+    /// opérations, ses ressources mises à jour et s'il a changé.
+    fn rewrite(
+        &mut self,
+        doc: &mut Document,
+        operations: Vec<Operation>,
+        mut state: ContentState,
+        depth: usize,
+    ) -> Result<(Vec<Operation>, Dictionary, bool)> {
+        let mut out = Vec::with_capacity(operations.len());
+        let mut changed = false;
+        let mut replaced: HashSet<Vec<u8>> = HashSet::new();
+
+        for op in operations {
+            state.apply(doc, &mut self.fonts, &op);
+            match op.operator.as_str() {
+                "Tj" | "TJ" | "'" | "\"" => match self.redact_text(&mut state, &op) {
+                    Some(ops) => {
+                        changed = true;
+                        out.extend(ops);
+                    }
+                    None => out.push(op),
+                },
+                "Do" => {
+                    let name = op.operands.first().and_then(|o| o.as_name().ok()).unwrap_or_default().to_vec();
+                    match self.redact_xobject(doc, &mut state, &name, depth)? {
+                        XObjectAction::Keep => out.push(op),
+                        XObjectAction::Remove => {
+                            changed = true;
+                            replaced.insert(name);
+                        }
+                        XObjectAction::Replace(new_name) => {
+                            changed = true;
+                            replaced.insert(name);
+                            out.push(Operation::new("Do", vec![Object::Name(new_name)]));
+                        }
+                    }
+                }
+                // Images en ligne : petites, retirées dès qu'elles touchent une zone.
+                "BI" if self.hits(state.image_bbox()) => {
+                    changed = true;
+                    self.removed.images_removed += 1;
+                }
+                _ => out.push(op),
+            }
+        }
+
+        // Les XObjects remplacés ne doivent plus être accessibles depuis ces
+        // ressources, sans quoi l'original resterait dans le fichier.
+        let used: HashSet<&[u8]> = out
+            .iter()
+            .filter(|op| op.operator == "Do")
+            .filter_map(|op| op.operands.first()?.as_name().ok())
+            .collect();
+        let stale: Vec<Vec<u8>> = replaced.into_iter().filter(|n| !used.contains(n.as_slice())).collect();
+        if !stale.is_empty() {
+            let xobjects = xobjects_mut(doc, &mut state.resources)?;
+            for name in stale {
+                xobjects.remove(&name);
+            }
+        }
+        Ok((out, state.resources, changed))
+    }
+
+    /// Retire d'un opérateur de texte les glyphes situés dans une zone,
+    /// remplacés par un décalage de même largeur pour ne pas déplacer les
+    /// suivants. `None` si aucun glyphe n'est retiré.
+    fn redact_text(&mut self, state: &mut ContentState, op: &Operation) -> Option<Vec<Operation>> {
+        let elements = match op.operator.as_str() {
+            "TJ" => op.operands.first()?.as_array().ok()?.clone(),
+            _ => vec![op.operands.last()?.clone()],
+        };
+
+        let mut rebuilt: Vec<Object> = Vec::new();
+        let mut count = 0;
+        for element in elements {
+            match element {
+                Object::String(bytes, format) => {
+                    let glyphs = state.show(&bytes);
+                    // Sans police, les glyphes ne peuvent être placés : rien
+                    // ne garantit qu'ils sont hors des zones, la chaîne est
+                    // retirée entière.
+                    if glyphs.is_empty() {
+                        count += bytes.len();
+                        continue;
+                    }
+                    let mut kept = Vec::new();
+                    for glyph in glyphs {
+                        if self.hits_glyph(&glyph) {
+                            if !kept.is_empty() {
+                                rebuilt.push(Object::String(std::mem::take(&mut kept), format));
+                            }
+                            push_offset(&mut rebuilt, glyph.advance);
+                            count += 1;
+                        } else {
+                            kept.extend(glyph.bytes);
+                        }
+                    }
+                    if !kept.is_empty() {
+                        rebuilt.push(Object::String(kept, format));
+                    }
+                }
+                other => {
+                    if let Ok(amount) = other.as_float() {
+                        state.adjust(amount);
+                        push_offset(&mut rebuilt, amount);
+                    }
+                }
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        self.removed.glyphs += count;
+
+        let mut ops = Vec::new();
+        match op.operator.as_str() {
+            "'" => ops.push(Operation::new("T*", vec![])),
+            "\"" => {
+                ops.push(Operation::new("Tw", op.operands.first().cloned().into_iter().collect()));
+                ops.push(Operation::new("Tc", op.operands.get(1).cloned().into_iter().collect()));
+                ops.push(Operation::new("T*", vec![]));
+            }
+            _ => {}
+        }
+        ops.push(Operation::new("TJ", vec![Object::Array(rebuilt)]));
+        Some(ops)
+    }
+
+    fn redact_xobject(
+        &mut self,
+        doc: &mut Document,
+        state: &mut ContentState,
+        name: &[u8],
+        depth: usize,
+    ) -> Result<XObjectAction> {
+        let Some((_, stream)) = state.xobject(doc, name) else {
+            return Ok(XObjectAction::Keep);
+        };
+        match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+            Ok(b"Image") => {
+                let bbox = state.image_bbox();
+                let hits: Vec<[f32; 4]> = self.targets.zones().copied().filter(|&r| intersects(bbox, r)).collect();
+                if hits.is_empty() {
+                    return Ok(XObjectAction::Keep);
+                }
+                // Une image entièrement couverte disparaît ; sinon seuls
+                // ses pixels couverts sont effacés, quand son format le permet.
+                let covered = hits.iter().any(|r| r[0] <= bbox[0] && r[1] <= bbox[1] && r[2] >= bbox[2] && r[3] >= bbox[3]);
+                let masked = if covered { None } else { mask_image(doc, stream, state.gs.ctm, &hits) };
+                let Some(masked) = masked else {
+                    self.removed.images_removed += 1;
+                    return Ok(XObjectAction::Remove);
+                };
+                let image_id = masked.add_to(doc);
+                self.removed.images_masked += 1;
+                Ok(XObjectAction::Replace(add_xobject(doc, &mut state.resources, image_id)?))
+            }
+            Ok(b"Form") if depth < MAX_FORM_DEPTH => {
+                let inner = state.form(doc, stream);
+                if inner.form_bbox(stream).is_some_and(|b| !self.hits(b)) {
+                    return Ok(XObjectAction::Keep);
+                }
+                let mut dict = stream.dict.clone();
+                let operations = decode(&stream.decompressed_content().unwrap_or_else(|_| stream.content.clone()))?;
+                let (operations, resources, changed) = self.rewrite(doc, operations, inner, depth + 1)?;
+                if !changed {
+                    return Ok(XObjectAction::Keep);
+                }
+                dict.remove(b"Filter");
+                dict.remove(b"DecodeParms");
+                dict.set("Resources", Object::Dictionary(resources));
+                let form_id = doc.add_object(Stream::new(dict, encode(&operations)?));
+                Ok(XObjectAction::Replace(add_xobject(doc, &mut state.resources, form_id)?))
+            }
+            _ => Ok(XObjectAction::Keep),
+        }
+    }
+}
+
+/// Ajoute un décalage à un tableau de `TJ`, fusionné avec le précédent.
+fn push_offset(elements: &mut Vec<Object>, amount: f32) {
+    match elements.last_mut() {
+        Some(Object::Real(last)) => *last += amount,
+        Some(Object::Integer(last)) => {
+            let sum = *last as f32 + amount;
+            elements.pop();
+            elements.push(Object::Real(sum));
+        }
+        _ => elements.push(Object::Real(amount)),
+    }
+}
+
+/// Sous-dictionnaire `/XObject` des ressources, rendu inline pour ne pas
+/// modifier un dictionnaire partagé.
+fn xobjects_mut<'a>(doc: &Document, resources: &'a mut Dictionary) -> Result<&'a mut Dictionary> {
+    if !matches!(resources.get(b"XObject"), Ok(Object::Dictionary(_))) {
+        let inline = resources
+            .get(b"XObject")
+            .ok()
+            .and_then(|x| resolve_dict(doc, x))
+            .cloned()
+            .unwrap_or_default();
+        resources.set("XObject", Object::Dictionary(inline));
+    }
+    Ok(resources.get_mut(b"XObject")?.as_dict_mut()?)
+}
+
+/// Enregistre `id` dans les ressources sous un nom libre `Redacted{n}`.
+fn add_xobject(doc: &Document, resources: &mut Dictionary, id: ObjectId) -> Result<Vec<u8>> {
+    let xobjects = xobjects_mut(doc, resources)?;
+    let name = (0..)
+        .map(|i| format!("Redacted{}", i).into_bytes())
+        .find(|n| !xobjects.has(n))
+        .unwrap_or_default();
+    xobjects.set(name.clone(), Object::Reference(id));
+    Ok(name)
+}
+
+// ── Images ────────────────────────────────────────────────────────────────────
+
+/// Copie d'une image dont les pixels couverts ont été effacés, avec ses
+/// masques (`/SMask`, `/Mask`) effacés de même.
+struct MaskedImage {
+    image: Stream,
+    smask: Option<Stream>,
+    mask: Option<Stream>,
+}
+
+impl MaskedImage {
+    fn add_to(self, doc: &mut Document) -> ObjectId {
+        let mut image = self.image;
+        if let Some(smask) = self.smask {
+            image.dict.set("SMask", Object::Reference(doc.add_object(smask)));
+        }
+        if let Some(mask) = self.mask {
+            image.dict.set("Mask", Object::Reference(doc.add_object(mask)));
+        }
+        doc.add_object(image)
+    }
+}
+
+/// `None` si l'image ou l'un de ses masques ne peut être décodé (JPEG,
+/// JPEG 2000, CCITT, JBIG2, espace de couleurs inconnu) : l'appelant la
+/// retire alors entièrement.
+fn mask_image(doc: &Document, image: &Stream, ctm: Matrix, hits: &[[f32; 4]]) -> Option<MaskedImage> {
+    let stream = |key: &[u8]| -> Option<Option<&Stream>> {
+        match image.dict.get(key) {
+            Ok(Object::Reference(id)) => Some(Some(doc.get_object(*id).ok()?.as_stream().ok()?)),
+            _ => Some(None),
+        }
+    };
+    let smask = match stream(b"SMask")? {
+        Some(s) => Some(erase_pixels(doc, s, ctm, hits)?),
+        None => None,
+    };
+    let mask = match stream(b"Mask")? {
+        Some(s) => Some(erase_pixels(doc, s, ctm, hits)?),
+        None => None,
+    };
+    Some(MaskedImage { image: erase_pixels(doc, image, ctm, hits)?, smask, mask })
+}
+
+/// Copie de l'image dont tout pixel touchant une zone est mis au noir
+/// (non peint pour un masque de détourage).
+fn erase_pixels(doc: &Document, image: &Stream, ctm: Matrix, hits: &[[f32; 4]]) -> Option<Stream> {
+    let dict = &image.dict;
+    // Après `decompress`, seuls les filtres non pris en charge subsistent.
+    if dict.has(b"Filter") {
+        return None;
+    }
+    let int = |key: &[u8]| dict.get(key).and_then(Object::as_i64).ok();
+    let width = usize::try_from(int(b"Width")?).ok().filter(|&w| w > 0)?;
+    let height = usize::try_from(int(b"Height")?).ok().filter(|&h| h > 0)?;
+    let stencil = dict.get(b"ImageMask").and_then(Object::as_bool).unwrap_or(false);
+    let (bpc, components) = if stencil {
+        (1, 1)
+    } else {
+        let bpc = usize::try_from(int(b"BitsPerComponent")?).ok()?;
+        (bpc, color_components(doc, dict.get(b"ColorSpace").ok()?)?)
+    };
+    if ![1, 2, 4, 8, 16].contains(&bpc) {
+        return None;
+    }
+
+    let max = (1u32 << bpc) - 1;
+    let decode: Vec<f32> = dict
+        .get(b"Decode")
+        .and_then(Object::as_array)
+        .map(|a| a.iter().filter_map(|v| v.as_float().ok()).collect())
+        .unwrap_or_default();
+    let fill: Vec<u32> = (0..components)
+        .map(|c| {
+            // Noir : composantes à 0, sauf le noir (K) en CMJN ; un masque
+            // de détourage à 1 ne peint rien.
+            let value = if stencil || (components == 4 && c == 3) { max } else { 0 };
+            let inverted = matches!((decode.get(2 * c), decode.get(2 * c + 1)), (Some(a), Some(b)) if a > b);
+            if inverted { max - value } else { value }
+        })
+        .collect();
+
+    let stride = (width * components * bpc).div_ceil(8);
+    let mut data = image.content.clone();
+    if data.len() < stride * height {
+        return None;
+    }
+    let inverse = invert(ctm)?;
+    let (w, h) = (width as f32, height as f32);
+    for &r in hits {
+        // Pixels candidats d'après la zone ramenée dans l'espace de l'image,
+        // puis test de chacun : la matrice peut tourner ou incliner l'image.
+        let unit = transform_rect(inverse, r);
+        let cols = (unit[0].max(0.0) * w).floor() as usize..((unit[2].min(1.0) * w).ceil() as usize).min(width);
+        let rows = ((1.0 - unit[3].min(1.0)) * h).floor() as usize..(((1.0 - unit[1].max(0.0)) * h).ceil() as usize).min(height);
+        for y in rows {
+            let row = &mut data[y * stride..(y + 1) * stride];
+            for x in cols.clone() {
+                let (x, yf) = (x as f32, y as f32);
+                let pixel = transform_rect(ctm, [x / w, 1.0 - (yf + 1.0) / h, (x + 1.0) / w, 1.0 - yf / h]);
+                if intersects(pixel, r) {
+                    for (c, &value) in fill.iter().enumerate() {
+                        set_sample(row, x as usize * components + c, bpc, value);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut dict = dict.clone();
+    dict.remove(b"DecodeParms");
+    Some(Stream::new(dict, data))
+}
+
+/// Nombre de composantes par pixel d'un espace de couleurs d'image.
+fn color_components(doc: &Document, color_space: &Object) -> Option<usize> {
+    let name = |o: &Object| o.as_name().ok().map(<[u8]>::to_vec);
+    match doc.dereference(color_space).ok()?.1 {
+        Object::Name(n) => match n.as_slice() {
+            b"DeviceGray" | b"CalGray" | b"G" | b"Indexed" | b"I" => Some(1),
+            b"DeviceRGB" | b"RGB" => Some(3),
+            b"DeviceCMYK" | b"CMYK" => Some(4),
+            _ => None,
+        },
+        Object::Array(parts) => match name(parts.first()?)?.as_slice() {
+            b"ICCBased" => {
+                let profile = doc.get_object(parts.get(1)?.as_reference().ok()?).ok()?.as_stream().ok()?;
+                usize::try_from(profile.dict.get(b"N").and_then(Object::as_i64).ok()?).ok()
+            }
+            b"CalGray" | b"Indexed" | b"I" | b"Separation" => Some(1),
+            b"CalRGB" | b"Lab" => Some(3),
+            b"DeviceN" => Some(doc.dereference(parts.get(1)?).ok()?.1.as_array().ok()?.len()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Écrit l'échantillon `index` d'une ligne de `bpc` bits par composante.
+fn set_sample(row: &mut [u8], index: usize, bpc: usize, value: u32) {
+    match bpc {
+        8 => row[index] = value as u8,
+        16 => {
+            row[2 * index] = (value >> 8) as u8;
+            row[2 * index + 1] = value as u8;
+        }
+        _ => {
+            let bit = index * bpc;
+            let shift = 8 - bpc - bit % 8;
+            let mask = (((1u32 << bpc) - 1) << shift) as u8;
+            row[bit / 8] = (row[bit / 8] & !mask) | ((value << shift) as u8 & mask);
+        }
+    }
+}
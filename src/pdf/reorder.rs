@@ -1,6 +1,7 @@
 use lopdf::{Document, ObjectId};
 use std::collections::HashSet;
 
+use super::encrypt::Encryption;
 use super::error::{PdfError, Result};
 use super::utils::{load_document, 
     copy_objects, find_catalog, find_pages_root, finalize,
@@ -9,8 +10,13 @@ use super::utils::{load_document,
 
 /// Réorganise les pages selon `order` (numéros 1-indexés dans le nouvel ordre).
 /// Toutes les pages du document doivent figurer exactement une fois dans `order`.
-pub fn reorder_pages(data: &[u8], order: &[u32]) -> Result<Vec<u8>> {
-    let mut src = load_document(data)?;
+pub fn reorder_pages(
+    data: &[u8],
+    order: &[u32],
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut src = load_document(data, password)?;
     src.decompress();
 
     let all_pages = src.get_pages();
@@ -47,5 +53,5 @@ pub fn reorder_pages(data: &[u8], order: &[u32]) -> Result<Vec<u8>> {
     set_parent(&mut out, &new_page_ids, pages_id);
 
     let catalog_id = insert_catalog(&mut out, pages_id);
-    finalize(&mut out, catalog_id)
+    finalize(&mut out, catalog_id, encryption)
 }
@@ -0,0 +1,189 @@
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::{HashMap, HashSet};
+
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::utils::{
+    copy_objects, find_catalog, find_pages_root, inline_inherited_attributes,
+    load_document, prune_unreachable, rebuild, remap_object,
+};
+
+/// Clés décrivant l'apparence d'une page, reprises de la page source.
+/// Tout le reste (`Parent`, `StructParents`…) appartient à la position.
+const PAGE_CONTENT_KEYS: &[&[u8]] = &[
+    b"Contents", b"Resources", b"MediaBox", b"CropBox", b"BleedBox", b"TrimBox",
+    b"ArtBox", b"Rotate", b"UserUnit", b"Group", b"Annots",
+];
+
+/// Remplace des pages de `target` par des pages de `source`.
+/// Chaque entrée de `mapping` est `(page_cible, page_source)`, 1-indexées.
+///
+/// Les pages cibles gardent leur identifiant d'objet : seul leur contenu
+/// change. Signets, liens internes, destinations nommées et libellés de
+/// pages qui pointaient vers une position remplacée restent donc valides.
+pub fn replace_pages(
+    target: &[u8],
+    source: &[u8],
+    mapping: &[(u32, u32)],
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    if mapping.is_empty() {
+        return Err(PdfError::NoPages);
+    }
+
+    let mut dst = load_document(target, password)?;
+    dst.decompress();
+    let mut src = load_document(source, password).map_err(|e| {
+        if e.is_password_error() { e } else { PdfError::InvalidFile(format!("(document source) : {}", e)) }
+    })?;
+    src.decompress();
+
+    let dst_pages = dst.get_pages();
+    let src_pages = src.get_pages();
+
+    let mut seen = HashSet::new();
+    let mut resolved: Vec<(ObjectId, ObjectId)> = Vec::with_capacity(mapping.len());
+    for &(t, s) in mapping {
+        let &t_id = dst_pages
+            .get(&t)
+            .ok_or(PdfError::PageOutOfRange { page: t, total: dst_pages.len() as u32 })?;
+        let &s_id = src_pages
+            .get(&s)
+            .ok_or(PdfError::PageOutOfRange { page: s, total: src_pages.len() as u32 })?;
+        if !seen.insert(t) {
+            return Err(PdfError::DuplicatePage(t));
+        }
+        resolved.push((t_id, s_id));
+    }
+
+    let used: Vec<ObjectId> = resolved.iter().map(|&(_, s)| s).collect();
+    inline_inherited_attributes(&mut src, &used);
+
+    // Les pages source elles-mêmes ne sont pas copiées : seul leur contenu
+    // est transplanté dans les pages cibles.
+    let excluded: HashSet<ObjectId> = [find_catalog(&src), find_pages_root(&src)]
+        .into_iter()
+        .flatten()
+        .chain(src_pages.values().copied())
+        .collect();
+    let id_map = copy_objects(&src, &mut dst, &excluded);
+
+    // Page source (identifiant copié) → première page cible qui la reçoit.
+    let mut page_map: HashMap<ObjectId, ObjectId> = HashMap::new();
+    for &(t_id, s_id) in &resolved {
+        page_map.entry(id_map[&s_id]).or_insert(t_id);
+    }
+
+    for (t_id, s_id) in resolved {
+        let Ok(Object::Dictionary(s_page)) = src
+            .get_object(s_id)
+            .map(|o| remap_object(o.clone(), &id_map))
+        else {
+            continue;
+        };
+
+        let annots = transplant_annotations(&mut dst, &s_page, t_id, &page_map);
+
+        let page = dst.get_object_mut(t_id)?.as_dict_mut()?;
+        for &key in PAGE_CONTENT_KEYS {
+            page.remove(key);
+            if let Ok(value) = s_page.get(key) {
+                page.set(key, value.clone());
+            }
+        }
+        // Une clé héritable absente de la source serait reprise des
+        // ancêtres de la cible (rotation, recadrage de l'autre document) :
+        // sa valeur par défaut est posée explicitement.
+        if !page.has(b"Rotate") {
+            page.set("Rotate", Object::Integer(0));
+        }
+        if !page.has(b"CropBox") {
+            if let Ok(media_box) = page.get(b"MediaBox").cloned() {
+                page.set("CropBox", media_box);
+            }
+        }
+        if !page.has(b"Resources") {
+            page.set("Resources", Object::Dictionary(Dictionary::new()));
+        }
+        page.set("Annots", Object::Array(annots));
+    }
+
+    // Le contenu remplacé ne doit pas subsister dans le fichier produit.
+    prune_unreachable(&mut dst);
+    rebuild(dst, encryption)
+}
+
+/// Rattache les annotations de la page source à la page cible (`/P`).
+/// Les liens internes (`/Dest` ou `/A /D`) vers une page source reprise
+/// pointent désormais vers la page cible qui la reçoit (`page_map`, indexé
+/// par l'identifiant copié de la page source) ; seuls ceux vers une page
+/// source non reprise sont écartés.
+fn transplant_annotations(
+    doc: &mut Document,
+    s_page: &Dictionary,
+    t_id: ObjectId,
+    page_map: &HashMap<ObjectId, ObjectId>,
+) -> Vec<Object> {
+    let annots = match s_page.get(b"Annots") {
+        Ok(Object::Array(arr)) => arr.clone(),
+        Ok(Object::Reference(id)) => doc
+            .objects
+            .get(id)
+            .and_then(|o| o.as_array().ok())
+            .cloned()
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    let mut kept = Vec::with_capacity(annots.len());
+    for mut annot in annots {
+        let target = match &annot {
+            Object::Dictionary(dict) => link_page(dict),
+            Object::Reference(id) => doc.objects.get(id).and_then(|o| o.as_dict().ok()).and_then(link_page),
+            _ => None,
+        };
+        let new_target = match target {
+            Some(id) => match page_map.get(&id) {
+                Some(&new_id) => Some(new_id),
+                None if doc.objects.contains_key(&id) => None,
+                // Page source non reprise : le lien ne mène plus nulle part.
+                None => continue,
+            },
+            None => None,
+        };
+
+        let dict = match &mut annot {
+            Object::Dictionary(dict) => Some(dict),
+            Object::Reference(id) => doc.objects.get_mut(id).and_then(|o| o.as_dict_mut().ok()),
+            _ => None,
+        };
+        if let Some(dict) = dict {
+            dict.set("P", Object::Reference(t_id));
+            if let (Some(new_id), Some(page)) = (new_target, link_page_mut(dict)) {
+                *page = Object::Reference(new_id);
+            }
+        }
+        kept.push(annot);
+    }
+    kept
+}
+
+/// Page visée par un lien interne : premier élément de `/Dest` ou de `/A /D`.
+fn link_page(dict: &Dictionary) -> Option<ObjectId> {
+    dict.get(b"Dest")
+        .or_else(|_| dict.get(b"A").and_then(Object::as_dict).and_then(|a| a.get(b"D")))
+        .and_then(Object::as_array)
+        .ok()
+        .and_then(|d| d.first())
+        .and_then(|p| p.as_reference().ok())
+}
+
+fn link_page_mut(dict: &mut Dictionary) -> Option<&mut Object> {
+    let dest = if dict.has(b"Dest") {
+        dict.get_mut(b"Dest").ok()?
+    } else {
+        dict.get_mut(b"A").and_then(Object::as_dict_mut).and_then(|a| a.get_mut(b"D")).ok()?
+    };
+    dest.as_array_mut().ok()?.first_mut()
+}
@@ -1,17 +1,26 @@
 use lopdf::{Document, Object, ObjectId};
-use std::collections::HashSet;
 
+use super::encrypt::Encryption;
 use super::error::{PdfError, Result};
-use super::utils::{load_document, 
-    copy_objects, find_catalog, find_pages_root, finalize,
-    insert_catalog, insert_pages_node, set_parent,
+use super::utils::{
+    load_document, multiply, page_box, page_rotation, rebuild, rotation_matrix, transform_page,
+    transform_rect, visible_box,
 };
 
 /// Applique des rotations à des pages spécifiques.
 /// Chaque entrée de `rotations` est `(numéro_de_page, angle)` avec angle ∈ {90, 180, 270}.
 /// L'angle est cumulé avec la rotation existante de la page.
-pub fn rotate_pages(data: &[u8], rotations: &[(u32, i64)]) -> Result<Vec<u8>> {
-    let mut src = load_document(data)?;
+///
+/// Si `bake` est vrai, la rotation de toutes les pages est ensuite intégrée
+/// au contenu (voir [`normalize_rotation`]).
+pub fn rotate_pages(
+    data: &[u8],
+    rotations: &[(u32, i64)],
+    bake: bool,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut src = load_document(data, password)?;
     src.decompress();
 
     let all_pages = src.get_pages();
@@ -37,42 +46,134 @@ pub fn rotate_pages(data: &[u8], rotations: &[(u32, i64)]) -> Result<Vec<u8>> {
         }
     }
 
-    rebuild(src)
+    if bake {
+        for &page_id in all_pages.values() {
+            bake_rotation(&mut src, page_id)?;
+        }
+    }
+
+    rebuild(src, encryption)
 }
 
-/// Reconstruit un document propre à partir d'un document modifié en place.
-/// Nécessaire car lopdf ne garantit pas un PDF valide après modification directe.
-fn rebuild(src: Document) -> Result<Vec<u8>> {
-    let mut out = Document::with_version("1.5");
+/// Intègre la rotation d'affichage (`/Rotate`) au contenu des pages listées
+/// (toutes si `None`), pour les outils qui ignorent cette clé.
+///
+/// Le contenu est transformé par une matrice de rotation, la `MediaBox` est
+/// permutée, les annotations suivent, et `/Rotate` est remis à 0 : le rendu
+/// est identique, mais ne dépend plus de la visionneuse.
+pub fn normalize_rotation(
+    data: &[u8],
+    page_numbers: Option<&[u32]>,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut src = load_document(data, password)?;
+    src.decompress();
 
-    let pages_root = find_pages_root(&src);
+    let all_pages = src.get_pages();
+    let total = all_pages.len() as u32;
+
+    let page_ids: Vec<ObjectId> = match page_numbers {
+        Some(numbers) => numbers
+            .iter()
+            .map(|&n| all_pages.get(&n).copied().ok_or(PdfError::PageOutOfRange { page: n, total }))
+            .collect::<Result<_>>()?,
+        None => all_pages.values().copied().collect(),
+    };
+
+    for page_id in page_ids {
+        bake_rotation(&mut src, page_id)?;
+    }
 
-    let excluded: HashSet<ObjectId> = [find_catalog(&src), pages_root]
-        .into_iter()
-        .flatten()
-        .collect();
+    rebuild(src, encryption)
+}
 
-    let id_map = copy_objects(&src, &mut out, &excluded);
+/// Traitement de la boîte de page lors d'une rotation libre du contenu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationFit {
+    /// La `MediaBox` est agrandie pour contenir la page entière une fois pivotée.
+    Grow,
+    /// La boîte est conservée : ce qui en déborde est rogné.
+    Clip,
+}
 
-    // Le nœud Pages source (avec ses Kids et sa hiérarchie intacte) devient
-    // l'unique enfant du nouveau nœud Pages racine.
-    if let Some(old_root) = pages_root {
-        let new_root = id_map[&old_root];
+/// Fait pivoter le contenu de pages d'un angle quelconque autour du centre
+/// de leur boîte visible, typiquement pour redresser un scan de travers.
+/// Chaque entrée de `rotations` est `(numéro_de_page, degrés)`, dans le sens
+/// horaire comme `/Rotate`.
+pub fn rotate_content(
+    data: &[u8],
+    rotations: &[(u32, f32)],
+    fit: RotationFit,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
+    let mut src = load_document(data, password)?;
+    src.decompress();
 
-        let count = src
-            .objects
-            .get(&old_root)
-            .and_then(|o| o.as_dict().ok())
-            .and_then(|d| d.get(b"Count").ok())
-            .and_then(|c| c.as_i64().ok())
-            .unwrap_or(0);
+    let all_pages = src.get_pages();
+    let total = all_pages.len() as u32;
 
-        let pages_id = insert_pages_node(&mut out, &[new_root], count);
-        set_parent(&mut out, &[new_root], pages_id);
+    for &(page_number, degrees) in rotations {
+        let &page_id = all_pages
+            .get(&page_number)
+            .ok_or(PdfError::PageOutOfRange { page: page_number, total })?;
+
+        let [x0, y0, x1, y1] = visible_box(&src, page_id);
+        let (cx, cy) = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+        let (sin, cos) = degrees.to_radians().sin_cos();
+
+        let m = multiply(
+            multiply([1.0, 0.0, 0.0, 1.0, -cx, -cy], [cos, -sin, sin, cos, 0.0, 0.0]),
+            [1.0, 0.0, 0.0, 1.0, cx, cy],
+        );
+
+        let grown: Vec<(&[u8], [f32; 4])> = match fit {
+            RotationFit::Grow => PAGE_BOXES
+                .iter()
+                .filter_map(|&key| page_box(&src, page_id, key).map(|b| (key, transform_rect(m, b))))
+                .collect(),
+            RotationFit::Clip => Vec::new(),
+        };
+
+        transform_page(&mut src, page_id, m)?;
+
+        let page = src.get_object_mut(page_id)?.as_dict_mut()?;
+        for (key, b) in grown {
+            page.set(key, Object::Array(b.iter().map(|&v| Object::Real(v)).collect()));
+        }
+    }
+
+    rebuild(src, encryption)
+}
+
+/// Clés de boîtes de page transformées avec le contenu.
+const PAGE_BOXES: &[&[u8]] = &[b"MediaBox", b"CropBox", b"BleedBox", b"TrimBox", b"ArtBox"];
 
-        let catalog_id = insert_catalog(&mut out, pages_id);
-        return finalize(&mut out, catalog_id);
+fn bake_rotation(doc: &mut Document, page_id: ObjectId) -> Result<()> {
+    let rotation = page_rotation(doc, page_id);
+    if rotation == 0 {
+        return Ok(());
     }
 
-    Err(PdfError::Lopdf(lopdf::Error::DictKey("Pages root introuvable".to_owned())))
+    let [x0, y0, x1, y1] = page_box(doc, page_id, b"MediaBox").unwrap_or([0.0, 0.0, 612.0, 792.0]);
+    let (w, h) = (x1 - x0, y1 - y0);
+
+    // Rotation horaire de l'affichage, le coin inférieur gauche de la
+    // MediaBox ramené à l'origine.
+    let m = multiply([1.0, 0.0, 0.0, 1.0, -x0, -y0], rotation_matrix(rotation, w, h));
+
+    let boxes: Vec<(&[u8], [f32; 4])> = PAGE_BOXES
+        .iter()
+        .filter_map(|&key| page_box(doc, page_id, key).map(|b| (key, transform_rect(m, b))))
+        .collect();
+
+    transform_page(doc, page_id, m)?;
+
+    let page = doc.get_object_mut(page_id)?.as_dict_mut()?;
+    for (key, b) in boxes {
+        page.set(key, Object::Array(b.iter().map(|&v| Object::Real(v)).collect()));
+    }
+    page.set("Rotate", Object::Integer(0));
+    Ok(())
 }
@@ -0,0 +1,432 @@
+use lopdf::content::Operation;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use std::collections::{BTreeMap, HashSet};
+
+use super::content::{decode, encode};
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::utils::{
+    find_catalog, finalize, inherited_attribute, load_document, page_content,
+    prune_unreachable, remove_name_tree, resolve_dict, visit_dicts_mut,
+};
+
+/// Catégories de données retirables par [`sanitize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SanitizeCategory {
+    /// Dictionnaire Info et paquets XMP (`/Metadata`).
+    Metadata,
+    /// Vignettes de pages (`/Thumb`).
+    Thumbnails,
+    /// Données privées d'applications (`/PieceInfo`).
+    PieceInfo,
+    /// JavaScript de niveau document (arbre `/JavaScript`, `/OpenAction`).
+    JavaScript,
+    /// Fichiers joints (arbre `/EmbeddedFiles`, annotations `FileAttachment`).
+    Attachments,
+    /// Commentaires : annotations de balisage et leurs popups.
+    Comments,
+    /// Calques masqués par défaut et le contenu qui leur appartient.
+    HiddenLayers,
+}
+
+impl SanitizeCategory {
+    pub const ALL: [Self; 7] = [
+        Self::Metadata,
+        Self::Thumbnails,
+        Self::PieceInfo,
+        Self::JavaScript,
+        Self::Attachments,
+        Self::Comments,
+        Self::HiddenLayers,
+    ];
+
+    /// Nom utilisé dans les champs multipart et le rapport JSON.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Metadata => "metadata",
+            Self::Thumbnails => "thumbnails",
+            Self::PieceInfo => "piece_info",
+            Self::JavaScript => "javascript",
+            Self::Attachments => "attachments",
+            Self::Comments => "comments",
+            Self::HiddenLayers => "hidden_layers",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Nombre d'éléments retirés, par nom de catégorie.
+pub type SanitizeReport = BTreeMap<&'static str, usize>;
+
+/// Annotations considérées comme des commentaires (ISO 32000, 12.5.6).
+const COMMENT_SUBTYPES: &[&[u8]] = &[
+    b"Text", b"FreeText", b"Line", b"Square", b"Circle", b"Polygon", b"PolyLine",
+    b"Highlight", b"Underline", b"Squiggly", b"StrikeOut", b"Caret", b"Ink",
+    b"Stamp", b"Popup", b"Sound",
+];
+
+/// Retire du document les catégories de données demandées et retourne le
+/// PDF nettoyé avec le nombre d'éléments retirés par catégorie.
+pub fn sanitize(
+    data: &[u8],
+    categories: &[SanitizeCategory],
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<(Vec<u8>, SanitizeReport)> {
+    let mut doc = load_document(data, password)?;
+    doc.decompress();
+    let catalog_id = find_catalog(&doc)
+        .ok_or_else(|| PdfError::InvalidFile("(catalogue introuvable)".to_string()))?;
+
+    let mut report = SanitizeReport::new();
+    let categories: HashSet<SanitizeCategory> = categories.iter().copied().collect();
+
+    for &category in SanitizeCategory::ALL.iter().filter(|c| categories.contains(c)) {
+        let removed = match category {
+            SanitizeCategory::Metadata => {
+                let info = usize::from(doc.trailer.remove(b"Info").is_some());
+                info + strip_key_everywhere(&mut doc, b"Metadata")
+            }
+            SanitizeCategory::Thumbnails => strip_key_everywhere(&mut doc, b"Thumb"),
+            SanitizeCategory::PieceInfo => strip_key_everywhere(&mut doc, b"PieceInfo"),
+            SanitizeCategory::JavaScript => strip_document_javascript(&mut doc, catalog_id)?,
+            SanitizeCategory::Attachments => {
+                let files = remove_name_tree(&mut doc, catalog_id, b"EmbeddedFiles")?;
+                let catalog = doc.get_object_mut(catalog_id)?.as_dict_mut()?;
+                let associated = usize::from(catalog.remove(b"AF").is_some());
+                files + associated + remove_annotations(&mut doc, |s| s == b"FileAttachment")
+            }
+            SanitizeCategory::Comments => {
+                remove_annotations(&mut doc, |s| COMMENT_SUBTYPES.contains(&s))
+            }
+            SanitizeCategory::HiddenLayers => strip_hidden_layers(&mut doc, catalog_id)?,
+        };
+        report.insert(category.name(), removed);
+    }
+
+    prune_unreachable(&mut doc);
+    let bytes = finalize(&mut doc, catalog_id, encryption)?;
+    Ok((bytes, report))
+}
+
+/// Retire `key` de tous les dictionnaires du document.
+fn strip_key_everywhere(doc: &mut Document, key: &[u8]) -> usize {
+    let mut removed = 0;
+    for obj in doc.objects.values_mut() {
+        visit_dicts_mut(obj, &mut |dict| {
+            if dict.remove(key).is_some() {
+                removed += 1;
+            }
+        });
+    }
+    removed
+}
+
+/// Arbre `/JavaScript` et `/OpenAction` de type JavaScript du catalogue.
+fn strip_document_javascript(doc: &mut Document, catalog_id: ObjectId) -> Result<usize> {
+    let mut removed = remove_name_tree(doc, catalog_id, b"JavaScript")?;
+
+    let catalog = doc.get_object(catalog_id)?.as_dict()?;
+    let open_action_is_js = catalog
+        .get(b"OpenAction")
+        .ok()
+        .and_then(|a| resolve_dict(doc, a))
+        .and_then(|a| a.get(b"S").ok())
+        .and_then(|s| s.as_name().ok())
+        == Some(b"JavaScript".as_slice());
+    if open_action_is_js {
+        doc.get_object_mut(catalog_id)?.as_dict_mut()?.remove(b"OpenAction");
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Retire des pages les annotations dont le sous-type satisfait `matches`.
+fn remove_annotations(doc: &mut Document, matches: impl Fn(&[u8]) -> bool) -> usize {
+    let page_ids: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    let mut removed = 0;
+
+    for page_id in page_ids {
+        let annots = match doc.get_object(page_id).and_then(Object::as_dict).and_then(|p| p.get(b"Annots")) {
+            Ok(Object::Array(arr)) => arr.clone(),
+            Ok(Object::Reference(id)) => match doc.get_object(*id) {
+                Ok(Object::Array(arr)) => arr.clone(),
+                _ => continue,
+            },
+            _ => continue,
+        };
+
+        let kept: Vec<Object> = annots
+            .iter()
+            .filter(|annot| {
+                let subtype = resolve_dict(doc, annot)
+                    .and_then(|a| a.get(b"Subtype").ok())
+                    .and_then(|s| s.as_name().ok());
+                !subtype.is_some_and(&matches)
+            })
+            .cloned()
+            .collect();
+
+        if kept.len() != annots.len() {
+            removed += annots.len() - kept.len();
+            if let Ok(page) = doc.get_object_mut(page_id).and_then(Object::as_dict_mut) {
+                page.set("Annots", Object::Array(kept));
+            }
+        }
+    }
+    removed
+}
+
+// ── Calques masqués ───────────────────────────────────────────────────────────
+
+/// Supprime les groupes de contenu optionnel masqués dans la configuration
+/// par défaut, ainsi que tout le contenu qui leur appartient : séquences
+/// `/OC … BDC … EMC`, XObjects et annotations portant un `/OC` masqué.
+fn strip_hidden_layers(doc: &mut Document, catalog_id: ObjectId) -> Result<usize> {
+    let hidden = hidden_ocgs(doc, catalog_id);
+    if hidden.is_empty() {
+        return Ok(0);
+    }
+
+    // Contenu des pages
+    let page_ids: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    for page_id in page_ids {
+        let Some(resources) = inherited_attribute(doc, page_id, b"Resources") else {
+            continue;
+        };
+        let content = page_content(doc, page_id);
+        if let Some(stripped) = strip_hidden_operations(doc, &resources, &content, &hidden) {
+            let stream_id = doc.add_object(Stream::new(Dictionary::new(), stripped));
+            doc.get_object_mut(page_id)?
+                .as_dict_mut()?
+                .set("Contents", Object::Reference(stream_id));
+        }
+    }
+
+    // Contenu des Form XObjects (ils peuvent eux-mêmes contenir des calques)
+    let forms: Vec<ObjectId> = doc
+        .objects
+        .iter()
+        .filter(|(_, o)| {
+            o.as_stream()
+                .is_ok_and(|s| s.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Form"))
+        })
+        .map(|(&id, _)| id)
+        .collect();
+    for form_id in forms {
+        let Ok(stream) = doc.get_object(form_id).and_then(Object::as_stream) else {
+            continue;
+        };
+        let Ok(resources) = stream.dict.get(b"Resources").cloned() else {
+            continue;
+        };
+        let content = stream.content.clone();
+        if let Some(stripped) = strip_hidden_operations(doc, &resources, &content, &hidden) {
+            if let Ok(stream) = doc.get_object_mut(form_id).and_then(Object::as_stream_mut) {
+                stream.set_content(stripped);
+            }
+        }
+    }
+
+    // Annotations rattachées à un calque masqué
+    let page_ids: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    for page_id in page_ids {
+        let Ok(Object::Array(annots)) = doc
+            .get_object(page_id)
+            .and_then(Object::as_dict)
+            .and_then(|p| p.get(b"Annots"))
+            .cloned()
+        else {
+            continue;
+        };
+        let kept: Vec<Object> = annots
+            .into_iter()
+            .filter(|a| {
+                !resolve_dict(doc, a)
+                    .and_then(|a| a.get(b"OC").ok())
+                    .is_some_and(|oc| is_hidden(doc, oc, &hidden))
+            })
+            .collect();
+        doc.get_object_mut(page_id)?.as_dict_mut()?.set("Annots", Object::Array(kept));
+    }
+
+    // Entrées de ressources vers les calques et XObjects masqués : sans
+    // elles, `prune_unreachable` retire ces objets du fichier.
+    let hidden_xobjects: HashSet<ObjectId> = doc
+        .objects
+        .iter()
+        .filter(|(_, o)| {
+            o.as_stream()
+                .ok()
+                .and_then(|s| s.dict.get(b"OC").ok())
+                .is_some_and(|oc| is_hidden(doc, oc, &hidden))
+        })
+        .map(|(&id, _)| id)
+        .collect();
+    let dropped: HashSet<ObjectId> = hidden.union(&hidden_xobjects).copied().collect();
+    for obj in doc.objects.values_mut() {
+        visit_dicts_mut(obj, &mut |dict| {
+            for category in [b"Properties".as_slice(), b"XObject"] {
+                if let Ok(Object::Dictionary(entries)) = dict.get_mut(category) {
+                    let names: Vec<Vec<u8>> = entries
+                        .iter()
+                        .filter(|(_, v)| v.as_reference().is_ok_and(|id| dropped.contains(&id)))
+                        .map(|(k, _)| k.clone())
+                        .collect();
+                    for name in names {
+                        entries.remove(&name);
+                    }
+                }
+            }
+        });
+    }
+
+    // Les groupes eux-mêmes disparaissent de /OCProperties
+    if let Ok(Object::Reference(id)) = doc.get_object(catalog_id)?.as_dict()?.get(b"OCProperties") {
+        let id = *id;
+        if let Ok(obj) = doc.get_object_mut(id) {
+            remove_references(obj, &hidden);
+        }
+    } else if let Ok(props) = doc.get_object_mut(catalog_id)?.as_dict_mut()?.get_mut(b"OCProperties") {
+        remove_references(props, &hidden);
+    }
+
+    Ok(hidden.len())
+}
+
+/// Groupes masqués dans la configuration par défaut (`/D`) : ceux de `/OFF`,
+/// ou tous ceux absents de `/ON` si `/BaseState` vaut `/OFF`.
+fn hidden_ocgs(doc: &Document, catalog_id: ObjectId) -> HashSet<ObjectId> {
+    let Some(props) = doc
+        .get_object(catalog_id)
+        .and_then(Object::as_dict)
+        .ok()
+        .and_then(|c| c.get(b"OCProperties").ok())
+        .and_then(|p| resolve_dict(doc, p))
+    else {
+        return HashSet::new();
+    };
+    let refs = |obj: Option<&Object>| -> HashSet<ObjectId> {
+        obj.and_then(|o| o.as_array().ok())
+            .map(|arr| arr.iter().filter_map(|o| o.as_reference().ok()).collect())
+            .unwrap_or_default()
+    };
+
+    let all = refs(props.get(b"OCGs").ok());
+    let Some(config) = props.get(b"D").ok().and_then(|d| resolve_dict(doc, d)) else {
+        return HashSet::new();
+    };
+    let base_off = config.get(b"BaseState").and_then(Object::as_name).ok() == Some(b"OFF");
+    if base_off {
+        let on = refs(config.get(b"ON").ok());
+        all.difference(&on).copied().collect()
+    } else {
+        refs(config.get(b"OFF").ok())
+    }
+}
+
+/// Un `/OC` (groupe ou dictionnaire d'appartenance `OCMD`) est-il masqué ?
+fn is_hidden(doc: &Document, oc: &Object, hidden: &HashSet<ObjectId>) -> bool {
+    if let Object::Reference(id) = oc {
+        if hidden.contains(id) {
+            return true;
+        }
+    }
+    let Some(dict) = resolve_dict(doc, oc) else {
+        return false;
+    };
+    if dict.get(b"Type").and_then(Object::as_name).ok() != Some(b"OCMD") {
+        return false;
+    }
+
+    let groups: Vec<bool> = match dict.get(b"OCGs") {
+        Ok(Object::Array(arr)) => arr
+            .iter()
+            .map(|g| g.as_reference().is_ok_and(|id| !hidden.contains(&id)))
+            .collect(),
+        Ok(Object::Reference(id)) => vec![!hidden.contains(id)],
+        _ => return false,
+    };
+    let visible = match dict.get(b"P").and_then(Object::as_name).ok() {
+        Some(b"AllOn") => groups.iter().all(|&on| on),
+        Some(b"AnyOff") => groups.iter().any(|&on| !on),
+        Some(b"AllOff") => groups.iter().all(|&on| !on),
+        _ => groups.iter().any(|&on| on),
+    };
+    !visible
+}
+
+/// Retire d'un flux de contenu les séquences marquées d'un calque masqué et
+/// les `Do` d'XObjects masqués. `None` si rien n'a changé (ou si le flux
+/// n'a pas pu être analysé, auquel cas il est laissé intact).
+fn strip_hidden_operations(
+    doc: &Document,
+    resources: &Object,
+    content: &[u8],
+    hidden: &HashSet<ObjectId>,
+) -> Option<Vec<u8>> {
+    let resources = resolve_dict(doc, resources)?;
+    let lookup = |category: &[u8], name: &[u8]| -> Option<Object> {
+        let entries = resolve_dict(doc, resources.get(category).ok()?)?;
+        entries.get(name).ok().cloned()
+    };
+    let hidden_property = |name: &[u8]| lookup(b"Properties", name).is_some_and(|oc| is_hidden(doc, &oc, hidden));
+    let hidden_xobject = |name: &[u8]| {
+        lookup(b"XObject", name)
+            .and_then(|x| resolve_dict(doc, &x).and_then(|d| d.get(b"OC").ok()).cloned())
+            .is_some_and(|oc| is_hidden(doc, &oc, hidden))
+    };
+
+    let operations = decode(content).ok()?;
+    let mut kept: Vec<Operation> = Vec::with_capacity(operations.len());
+    let mut skip_depth = 0usize;
+    let mut changed = false;
+
+    for op in operations {
+        let name_operand = |i: usize| op.operands.get(i).and_then(|o| o.as_name().ok());
+        if skip_depth > 0 {
+            match op.operator.as_str() {
+                "BMC" | "BDC" => skip_depth += 1,
+                "EMC" => skip_depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+        let drop = match op.operator.as_str() {
+            "BDC" if name_operand(0) == Some(b"OC") => {
+                let hidden = name_operand(1).is_some_and(hidden_property);
+                if hidden {
+                    skip_depth = 1;
+                }
+                hidden
+            }
+            "Do" => name_operand(0).is_some_and(hidden_xobject),
+            _ => false,
+        };
+        if drop {
+            changed = true;
+        } else {
+            kept.push(op);
+        }
+    }
+
+    if !changed {
+        return None;
+    }
+    encode(&kept).ok()
+}
+
+/// Retire récursivement des tableaux de `obj` les références vers `ids`.
+fn remove_references(obj: &mut Object, ids: &HashSet<ObjectId>) {
+    match obj {
+        Object::Array(arr) => {
+            arr.retain(|o| !o.as_reference().is_ok_and(|id| ids.contains(&id)));
+            arr.iter_mut().for_each(|o| remove_references(o, ids));
+        }
+        Object::Dictionary(dict) => dict.iter_mut().for_each(|(_, o)| remove_references(o, ids)),
+        _ => {}
+    }
+}
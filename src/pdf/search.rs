@@ -0,0 +1,267 @@
+use regex::{Regex, RegexBuilder};
+use serde_json::{json, Value};
+use std::ops::Range;
+
+use super::error::{PdfError, Result};
+use super::text::extract_text;
+use super::utils::format_page_ranges;
+
+/// Nombre maximum d'extraits renvoyés par page ; le décompte reste exact.
+pub const MAX_SNIPPETS_PER_PAGE: usize = 20;
+
+/// Caractères de contexte de part et d'autre d'une occurrence.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Lettres accentuées (minuscules) et leur forme sans accent.
+const DIACRITICS: &[(&str, &str)] = &[
+    ("àáâãäåāăą", "a"),
+    ("çćĉċč", "c"),
+    ("ďđ", "d"),
+    ("èéêëēĕėęě", "e"),
+    ("ĝğġģ", "g"),
+    ("ĥħ", "h"),
+    ("ìíîïĩīĭįı", "i"),
+    ("ĵ", "j"),
+    ("ķ", "k"),
+    ("ĺļľŀł", "l"),
+    ("ñńņňŉ", "n"),
+    ("òóôõöøōŏő", "o"),
+    ("ŕŗř", "r"),
+    ("śŝşšș", "s"),
+    ("ţťŧț", "t"),
+    ("ùúûüũūŭůűų", "u"),
+    ("ŵ", "w"),
+    ("ýÿŷ", "y"),
+    ("źżž", "z"),
+    ("æ", "ae"),
+    ("œ", "oe"),
+    ("ß", "ss"),
+];
+
+/// Ligatures typographiques, toujours décomposées : « ﬁchier » doit
+/// répondre à « fichier ».
+const LIGATURES: &[(char, &str)] = &[
+    ('ﬀ', "ff"),
+    ('ﬁ', "fi"),
+    ('ﬂ', "fl"),
+    ('ﬃ', "ffi"),
+    ('ﬄ', "ffl"),
+    ('ﬅ', "st"),
+    ('ﬆ', "st"),
+];
+
+/// Requête de recherche plein texte.
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    pub query: String,
+    /// `query` est une expression régulière plutôt qu'un texte littéral.
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub accent_sensitive: bool,
+}
+
+/// Requête compilée, appliquée au texte normalisé de la même façon.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    regex: Regex,
+    fold_accents: bool,
+}
+
+impl SearchQuery {
+    pub fn compile(&self) -> Result<Matcher> {
+        let query = self.query.trim();
+        if query.is_empty() {
+            return Err(PdfError::InvalidPattern("requête vide".to_string()));
+        }
+        let fold_accents = !self.accent_sensitive;
+        let (folded, _) = fold(query, fold_accents);
+        let source = if self.regex {
+            folded
+        } else {
+            // Un espace de la requête couvre toute suite de blancs, saut de
+            // ligne compris.
+            folded.split_whitespace().map(regex::escape).collect::<Vec<_>>().join(r"\s+")
+        };
+        // Taille bornée : le motif vient du client.
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(!self.case_sensitive)
+            .size_limit(1 << 20)
+            .build()
+            .map_err(|e| PdfError::InvalidPattern(e.to_string()))?;
+        Ok(Matcher { regex, fold_accents })
+    }
+}
+
+impl Matcher {
+    /// Occurrences non vides dans `text`, en plages d'octets de `text`.
+    pub fn find(&self, text: &str) -> Vec<Range<usize>> {
+        let (folded, sources) = fold(text, self.fold_accents);
+        let start = |i: usize| sources.get(i).map_or(text.len(), |r| r.start);
+        // Une fin au milieu d'un caractère décomposé l'inclut entier.
+        let end = |i: usize| match sources.get(i) {
+            Some(r) if i > 0 && sources[i - 1] == *r => r.end,
+            Some(r) => r.start,
+            None => text.len(),
+        };
+        self.regex
+            .find_iter(&folded)
+            .filter(|m| !m.is_empty())
+            .map(|m| start(m.start())..end(m.end()))
+            .collect()
+    }
+
+    /// `text` contient au moins une occurrence non vide.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.find_iter(&fold(text, self.fold_accents).0).any(|m| !m.is_empty())
+    }
+}
+
+/// Sélection de pages d'après leur texte, pour `/delete` et `/extract`.
+#[derive(Debug, Clone)]
+pub struct PageSelector {
+    criterion: Criterion,
+    /// Retient les pages qui ne satisfont pas le critère.
+    invert: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Criterion {
+    /// Pages contenant une occurrence.
+    Matching(Matcher),
+    /// Pages sans texte extractible : blanches ou numérisées sans OCR.
+    NoText,
+}
+
+/// Champs de sélection textuelle tels que reçus : `contains` (avec
+/// `regex`, `case_sensitive`, `accent_sensitive`), `invert` et `no_text`.
+#[derive(Debug, Clone, Default)]
+pub struct SelectionInput {
+    pub query: SearchQuery,
+    pub invert: bool,
+    pub no_text: bool,
+}
+
+impl SelectionInput {
+    /// Sélection décrite, `None` sans critère textuel.
+    pub fn selector(&self) -> Result<Option<PageSelector>> {
+        let has_query = !self.query.query.trim().is_empty();
+        let criterion = match (self.no_text, has_query) {
+            (true, true) => {
+                return Err(PdfError::InvalidPattern("'contains' et 'no_text' s'excluent".to_string()));
+            }
+            (true, false) => Criterion::NoText,
+            (false, true) => Criterion::Matching(self.query.compile()?),
+            (false, false) => return Ok(None),
+        };
+        Ok(Some(PageSelector { criterion, invert: self.invert }))
+    }
+}
+
+impl PageSelector {
+    fn selects(&self, text: &str) -> bool {
+        let satisfied = match &self.criterion {
+            Criterion::Matching(matcher) => matcher.is_match(text),
+            Criterion::NoText => text.trim().is_empty(),
+        };
+        satisfied != self.invert
+    }
+}
+
+/// Pages de `within` (toutes si `None`) retenues par `selector`, dans
+/// l'ordre du document.
+pub fn select_pages(
+    data: &[u8],
+    selector: &PageSelector,
+    within: Option<&[u32]>,
+    password: Option<&str>,
+) -> Result<Vec<u32>> {
+    let mut pages: Vec<u32> = extract_text(data, within, password)?
+        .into_iter()
+        .filter(|(_, text)| selector.selects(text))
+        .map(|(page, _)| page)
+        .collect();
+    pages.sort_unstable();
+    Ok(pages)
+}
+
+/// Texte normalisé pour la recherche (ligatures décomposées, accents
+/// retirés si `accents`) et, pour chacun de ses octets, la plage d'octets
+/// du caractère d'origine.
+fn fold(text: &str, accents: bool) -> (String, Vec<Range<usize>>) {
+    let mut out = String::with_capacity(text.len());
+    let mut sources: Vec<Range<usize>> = Vec::with_capacity(text.len());
+    let mut push = |s: &str, source: Range<usize>| {
+        out.push_str(s);
+        sources.extend(std::iter::repeat_n(source, s.len()));
+    };
+    for (i, c) in text.char_indices() {
+        let source = i..i + c.len_utf8();
+        if let Some((_, expanded)) = LIGATURES.iter().find(|(l, _)| *l == c) {
+            push(expanded, source);
+            continue;
+        }
+        if accents {
+            // Diacritiques combinants (forme décomposée).
+            if ('\u{300}'..='\u{36f}').contains(&c) {
+                continue;
+            }
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            if let Some((_, base)) = DIACRITICS.iter().find(|(letters, _)| letters.contains(lower)) {
+                if c.is_uppercase() {
+                    push(&base.to_uppercase(), source);
+                } else {
+                    push(base, source);
+                }
+                continue;
+            }
+        }
+        push(c.encode_utf8(&mut [0; 4]), source);
+    }
+    (out, sources)
+}
+
+/// Recherche dans le texte des pages `pages` (toutes si `None`) :
+/// `{ "count", "pages": [{ "page", "count", "hits": [{ "text", "snippet" }] }], "ranges" }`.
+/// `ranges` liste les pages trouvées dans la syntaxe de `parse_page_ranges`,
+/// prête pour `/extract`.
+pub fn search(data: &[u8], matcher: &Matcher, pages: Option<&[u32]>, password: Option<&str>) -> Result<Value> {
+    let mut total = 0;
+    let mut found: Vec<u32> = Vec::new();
+    let mut results: Vec<Value> = Vec::new();
+    for (page, text) in extract_text(data, pages, password)? {
+        let hits = matcher.find(&text);
+        if hits.is_empty() {
+            continue;
+        }
+        total += hits.len();
+        found.push(page);
+        let snippets: Vec<Value> = hits
+            .iter()
+            .take(MAX_SNIPPETS_PER_PAGE)
+            .map(|r| json!({ "text": collapse(&text[r.clone()]), "snippet": snippet(&text, r.clone()) }))
+            .collect();
+        results.push(json!({ "page": page, "count": hits.len(), "hits": snippets }));
+    }
+    Ok(json!({ "count": total, "pages": results, "ranges": format_page_ranges(&found) }))
+}
+
+/// Occurrence et son contexte, sur une ligne.
+fn snippet(text: &str, range: Range<usize>) -> String {
+    let before: Vec<(usize, char)> = text[..range.start].char_indices().collect();
+    let start = before.len().checked_sub(SNIPPET_CONTEXT).map_or(0, |i| before[i].0);
+    let end = text[range.end..].char_indices().nth(SNIPPET_CONTEXT).map_or(text.len(), |(i, _)| range.end + i);
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    out.push_str(&collapse(&text[start..end]));
+    if end < text.len() {
+        out.push('…');
+    }
+    out
+}
+
+/// Suites de blancs (sauts de ligne compris) réduites à une espace.
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,72 @@
+use lopdf::ObjectId;
+use serde_json::{json, Value};
+
+use super::archive::ZipArchive;
+use super::encrypt::Encryption;
+use super::error::{PdfError, Result};
+use super::extract::pages_to_document;
+use super::search::Matcher;
+use super::text::page_text;
+use super::utils::{format_page_ranges, inline_inherited_attributes, load_document};
+
+/// Nom du rapport détaillé dans l'archive, distinct de `document-N.pdf`.
+const REPORT_NAME: &str = "report.json";
+
+/// Découpe le document aux pages de séparation, reconnues à leur texte
+/// (`marker`) : chaque séparateur clôt un document et, s'il est conservé
+/// (`keep_separators`), ouvre le suivant comme page de garde.
+///
+/// Retourne l'archive ZIP (`document-1.pdf`, `document-2.pdf`…) et les
+/// totaux `{ "separators", "documents" }`. Le rapport détaillé
+/// `{ "separators": "3,7", "documents": [{ "name", "pages" }] }`, pages dans
+/// la syntaxe de `parse_page_ranges`, est ajouté à l'archive sous
+/// `report.json` : sa taille croît avec le nombre de documents.
+pub fn split_at_separators(
+    data: &[u8],
+    marker: &Matcher,
+    keep_separators: bool,
+    password: Option<&str>,
+    encryption: Option<&Encryption>,
+) -> Result<(Vec<u8>, Value)> {
+    let mut doc = load_document(data, password)?;
+    doc.decompress();
+    let pages = doc.get_pages();
+    let page_ids: Vec<ObjectId> = pages.values().copied().collect();
+    inline_inherited_attributes(&mut doc, &page_ids);
+
+    let mut separators: Vec<u32> = Vec::new();
+    let mut groups: Vec<Vec<u32>> = vec![Vec::new()];
+    for (&number, &page_id) in &pages {
+        if marker.is_match(&page_text(&doc, page_id)?.text) {
+            separators.push(number);
+            groups.push(Vec::new());
+            if !keep_separators {
+                continue;
+            }
+        }
+        if let Some(group) = groups.last_mut() {
+            group.push(number);
+        }
+    }
+    // Séparateurs consécutifs, en tête ou en fin : pas de document vide.
+    groups.retain(|g| !g.is_empty());
+    if groups.is_empty() {
+        return Err(PdfError::NoPages);
+    }
+
+    let width = groups.len().to_string().len();
+    let mut archive = ZipArchive::new();
+    let mut documents = Vec::with_capacity(groups.len());
+    for (i, group) in groups.iter().enumerate() {
+        let ids: Vec<ObjectId> = group.iter().map(|n| pages[n]).collect();
+        let name = format!("document-{:0width$}.pdf", i + 1, width = width);
+        // Ni les pages des autres documents, ni les liens qui y renvoient.
+        archive.add(&name, &pages_to_document(&doc, &ids, encryption)?)?;
+        documents.push(json!({ "name": name, "pages": format_page_ranges(group) }));
+    }
+
+    let totals = json!({ "separators": separators.len(), "documents": documents.len() });
+    let report = json!({ "separators": format_page_ranges(&separators), "documents": documents });
+    archive.add(REPORT_NAME, report.to_string().as_bytes())?;
+    Ok((archive.finish()?, totals))
+}
@@ -0,0 +1,213 @@
+use lopdf::{Document, Object, ObjectId};
+use std::ops::Range;
+
+use super::content::{decode, ContentState, FontCache, Glyph, IDENTITY, MAX_FORM_DEPTH};
+use super::error::{PdfError, Result};
+use super::utils::{inherited_attribute, load_document, page_content, resolve_dict};
+
+/// Texte des pages `pages` (toutes si `None`), dans l'ordre de lecture.
+pub fn extract_text(data: &[u8], pages: Option<&[u32]>, password: Option<&str>) -> Result<Vec<(u32, String)>> {
+    let doc = load_document(data, password)?;
+    let all = doc.get_pages();
+    let total = all.len() as u32;
+    let numbers: Vec<u32> = match pages {
+        Some(pages) => pages.to_vec(),
+        None => all.keys().copied().collect(),
+    };
+    numbers
+        .into_iter()
+        .map(|page| {
+            let page_id = *all.get(&page).ok_or(PdfError::PageOutOfRange { page, total })?;
+            Ok((page, page_text(&doc, page_id)?.text))
+        })
+        .collect()
+}
+
+/// Texte d'une page, voir [`PageText`].
+pub fn page_text(doc: &Document, page_id: ObjectId) -> Result<PageText> {
+    Ok(PageText::new(&page_glyphs(doc, page_id)?))
+}
+
+/// Objet JSON `{ "1": "…", "2": "…" }`, clés dans l'ordre des pages
+/// (`serde_json::Map` les trierait comme des chaînes : 1, 10, 2).
+pub fn text_to_json(pages: &[(u32, String)]) -> String {
+    let entries: Vec<String> = pages
+        .iter()
+        .map(|(page, text)| format!("\"{}\":{}", page, serde_json::Value::from(text.as_str())))
+        .collect();
+    format!("{{{}}}", entries.join(","))
+}
+
+/// Glyphes de la page dans l'ordre du flux de contenu, Form XObjects compris.
+pub fn page_glyphs(doc: &Document, page_id: ObjectId) -> Result<Vec<Glyph>> {
+    let resources = inherited_attribute(doc, page_id, b"Resources")
+        .and_then(|r| resolve_dict(doc, &r).cloned())
+        .unwrap_or_default();
+    let operations = decode(&page_content(doc, page_id))?;
+    let mut glyphs = Vec::new();
+    let state = ContentState::new(IDENTITY, resources);
+    collect(doc, &operations, state, &mut FontCache::default(), &mut glyphs, 0)?;
+    Ok(glyphs)
+}
+
+fn collect(
+    doc: &Document,
+    operations: &[lopdf::content::Operation],
+    mut state: ContentState,
+    fonts: &mut FontCache,
+    out: &mut Vec<Glyph>,
+    depth: usize,
+) -> Result<()> {
+    for op in operations {
+        state.apply(doc, fonts, op);
+        match op.operator.as_str() {
+            "Tj" | "'" | "\"" => {
+                if let Some(Object::String(bytes, _)) = op.operands.last() {
+                    out.extend(state.show(bytes));
+                }
+            }
+            "TJ" => {
+                for element in op.operands.first().and_then(|a| a.as_array().ok()).into_iter().flatten() {
+                    match element {
+                        Object::String(bytes, _) => out.extend(state.show(bytes)),
+                        other => {
+                            if let Ok(amount) = other.as_float() {
+                                state.adjust(amount);
+                            }
+                        }
+                    }
+                }
+            }
+            "Do" if depth < MAX_FORM_DEPTH => {
+                let name = op.operands.first().and_then(|o| o.as_name().ok()).unwrap_or_default();
+                let Some((_, stream)) = state.xobject(doc, name) else {
+                    continue;
+                };
+                if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Form") {
+                    let inner = state.form(doc, stream);
+                    let content = stream.decompressed_content().unwrap_or_else(|_| stream.content.clone());
+                    collect(doc, &decode(&content)?, inner, fonts, out, depth + 1)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Texte d'une page dans l'ordre de lecture : lignes de haut en bas, puis
+/// de gauche à droite dans chaque ligne. Les espaces et sauts de ligne
+/// absents du flux (mots placés par positionnement) sont ajoutés d'après
+/// l'écart entre les glyphes.
+#[derive(Debug, Clone, Default)]
+pub struct PageText {
+    pub text: String,
+    /// Pour chaque caractère de `text` : sa position en octets et les
+    /// glyphes qui l'affichent (aucun pour un séparateur ajouté).
+    chars: Vec<(usize, Vec<usize>)>,
+}
+
+/// Glyphe d'une ligne, en coordonnées tournées dans le sens du texte.
+struct Placed {
+    index: usize,
+    along: f32,
+    across: f32,
+}
+
+impl PageText {
+    pub fn new(glyphs: &[Glyph]) -> Self {
+        // Regroupement par direction d'écriture, dans l'ordre d'apparition.
+        let mut directions: Vec<(i32, Vec<Placed>)> = Vec::new();
+        for (index, glyph) in glyphs.iter().enumerate() {
+            if glyph.text.is_empty() || glyph.size <= 0.0 {
+                continue;
+            }
+            let angle = glyph.angle.round() as i32;
+            let (sin, cos) = (angle as f32).to_radians().sin_cos();
+            let (x, y) = glyph.origin;
+            let placed = Placed { index, along: x * cos + y * sin, across: -x * sin + y * cos };
+            match directions.iter_mut().find(|(a, _)| *a == angle) {
+                Some((_, list)) => list.push(placed),
+                None => directions.push((angle, vec![placed])),
+            }
+        }
+
+        let mut page = PageText::default();
+        for (_, mut placed) in directions {
+            placed.sort_by(|a, b| b.across.total_cmp(&a.across));
+            // Lignes : glyphes dont la ligne de base est proche de celle du
+            // premier glyphe de la ligne (exposants et indices compris).
+            let mut lines: Vec<Vec<Placed>> = Vec::new();
+            for p in placed {
+                match lines.last_mut() {
+                    Some(line)
+                        if line[0].across - p.across < 0.5 * glyphs[line[0].index].size.min(glyphs[p.index].size) =>
+                    {
+                        line.push(p)
+                    }
+                    _ => lines.push(vec![p]),
+                }
+            }
+            for mut line in lines {
+                line.sort_by(|a, b| a.along.total_cmp(&b.along));
+                if !page.text.is_empty() {
+                    page.push('\n', Vec::new());
+                }
+                let mut previous: Option<&Placed> = None;
+                for p in &line {
+                    let glyph = &glyphs[p.index];
+                    if let Some(prev) = previous {
+                        let prev_glyph = &glyphs[prev.index];
+                        let gap = p.along - (prev.along + prev_glyph.width);
+                        // Glyphe redessiné au même endroit (faux gras) : un seul caractère.
+                        if glyph.text == prev_glyph.text && (p.along - prev.along).abs() < 0.15 * glyph.size {
+                            if let Some((_, sources)) = page.chars.last_mut() {
+                                sources.push(p.index);
+                            }
+                            continue;
+                        }
+                        let spaced = prev_glyph.text.ends_with(char::is_whitespace)
+                            || glyph.text.starts_with(char::is_whitespace);
+                        if gap > 0.2 * glyph.size.min(prev_glyph.size) && !spaced {
+                            page.push(' ', Vec::new());
+                        }
+                    }
+                    for c in glyph.text.chars() {
+                        page.push(c, vec![p.index]);
+                    }
+                    previous = Some(p);
+                }
+            }
+        }
+        page
+    }
+
+    fn push(&mut self, c: char, sources: Vec<usize>) {
+        self.chars.push((self.text.len(), sources));
+        self.text.push(c);
+    }
+
+    /// Glyphes des caractères de la plage d'octets `range`, regroupés par
+    /// ligne.
+    pub fn glyph_lines(&self, range: Range<usize>) -> Vec<Vec<usize>> {
+        let first = self.chars.partition_point(|(offset, _)| *offset < range.start);
+        let mut lines: Vec<Vec<usize>> = vec![Vec::new()];
+        for (offset, sources) in &self.chars[first..] {
+            if *offset >= range.end {
+                break;
+            }
+            if self.text[*offset..].starts_with('\n') {
+                lines.push(Vec::new());
+            }
+            if let Some(line) = lines.last_mut() {
+                for &index in sources {
+                    if !line.contains(&index) {
+                        line.push(index);
+                    }
+                }
+            }
+        }
+        lines.retain(|l| !l.is_empty());
+        lines
+    }
+}
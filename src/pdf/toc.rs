@@ -0,0 +1,150 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+
+use super::error::Result;
+use super::font::{add_standard_font, text_width, win_ansi_encode, HELVETICA, HELVETICA_BOLD};
+
+/// Ligne de la table des matières.
+#[derive(Debug, Clone)]
+pub struct TocEntry {
+    pub title: String,
+    /// Niveau d'indentation, 0 pour le premier niveau.
+    pub level: usize,
+    /// Page cible dans le document.
+    pub page_id: ObjectId,
+    /// Numéro de la page cible, 1-indexé, sans compter la table des matières.
+    pub page: u32,
+}
+
+const HEADING: &str = "Table des matières";
+const MARGIN: f32 = 56.0;
+const HEADING_SIZE: f32 = 18.0;
+const FONT_SIZE: f32 = 11.0;
+const LINE_HEIGHT: f32 = 18.0;
+const INDENT: f32 = 16.0;
+/// Espace entre le texte et les points de conduite.
+const GAP: f32 = 4.0;
+
+/// Crée les pages de la table des matières, au format `media_box`, et
+/// retourne leurs IDs dans l'ordre. Les numéros affichés tiennent compte
+/// de ces pages, destinées à être insérées en tête du document.
+pub fn build_toc_pages(doc: &mut Document, entries: &[TocEntry], media_box: [f32; 4]) -> Result<Vec<ObjectId>> {
+    let [left, bottom, right, top] = media_box;
+    let (left, right) = (left + MARGIN, right - MARGIN);
+    let (bottom, top) = (bottom + MARGIN, top - MARGIN);
+
+    // Lignes par page : la première porte aussi le titre.
+    let first_start = top - HEADING_SIZE - 2.0 * LINE_HEIGHT;
+    let capacity = |start: f32| (((start - bottom) / LINE_HEIGHT).floor() as usize + 1).max(1);
+    let (first_capacity, capacity) = (capacity(first_start), capacity(top - FONT_SIZE));
+    let chunks: Vec<&[TocEntry]> = if entries.len() <= first_capacity {
+        vec![entries]
+    } else {
+        let (first, rest) = entries.split_at(first_capacity);
+        std::iter::once(first).chain(rest.chunks(capacity)).collect()
+    };
+    let offset = chunks.len() as u32;
+
+    let regular = add_standard_font(doc, HELVETICA);
+    let bold = add_standard_font(doc, HELVETICA_BOLD);
+    let resources = doc.add_object(Dictionary::from_iter(vec![(
+        "Font",
+        Object::Dictionary(Dictionary::from_iter(vec![
+            ("F1", Object::Reference(regular)),
+            ("F2", Object::Reference(bold)),
+        ])),
+    )]));
+
+    let mut page_ids = Vec::with_capacity(chunks.len());
+    let mut index = 0;
+    for (n, chunk) in chunks.iter().enumerate() {
+        let mut ops = Vec::new();
+        let mut y = if n == 0 {
+            show_text(&mut ops, "F2", HEADING_SIZE, left, top - HEADING_SIZE, win_ansi_encode(HEADING));
+            first_start
+        } else {
+            top - FONT_SIZE
+        };
+
+        let mut annots = Vec::with_capacity(chunk.len());
+        for entry in chunk.iter() {
+            let x = left + INDENT * entry.level as f32;
+            let size = FONT_SIZE;
+            // En gras : les entrées suivies de sous-entrées.
+            index += 1;
+            let font = match entries.get(index) {
+                Some(next) if next.level > entry.level => "F2",
+                _ => "F1",
+            };
+
+            let number = win_ansi_encode(&(entry.page + offset).to_string());
+            let number_width = text_width(&number, size);
+            let dot_width = text_width(b".", size);
+            // Au moins trois points de conduite entre le titre et le numéro.
+            let max_title = right - x - number_width - 2.0 * GAP - 3.0 * dot_width;
+            let title = fit_text(&entry.title, size, max_title);
+            let title_width = text_width(&title, size);
+
+            show_text(&mut ops, font, size, x, y, title);
+            // Points alignés d'une ligne à l'autre, calés sur la marge droite.
+            let leaders_end = right - number_width - GAP;
+            let dots = ((leaders_end - x - title_width - GAP) / dot_width).floor().max(0.0) as usize;
+            if dots > 0 {
+                let leaders_x = leaders_end - dots as f32 * dot_width;
+                show_text(&mut ops, "F1", size, leaders_x, y, vec![b'.'; dots]);
+            }
+            show_text(&mut ops, font, size, right - number_width, y, number);
+
+            annots.push(Object::Dictionary(Dictionary::from_iter(vec![
+                ("Type", Object::Name(b"Annot".to_vec())),
+                ("Subtype", Object::Name(b"Link".to_vec())),
+                ("Rect", Object::Array(
+                    [x, y - 4.0, right, y + size].iter().map(|&v| Object::Real(v)).collect(),
+                )),
+                ("Border", Object::Array(vec![0.into(), 0.into(), 0.into()])),
+                ("Dest", Object::Array(vec![
+                    Object::Reference(entry.page_id),
+                    Object::Name(b"Fit".to_vec()),
+                ])),
+            ])));
+            y -= LINE_HEIGHT;
+        }
+
+        let content = Content { operations: ops }.encode()?;
+        let contents = doc.add_object(Stream::new(Dictionary::new(), content));
+        page_ids.push(doc.add_object(Dictionary::from_iter(vec![
+            ("Type", Object::Name(b"Page".to_vec())),
+            ("MediaBox", Object::Array(media_box.iter().map(|&v| Object::Real(v)).collect())),
+            ("Resources", Object::Reference(resources)),
+            ("Contents", Object::Reference(contents)),
+            ("Annots", Object::Array(annots)),
+        ])));
+    }
+    Ok(page_ids)
+}
+
+fn show_text(ops: &mut Vec<Operation>, font: &str, size: f32, x: f32, y: f32, text: Vec<u8>) {
+    ops.push(Operation::new("BT", vec![]));
+    ops.push(Operation::new("Tf", vec![Object::Name(font.as_bytes().to_vec()), Object::Real(size)]));
+    ops.push(Operation::new("Td", vec![Object::Real(x), Object::Real(y)]));
+    ops.push(Operation::new("Tj", vec![Object::String(text, StringFormat::Literal)]));
+    ops.push(Operation::new("ET", vec![]));
+}
+
+/// Encode `text` en le tronquant avec `…` s'il dépasse `max_width`.
+fn fit_text(text: &str, size: f32, max_width: f32) -> Vec<u8> {
+    let encoded = win_ansi_encode(text);
+    if text_width(&encoded, size) <= max_width {
+        return encoded;
+    }
+    let ellipsis = win_ansi_encode("…");
+    let mut kept = encoded;
+    while !kept.is_empty() && text_width(&kept, size) + text_width(&ellipsis, size) > max_width {
+        kept.pop();
+    }
+    while kept.last() == Some(&b' ') {
+        kept.pop();
+    }
+    kept.extend(ellipsis);
+    kept
+}
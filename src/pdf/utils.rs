@@ -1,7 +1,9 @@
-use lopdf::{Dictionary, Document, Object, ObjectId};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
 use std::collections::{HashMap, HashSet};
 use std::io::Write;
 
+use super::active_content::{self, strip_active_content, ActiveContentPolicy};
+use super::encrypt::{apply_encryption, Encryption};
 use super::error::{PdfError, Result};
 
 // ── Chargement robuste ────────────────────────────────────────────────────────
@@ -15,7 +17,18 @@ use super::error::{PdfError, Result};
 ///
 /// Si qpdf n'est pas installé ou échoue aussi, l'erreur lopdf originale
 /// est retournée.
-pub fn load_document(data: &[u8]) -> Result<Document> {
+///
+/// Un document chiffré est déchiffré avec `password` (voir [`unlock`]) :
+/// le document retourné n'est plus chiffré.
+pub fn load_document(data: &[u8], password: Option<&str>) -> Result<Document> {
+    let doc = load_raw(data)?;
+    if doc.trailer.has(b"Encrypt") {
+        return unlock(doc, data, password);
+    }
+    Ok(doc)
+}
+
+fn load_raw(data: &[u8]) -> Result<Document> {
     match Document::load_mem(data) {
         Ok(doc) => Ok(doc),
         Err(original_err) => {
@@ -39,6 +52,100 @@ pub fn load_document(data: &[u8]) -> Result<Document> {
     }
 }
 
+/// Déchiffre un document chargé par lopdf.
+///
+/// lopdf ne sait ouvrir seul que les documents sans mot de passe
+/// d'ouverture dont le dictionnaire `/Encrypt` est un objet indirect ; pour
+/// les autres il ne charge aucun objet. Le fichier est alors relu avec la
+/// clé `/Encrypt` masquée (objets lus bruts, toujours chiffrés), puis
+/// déchiffré avec `password`.
+///
+/// Le masquage se fait octet par octet, sans décaler la xref : il couvre
+/// chaque trailer et dictionnaire de xref stream, y compris ceux des mises à
+/// jour incrémentales, mais pas une clé écrite avec des échappements `#xx`.
+/// Un tel fichier est refusé par [`PdfError::UnsupportedEncryption`].
+fn unlock(doc: Document, data: &[u8], password: Option<&str>) -> Result<Document> {
+    let mut doc = doc;
+
+    // Mot de passe d'ouverture vide : lopdf a déjà tout déchiffré, il ne
+    // reste qu'à retirer le dictionnaire /Encrypt.
+    if doc.encryption_state.is_some() {
+        if let Some(Object::Reference(id)) = doc.trailer.remove(b"Encrypt") {
+            doc.objects.remove(&id);
+        }
+        return Ok(doc);
+    }
+
+    // Sans mot de passe, on tente quand même le mot de passe vide : lopdf
+    // ne l'a pas essayé si le dictionnaire /Encrypt est direct.
+    let password = password.unwrap_or("");
+
+    let mut doc = load_raw(&mask_encrypt_key(data))?;
+    // lopdf n'accepte qu'un /Encrypt indirect : un dictionnaire direct
+    // devient un objet du document.
+    let encrypt_id = match doc.trailer.remove(MASKED_ENCRYPT_KEY) {
+        Some(Object::Reference(id)) => id,
+        Some(Object::Dictionary(dict)) => doc.add_object(dict),
+        _ => return Err(PdfError::UnsupportedEncryption),
+    };
+    doc.trailer.set("Encrypt", Object::Reference(encrypt_id));
+
+    doc.decrypt(password).map_err(|e| match e {
+        lopdf::Error::Decryption(lopdf::encryption::DecryptionError::IncorrectPassword) => {
+            if password.is_empty() {
+                PdfError::Encrypted
+            } else {
+                PdfError::WrongPassword
+            }
+        }
+        e => PdfError::Lopdf(e),
+    })?;
+    Ok(doc)
+}
+
+/// Nom de même longueur que `Encrypt`, pour ne décaler aucun offset de la xref.
+const MASKED_ENCRYPT_KEY: &[u8] = b"Encrypx";
+
+/// Renomme les entrées `/Encrypt n g R` et `/Encrypt << … >>` (trailer,
+/// dictionnaire de xref stream).
+fn mask_encrypt_key(data: &[u8]) -> Vec<u8> {
+    let mut out = data.to_vec();
+    let key = b"/Encrypt";
+    let mut i = 0;
+    while let Some(pos) = out[i..].windows(key.len()).position(|w| w == key) {
+        let start = i + pos;
+        i = start + key.len();
+        // Seules les références indirectes et les dictionnaires sont
+        // concernés : on évite ainsi de toucher au contenu d'un stream qui
+        // contiendrait ce texte.
+        if is_reference(&out[i..]) || is_dictionary(&out[i..]) {
+            out[start + 1..i].copy_from_slice(MASKED_ENCRYPT_KEY);
+        }
+    }
+    out
+}
+
+/// `true` si `data` commence par `<<` (espaces de tête autorisés).
+fn is_dictionary(data: &[u8]) -> bool {
+    let spaces = data.iter().take_while(|b| b.is_ascii_whitespace()).count();
+    data[spaces..].starts_with(b"<<")
+}
+
+/// `true` si `data` commence par `n g R` (espaces de tête autorisés).
+fn is_reference(data: &[u8]) -> bool {
+    let mut rest = data;
+    for _ in 0..2 {
+        let spaces = rest.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        let digits = rest[spaces..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return false;
+        }
+        rest = &rest[spaces + digits..];
+    }
+    let spaces = rest.iter().take_while(|b| b.is_ascii_whitespace()).count();
+    rest.get(spaces) == Some(&b'R')
+}
+
 /// Normalise un PDF via `qpdf --object-streams=disable`.
 /// Retourne les bytes du PDF normalisé, ou une erreur si qpdf est absent/échoue.
 fn normalize_via_qpdf(data: &[u8]) -> std::result::Result<Vec<u8>, ()> {
@@ -98,6 +205,25 @@ fn remap_dict(dict: Dictionary, map: &HashMap<ObjectId, ObjectId>) -> Dictionary
     out
 }
 
+/// Appelle `f` sur chaque dictionnaire contenu dans `obj` (dictionnaires
+/// de streams compris), parcouru comme dans `remap_object`. `f` est appelé
+/// avant la descente : les entrées qu'il retire ne sont pas visitées.
+pub fn visit_dicts_mut(obj: &mut Object, f: &mut dyn FnMut(&mut Dictionary)) {
+    match obj {
+        Object::Array(arr) => arr.iter_mut().for_each(|o| visit_dicts_mut(o, f)),
+        Object::Dictionary(dict) => visit_dict_mut(dict, f),
+        Object::Stream(s) => visit_dict_mut(&mut s.dict, f),
+        _ => {}
+    }
+}
+
+fn visit_dict_mut(dict: &mut Dictionary, f: &mut dyn FnMut(&mut Dictionary)) {
+    f(dict);
+    for (_, v) in dict.iter_mut() {
+        visit_dicts_mut(v, f);
+    }
+}
+
 // ── Recherche d'objets ────────────────────────────────────────────────────────
 
 fn dict_type(dict: &Dictionary) -> Option<&[u8]> {
@@ -124,6 +250,459 @@ pub fn find_pages_root(doc: &Document) -> Option<ObjectId> {
     })
 }
 
+/// Résout une référence vers le dictionnaire (ou le dictionnaire d'un stream) pointé.
+pub fn resolve_dict<'a>(doc: &'a Document, obj: &'a Object) -> Option<&'a Dictionary> {
+    match obj {
+        Object::Reference(id) => match doc.get_object(*id).ok()? {
+            Object::Dictionary(dict) => Some(dict),
+            Object::Stream(stream) => Some(&stream.dict),
+            _ => None,
+        },
+        Object::Dictionary(dict) => Some(dict),
+        Object::Stream(stream) => Some(&stream.dict),
+        _ => None,
+    }
+}
+
+/// Retire l'arbre `key` du dictionnaire `/Names` du catalogue et retourne
+/// le nombre d'entrées qu'il contenait.
+pub fn remove_name_tree(doc: &mut Document, catalog_id: ObjectId, key: &[u8]) -> Result<usize> {
+    let names_id = match doc.get_object(catalog_id)?.as_dict()?.get(b"Names") {
+        Ok(Object::Reference(id)) => Some(*id),
+        _ => None,
+    };
+    let names = match names_id {
+        Some(id) => doc.get_object_mut(id)?.as_dict_mut()?,
+        None => match doc.get_object_mut(catalog_id)?.as_dict_mut()?.get_mut(b"Names") {
+            Ok(Object::Dictionary(dict)) => dict,
+            _ => return Ok(0),
+        },
+    };
+    let Some(tree) = names.remove(key) else {
+        return Ok(0);
+    };
+    Ok(count_name_tree(doc, &tree, 0))
+}
+
+fn count_name_tree(doc: &Document, node: &Object, depth: usize) -> usize {
+    // Borne la descente pour se protéger des arbres cycliques.
+    let Some(dict) = resolve_dict(doc, node).filter(|_| depth < 32) else {
+        return 0;
+    };
+    let leaves = dict
+        .get(b"Names")
+        .and_then(Object::as_array)
+        .map_or(0, |arr| arr.len() / 2);
+    let kids: usize = dict
+        .get(b"Kids")
+        .and_then(Object::as_array)
+        .map_or(0, |arr| arr.iter().map(|k| count_name_tree(doc, k, depth + 1)).sum());
+    leaves + kids
+}
+
+/// Supprime les objets inaccessibles depuis le trailer et retourne leur nombre.
+///
+/// Après une modification en place, l'ancien contenu (flux remplacés,
+/// annotations retirées…) resterait sinon présent dans le fichier produit.
+pub fn prune_unreachable(doc: &mut Document) -> usize {
+    fn collect(obj: &Object, stack: &mut Vec<ObjectId>) {
+        match obj {
+            Object::Reference(id) => stack.push(*id),
+            Object::Array(arr) => arr.iter().for_each(|o| collect(o, stack)),
+            Object::Dictionary(dict) => dict.iter().for_each(|(_, o)| collect(o, stack)),
+            Object::Stream(s) => s.dict.iter().for_each(|(_, o)| collect(o, stack)),
+            _ => {}
+        }
+    }
+
+    let mut reachable: HashSet<ObjectId> = HashSet::new();
+    let mut stack = Vec::new();
+    doc.trailer.iter().for_each(|(_, o)| collect(o, &mut stack));
+    while let Some(id) = stack.pop() {
+        if reachable.insert(id) {
+            if let Some(obj) = doc.objects.get(&id) {
+                collect(obj, &mut stack);
+            }
+        }
+    }
+
+    let before = doc.objects.len();
+    doc.objects.retain(|id, _| reachable.contains(id));
+    before - doc.objects.len()
+}
+
+// ── Attributs de page ─────────────────────────────────────────────────────────
+
+/// Retourne un attribut de page en remontant la chaîne `Parent` si la page
+/// ne le définit pas elle-même (`Resources`, `MediaBox`, `CropBox`, `Rotate`
+/// sont héritables).
+pub fn inherited_attribute(doc: &Document, page_id: ObjectId, key: &[u8]) -> Option<Object> {
+    let mut current = page_id;
+    // Borne la remontée pour se protéger des arbres de pages cycliques.
+    for _ in 0..64 {
+        let dict = doc.objects.get(&current)?.as_dict().ok()?;
+        if let Ok(value) = dict.get(key) {
+            return Some(value.clone());
+        }
+        current = dict.get(b"Parent").ok()?.as_reference().ok()?;
+    }
+    None
+}
+
+/// Recopie sur chaque page les attributs hérités de ses ancêtres.
+///
+/// À appeler avant de reconstruire un arbre de pages sans l'ancien nœud racine :
+/// sans cela, une page qui héritait sa `MediaBox` ou ses `Resources` les perdrait.
+pub fn inline_inherited_attributes(doc: &mut Document, page_ids: &[ObjectId]) {
+    for &page_id in page_ids {
+        for key in [&b"Resources"[..], b"MediaBox", b"CropBox", b"Rotate"] {
+            let Some(value) = inherited_attribute(doc, page_id, key) else { continue };
+            if let Some(Object::Dictionary(dict)) = doc.objects.get_mut(&page_id) {
+                if !dict.has(key) {
+                    dict.set(key, value);
+                }
+            }
+        }
+    }
+}
+
+/// Rectangle `[x0, y0, x1, y1]` normalisé d'une boîte de page (`MediaBox`,
+/// `CropBox`…), ou `None` si elle est absente ou mal formée.
+pub fn page_box(doc: &Document, page_id: ObjectId, key: &[u8]) -> Option<[f32; 4]> {
+    let obj = inherited_attribute(doc, page_id, key)?;
+    let arr = match obj {
+        Object::Reference(id) => doc.objects.get(&id)?.as_array().ok()?.clone(),
+        Object::Array(arr) => arr,
+        _ => return None,
+    };
+    let values: Vec<f32> = arr
+        .iter()
+        .map(|o| match o {
+            Object::Reference(id) => doc.objects.get(id).and_then(|o| o.as_float().ok()),
+            other => other.as_float().ok(),
+        })
+        .collect::<Option<_>>()?;
+    let [a, b, c, d] = values[..] else { return None };
+    Some([a.min(c), b.min(d), a.max(c), b.max(d)])
+}
+
+/// Boîte visible de la page : `CropBox` si présente, sinon `MediaBox`,
+/// sinon le format Letter par défaut.
+pub fn visible_box(doc: &Document, page_id: ObjectId) -> [f32; 4] {
+    page_box(doc, page_id, b"CropBox")
+        .or_else(|| page_box(doc, page_id, b"MediaBox"))
+        .unwrap_or([0.0, 0.0, 612.0, 792.0])
+}
+
+/// Page de chaque annotation référencée par un `/Annots` : la clé `/P` de
+/// l'annotation est facultative et ne peut servir de référence.
+pub fn annotation_pages(doc: &Document) -> HashMap<ObjectId, ObjectId> {
+    let mut pages = HashMap::new();
+    for page_id in doc.get_pages().into_values() {
+        let annots = doc
+            .get_dictionary(page_id)
+            .ok()
+            .and_then(|p| p.get(b"Annots").ok())
+            .and_then(|a| doc.dereference(a).ok())
+            .and_then(|(_, a)| a.as_array().ok());
+        for annot in annots.into_iter().flatten().filter_map(|r| r.as_reference().ok()) {
+            pages.insert(annot, page_id);
+        }
+    }
+    pages
+}
+
+/// `Rect` d'une annotation, `[x0, y0, x1, y1]` normalisé et arrondi au
+/// centième de point pour les réponses JSON.
+pub fn annotation_rect(doc: &Document, annot: ObjectId) -> Option<[f64; 4]> {
+    let rect = doc.get_dictionary(annot).ok()?.get(b"Rect").ok()?;
+    let values: Vec<f64> = doc
+        .dereference(rect)
+        .ok()?
+        .1
+        .as_array()
+        .ok()?
+        .iter()
+        .map(|v| v.as_float().ok().map(|f| (f64::from(f) * 100.0).round() / 100.0))
+        .collect::<Option<_>>()?;
+    let [x0, y0, x1, y1] = values[..] else {
+        return None;
+    };
+    Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)])
+}
+
+/// Garantit que la page possède son propre dictionnaire `Resources` inline
+/// (copie des ressources héritées ou référencées) et le retourne.
+///
+/// Indispensable avant d'y ajouter une entrée : un dictionnaire partagé entre
+/// plusieurs pages serait modifié pour toutes à la fois.
+pub fn own_resources(doc: &mut Document, page_id: ObjectId) -> Result<&mut Dictionary> {
+    let resources = match inherited_attribute(doc, page_id, b"Resources") {
+        Some(Object::Reference(id)) => doc
+            .objects
+            .get(&id)
+            .and_then(|o| o.as_dict().ok())
+            .cloned()
+            .unwrap_or_default(),
+        Some(Object::Dictionary(dict)) => dict,
+        _ => Dictionary::new(),
+    };
+
+    let page = doc.get_object_mut(page_id)?.as_dict_mut()?;
+    page.set("Resources", Object::Dictionary(resources));
+    Ok(page.get_mut(b"Resources")?.as_dict_mut()?)
+}
+
+/// Ajoute `xobject_id` aux ressources de la page sous un nom libre commençant
+/// par `prefix`, et retourne ce nom.
+pub fn add_page_xobject(
+    doc: &mut Document,
+    page_id: ObjectId,
+    xobject_id: ObjectId,
+    prefix: &str,
+) -> Result<Vec<u8>> {
+    // Le sous-dictionnaire XObject peut lui-même être une référence partagée.
+    let shared = match own_resources(doc, page_id)?.get(b"XObject") {
+        Ok(Object::Reference(id)) => Some(*id),
+        _ => None,
+    };
+    let shared = shared
+        .and_then(|id| doc.objects.get(&id))
+        .and_then(|o| o.as_dict().ok())
+        .cloned();
+
+    let resources = own_resources(doc, page_id)?;
+    if let Some(dict) = shared {
+        resources.set("XObject", Object::Dictionary(dict));
+    }
+    if !matches!(resources.get(b"XObject"), Ok(Object::Dictionary(_))) {
+        resources.set("XObject", Dictionary::new());
+    }
+    let xobjects = resources.get_mut(b"XObject")?.as_dict_mut()?;
+
+    let name = (0..)
+        .map(|i| format!("{}{}", prefix, i).into_bytes())
+        .find(|n| !xobjects.has(n))
+        .unwrap_or_default();
+    xobjects.set(name.clone(), Object::Reference(xobject_id));
+    Ok(name)
+}
+
+/// Entoure le contenu existant de la page par deux nouveaux flux : `before`
+/// est exécuté avant le contenu d'origine, `after` après.
+pub fn wrap_page_content(
+    doc: &mut Document,
+    page_id: ObjectId,
+    before: Vec<u8>,
+    after: Vec<u8>,
+) -> Result<()> {
+    let existing: Vec<Object> = doc
+        .get_page_contents(page_id)
+        .into_iter()
+        .map(Object::Reference)
+        .collect();
+
+    let before_id = doc.add_object(Stream::new(Dictionary::new(), before));
+    let after_id = doc.add_object(Stream::new(Dictionary::new(), after));
+
+    let contents: Vec<Object> = std::iter::once(Object::Reference(before_id))
+        .chain(existing)
+        .chain(std::iter::once(Object::Reference(after_id)))
+        .collect();
+
+    doc.get_object_mut(page_id)?
+        .as_dict_mut()?
+        .set("Contents", Object::Array(contents));
+    Ok(())
+}
+
+/// Convertit une page en Form XObject autonome (contenu concaténé, ressources
+/// héritées, `BBox` = boîte visible) ajouté à `doc`, et retourne son ID.
+/// La rotation d'affichage (`/Rotate`) est portée par la `Matrix` du
+/// formulaire, autour du coin inférieur gauche de la boîte : dessiné, il
+/// apparaît comme la page affichée, avec le même coin inférieur gauche.
+pub fn page_to_form_xobject(doc: &mut Document, page_id: ObjectId) -> Result<ObjectId> {
+    let content = page_content(doc, page_id);
+    let bbox = visible_box(doc, page_id);
+    let resources = inherited_attribute(doc, page_id, b"Resources")
+        .unwrap_or_else(|| Object::Dictionary(Dictionary::new()));
+
+    let mut dict = Dictionary::from_iter(vec![
+        ("Type",      Object::Name(b"XObject".to_vec())),
+        ("Subtype",   Object::Name(b"Form".to_vec())),
+        ("BBox",      Object::Array(bbox.iter().map(|&v| Object::Real(v)).collect())),
+        ("Resources", resources),
+    ]);
+    let rotation = page_rotation(doc, page_id);
+    if rotation != 0 {
+        let [x0, y0, x1, y1] = bbox;
+        let m = multiply(
+            multiply([1.0, 0.0, 0.0, 1.0, -x0, -y0], rotation_matrix(rotation, x1 - x0, y1 - y0)),
+            [1.0, 0.0, 0.0, 1.0, x0, y0],
+        );
+        dict.set("Matrix", Object::Array(m.iter().map(|&v| Object::Real(v)).collect()));
+    }
+    Ok(doc.add_object(Stream::new(dict, content)))
+}
+
+/// Rotation d'affichage de la page (`/Rotate`, éventuellement héritée),
+/// ramenée à 0, 90, 180 ou 270 ; 0 si elle n'est pas un multiple de 90.
+pub fn page_rotation(doc: &Document, page_id: ObjectId) -> i64 {
+    let rotation = inherited_attribute(doc, page_id, b"Rotate")
+        .and_then(|r| r.as_i64().ok())
+        .unwrap_or(0)
+        .rem_euclid(360);
+    if rotation % 90 == 0 { rotation } else { 0 }
+}
+
+/// Rotation horaire de `rotation` degrés (multiple de 90) d'un rectangle
+/// `w × h` posé à l'origine, ramené dans le quadrant positif.
+pub fn rotation_matrix(rotation: i64, w: f32, h: f32) -> Matrix {
+    match rotation {
+        90  => [0.0, -1.0, 1.0, 0.0, 0.0, w],
+        180 => [-1.0, 0.0, 0.0, -1.0, w, h],
+        270 => [0.0, 1.0, -1.0, 0.0, h, 0.0],
+        _   => [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+    }
+}
+
+/// Contenu complet de la page, ses flux décompressés et concaténés.
+pub fn page_content(doc: &Document, page_id: ObjectId) -> Vec<u8> {
+    // Les flux sont séparés par un saut de ligne : concaténés bruts, deux
+    // opérateurs en bordure pourraient fusionner (`Q` + `q` → `Qq`).
+    let mut content = Vec::new();
+    for id in doc.get_page_contents(page_id) {
+        if let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) {
+            content.extend(stream.decompressed_content().unwrap_or_else(|_| stream.content.clone()));
+            content.push(b'\n');
+        }
+    }
+    content
+}
+
+/// Formate un nombre pour un flux de contenu (sans notation exponentielle).
+pub fn fmt_num(value: f32) -> String {
+    let s = format!("{:.4}", value);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".to_owned() } else { s.to_owned() }
+}
+
+// ── Transformations ───────────────────────────────────────────────────────────
+
+/// Matrice de transformation PDF `[a b c d e f]` :
+/// `x' = a·x + c·y + e`, `y' = b·x + d·y + f`.
+pub type Matrix = [f32; 6];
+
+/// Produit `m1 × m2` : applique `m1` puis `m2` (convention PDF de `cm`).
+pub fn multiply(m1: Matrix, m2: Matrix) -> Matrix {
+    [
+        m1[0] * m2[0] + m1[1] * m2[2],
+        m1[0] * m2[1] + m1[1] * m2[3],
+        m1[2] * m2[0] + m1[3] * m2[2],
+        m1[2] * m2[1] + m1[3] * m2[3],
+        m1[4] * m2[0] + m1[5] * m2[2] + m2[4],
+        m1[4] * m2[1] + m1[5] * m2[3] + m2[5],
+    ]
+}
+
+/// Matrice inverse, `None` si `m` n'est pas inversible.
+pub fn invert(m: Matrix) -> Option<Matrix> {
+    let det = m[0] * m[3] - m[1] * m[2];
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let (a, b, c, d) = (m[3] / det, -m[1] / det, -m[2] / det, m[0] / det);
+    Some([a, b, c, d, -(m[4] * a + m[5] * c), -(m[4] * b + m[5] * d)])
+}
+
+pub fn transform_point(m: Matrix, x: f32, y: f32) -> (f32, f32) {
+    (m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5])
+}
+
+/// Transforme un rectangle et retourne la boîte englobante normalisée.
+pub fn transform_rect(m: Matrix, r: [f32; 4]) -> [f32; 4] {
+    let corners = [(r[0], r[1]), (r[2], r[1]), (r[0], r[3]), (r[2], r[3])]
+        .map(|(x, y)| transform_point(m, x, y));
+    let xs = corners.map(|c| c.0);
+    let ys = corners.map(|c| c.1);
+    [
+        xs.iter().copied().fold(f32::INFINITY, f32::min),
+        ys.iter().copied().fold(f32::INFINITY, f32::min),
+        xs.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+        ys.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+    ]
+}
+
+/// Vrai si les rectangles normalisés `a` et `b` se chevauchent (un bord
+/// commun ne suffit pas).
+pub fn intersects(a: [f32; 4], b: [f32; 4]) -> bool {
+    a[0] < b[2] && b[0] < a[2] && a[1] < b[3] && b[1] < a[3]
+}
+
+/// Encode une matrice en opérande de `cm`.
+pub fn matrix_operands(m: Matrix) -> String {
+    m.map(fmt_num).join(" ")
+}
+
+/// Applique `m` au contenu de la page (`q m cm … Q`) et repositionne ses
+/// annotations (`Rect`, `QuadPoints`) pour qu'elles restent alignées.
+pub fn transform_page(doc: &mut Document, page_id: ObjectId, m: Matrix) -> Result<()> {
+    let before = format!("q {} cm\n", matrix_operands(m)).into_bytes();
+    wrap_page_content(doc, page_id, before, b"\nQ\n".to_vec())?;
+
+    // Annotations inline dans le tableau de la page…
+    if let Ok(Object::Array(arr)) = doc.get_dictionary_mut(page_id)?.get_mut(b"Annots") {
+        for annot in arr.iter_mut() {
+            if let Object::Dictionary(dict) = annot {
+                transform_annotation(dict, m);
+            }
+        }
+    }
+
+    // … ou, cas le plus courant, objets indirects.
+    let annots = doc.get_dictionary(page_id)?.get(b"Annots").ok().cloned();
+    let annot_ids: Vec<ObjectId> = match annots {
+        Some(Object::Array(arr)) => arr.iter().filter_map(|o| o.as_reference().ok()).collect(),
+        Some(Object::Reference(id)) => doc
+            .objects
+            .get(&id)
+            .and_then(|o| o.as_array().ok())
+            .map(|arr| arr.iter().filter_map(|o| o.as_reference().ok()).collect())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    for id in annot_ids {
+        if let Some(Object::Dictionary(dict)) = doc.objects.get_mut(&id) {
+            transform_annotation(dict, m);
+        }
+    }
+    Ok(())
+}
+
+fn transform_annotation(annot: &mut Dictionary, m: Matrix) {
+    let rect = annot.get(b"Rect").and_then(Object::as_array).ok().and_then(|a| {
+        let v: Vec<f32> = a.iter().filter_map(|o| o.as_float().ok()).collect();
+        <[f32; 4]>::try_from(v).ok()
+    });
+    if let Some(rect) = rect {
+        let r = transform_rect(m, rect);
+        annot.set("Rect", Object::Array(r.iter().map(|&v| Object::Real(v)).collect()));
+    }
+    let quads = annot.get(b"QuadPoints").and_then(Object::as_array).ok().map(|a| {
+        a.iter().filter_map(|o| o.as_float().ok()).collect::<Vec<f32>>()
+    });
+    if let Some(q) = quads {
+        let points: Vec<Object> = q
+            .chunks_exact(2)
+            .flat_map(|p| {
+                let (x, y) = transform_point(m, p[0], p[1]);
+                [Object::Real(x), Object::Real(y)]
+            })
+            .collect();
+        annot.set("QuadPoints", Object::Array(points));
+    }
+}
+
 // ── Construction de document ──────────────────────────────────────────────────
 
 /// Copie tous les objets de `src` vers `dst` en excluant ceux dans `exclude`.
@@ -171,6 +750,21 @@ pub fn insert_pages_node(doc: &mut Document, kids: &[ObjectId], count: i64) -> O
     pages_id
 }
 
+/// Insère une page vierge de dimensions `media_box` dans `doc` et retourne son
+/// ID. Le `Parent` est posé ensuite par `set_parent`, comme pour les autres pages.
+pub fn insert_blank_page(doc: &mut Document, media_box: [f32; 4]) -> ObjectId {
+    let page_id = alloc_id(doc);
+    doc.objects.insert(
+        page_id,
+        Object::Dictionary(Dictionary::from_iter(vec![
+            ("Type",      Object::Name(b"Page".to_vec())),
+            ("MediaBox",  Object::Array(media_box.iter().map(|&v| Object::Real(v)).collect())),
+            ("Resources", Object::Dictionary(Dictionary::new())),
+        ])),
+    );
+    page_id
+}
+
 /// Insère un Catalog dans `doc` pointant vers `pages_id` et retourne son ID.
 pub fn insert_catalog(doc: &mut Document, pages_id: ObjectId) -> ObjectId {
     let catalog_id = alloc_id(doc);
@@ -184,13 +778,82 @@ pub fn insert_catalog(doc: &mut Document, pages_id: ObjectId) -> ObjectId {
     catalog_id
 }
 
-/// Finalise le trailer et sérialise le document.
-pub fn finalize(doc: &mut Document, catalog_id: ObjectId) -> Result<Vec<u8>> {
+/// Finalise le trailer et sérialise le document, chiffré si `encryption`
+/// est fourni.
+pub fn finalize(
+    doc: &mut Document,
+    catalog_id: ObjectId,
+    encryption: Option<&Encryption>,
+) -> Result<Vec<u8>> {
     doc.trailer.set("Root", Object::Reference(catalog_id));
+    // Politique du serveur : appliquée à toutes les sorties, avant le chiffrement.
+    match active_content::policy() {
+        ActiveContentPolicy::Keep => {}
+        ActiveContentPolicy::Strip => {
+            strip_active_content(doc, catalog_id, false)?;
+        }
+        ActiveContentPolicy::StripWithUris => {
+            strip_active_content(doc, catalog_id, true)?;
+        }
+    }
+    if let Some(encryption) = encryption {
+        apply_encryption(doc, encryption)?;
+    }
+    // Après le chiffrement, qui ajoute le dictionnaire /Encrypt
     doc.trailer.set("Size", Object::Integer((doc.max_id + 1) as i64));
     save_to_bytes(doc)
 }
 
+/// Reconstruit un document propre à partir d'un document modifié en place.
+/// Nécessaire car lopdf ne garantit pas un PDF valide après modification directe.
+/// Le document est chiffré si `encryption` est fourni (voir [`finalize`]).
+pub fn rebuild(src: Document, encryption: Option<&Encryption>) -> Result<Vec<u8>> {
+    let mut out = Document::with_version("1.5");
+
+    let catalog = find_catalog(&src);
+    let pages_root = find_pages_root(&src);
+
+    // N'exclure que le Catalog : le nœud Pages racine est copié pour
+    // devenir l'enfant du nouveau nœud racine.
+    let excluded: HashSet<ObjectId> = catalog.into_iter().collect();
+
+    let id_map = copy_objects(&src, &mut out, &excluded);
+
+    // Le nœud Pages source (avec ses Kids et sa hiérarchie intacte) devient
+    // l'unique enfant du nouveau nœud Pages racine.
+    if let Some(old_root) = pages_root {
+        let new_root = id_map[&old_root];
+
+        let count = src
+            .objects
+            .get(&old_root)
+            .and_then(|o| o.as_dict().ok())
+            .and_then(|d| d.get(b"Count").ok())
+            .and_then(|c| c.as_i64().ok())
+            .unwrap_or(0);
+
+        let pages_id = insert_pages_node(&mut out, &[new_root], count);
+        set_parent(&mut out, &[new_root], pages_id);
+
+        let catalog_id = insert_catalog(&mut out, pages_id);
+
+        // Le document n'a pas changé de structure : signets, libellés de
+        // pages, formulaires… restent valides et sont conservés.
+        if let Some(old) = catalog.and_then(|id| src.objects.get(&id)).and_then(|o| o.as_dict().ok()) {
+            if let Some(Object::Dictionary(new)) = out.objects.get_mut(&catalog_id) {
+                for (key, value) in old.iter() {
+                    if key != b"Type" && key != b"Pages" {
+                        new.set(key.clone(), remap_object(value.clone(), &id_map));
+                    }
+                }
+            }
+        }
+        return finalize(&mut out, catalog_id, encryption);
+    }
+
+    Err(PdfError::Lopdf(lopdf::Error::DictKey("Pages root introuvable".to_owned())))
+}
+
 fn alloc_id(doc: &mut Document) -> ObjectId {
     doc.max_id += 1;
     (doc.max_id, 0)
@@ -242,3 +905,43 @@ fn parse_page_number(s: &str, context: &str) -> Result<u32> {
         .parse()
         .map_err(|_| PdfError::InvalidPageNumber(context.to_owned()))
 }
+
+/// Échappe les caractères spéciaux XML et retire les caractères de
+/// contrôle, interdits en XML 1.0.
+pub fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c < ' ' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Inverse de [`parse_page_ranges`] : `[1, 3, 5, 6, 7, 10]` → `"1,3,5-7,10"`.
+/// Les pages sont triées et dédoublonnées.
+pub fn format_page_ranges(pages: &[u32]) -> String {
+    let mut sorted = pages.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut parts: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < sorted.len() {
+        let start = sorted[i];
+        while i + 1 < sorted.len() && sorted[i + 1] == sorted[i] + 1 {
+            i += 1;
+        }
+        parts.push(if sorted[i] == start { start.to_string() } else { format!("{}-{}", start, sorted[i]) });
+        i += 1;
+    }
+    parts.join(",")
+}
+